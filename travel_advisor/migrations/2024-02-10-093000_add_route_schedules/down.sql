@@ -0,0 +1 @@
+DROP TABLE route_schedules;
\ No newline at end of file
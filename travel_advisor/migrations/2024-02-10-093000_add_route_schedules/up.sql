@@ -0,0 +1,9 @@
+CREATE TABLE route_schedules (
+    id               BIGINT   NOT NULL AUTO_INCREMENT,
+    route_id         BIGINT   NOT NULL,
+    departure_time   TIME     NOT NULL,
+    duration_minutes INT      NOT NULL,
+    days_of_week     TINYINT UNSIGNED NOT NULL DEFAULT 127,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_schedule_route FOREIGN KEY (route_id) REFERENCES routes(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+DROP TABLE airport_transfers;
\ No newline at end of file
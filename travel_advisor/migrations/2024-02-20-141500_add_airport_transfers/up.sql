@@ -0,0 +1,11 @@
+CREATE TABLE airport_transfers (
+    id               BIGINT NOT NULL AUTO_INCREMENT,
+    from_airport_id  BIGINT NOT NULL,
+    to_airport_id    BIGINT NOT NULL,
+    price            BIGINT NOT NULL,
+    duration_minutes INT    NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_transfer_airports UNIQUE (from_airport_id, to_airport_id),
+    CONSTRAINT fk_transfer_from_airport FOREIGN KEY (from_airport_id) REFERENCES airports(id) ON DELETE CASCADE,
+    CONSTRAINT fk_transfer_to_airport   FOREIGN KEY (to_airport_id)   REFERENCES airports(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE airports DROP latitude, DROP longitude;
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE airports
+    ADD latitude  DOUBLE NULL,
+    ADD longitude DOUBLE NULL;
@@ -0,0 +1,5 @@
+ALTER TABLE airports
+    DROP INDEX uq_airport_iata_code,
+    DROP INDEX uq_airport_icao_code,
+    DROP iata_code,
+    DROP icao_code;
@@ -0,0 +1,5 @@
+ALTER TABLE airports
+    ADD iata_code CHAR(3) NULL,
+    ADD icao_code CHAR(4) NULL,
+    ADD CONSTRAINT uq_airport_iata_code UNIQUE (iata_code),
+    ADD CONSTRAINT uq_airport_icao_code UNIQUE (icao_code);
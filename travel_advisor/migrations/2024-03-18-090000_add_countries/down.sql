@@ -0,0 +1,9 @@
+ALTER TABLE cities
+    DROP FOREIGN KEY fk_city_country,
+    DROP FOREIGN KEY fk_city_region,
+    DROP country_code,
+    DROP region_id;
+
+DROP TABLE regions;
+
+DROP TABLE countries;
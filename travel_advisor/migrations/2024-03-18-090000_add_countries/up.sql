@@ -0,0 +1,20 @@
+CREATE TABLE countries (
+    code   CHAR(2)     NOT NULL,
+    `name` VARCHAR(60) NOT NULL,
+    PRIMARY KEY (code)
+);
+
+CREATE TABLE regions (
+    id           BIGINT      NOT NULL AUTO_INCREMENT,
+    country_code CHAR(2)     NOT NULL,
+    `name`       VARCHAR(60) NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT uq_region_name UNIQUE (country_code, `name`),
+    CONSTRAINT fk_region_country FOREIGN KEY (country_code) REFERENCES countries(code)
+);
+
+ALTER TABLE cities
+    ADD country_code CHAR(2) NULL,
+    ADD region_id    BIGINT  NULL,
+    ADD CONSTRAINT fk_city_country FOREIGN KEY (country_code) REFERENCES countries(code),
+    ADD CONSTRAINT fk_city_region  FOREIGN KEY (region_id)    REFERENCES regions(id);
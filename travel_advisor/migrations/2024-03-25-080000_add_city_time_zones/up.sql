@@ -0,0 +1 @@
+ALTER TABLE cities ADD time_zone VARCHAR(64) NULL;
@@ -0,0 +1,7 @@
+ALTER TABLE routes
+    DROP FOREIGN KEY fk_start_airport,
+    DROP FOREIGN KEY fk_finish_airport;
+
+ALTER TABLE routes
+    ADD CONSTRAINT fk_start_city  FOREIGN KEY (start)  REFERENCES cities(id),
+    ADD CONSTRAINT fk_finish_city FOREIGN KEY (finish) REFERENCES cities(id);
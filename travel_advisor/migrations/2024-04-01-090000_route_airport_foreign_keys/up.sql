@@ -0,0 +1,7 @@
+ALTER TABLE routes
+    DROP FOREIGN KEY fk_start_city,
+    DROP FOREIGN KEY fk_finish_city;
+
+ALTER TABLE routes
+    ADD CONSTRAINT fk_start_airport  FOREIGN KEY (start)  REFERENCES airports(id),
+    ADD CONSTRAINT fk_finish_airport FOREIGN KEY (finish) REFERENCES airports(id);
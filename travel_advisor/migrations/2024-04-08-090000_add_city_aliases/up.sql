@@ -0,0 +1,10 @@
+CREATE TABLE city_aliases (
+    id       BIGINT      NOT NULL AUTO_INCREMENT,
+    city_id  BIGINT      NOT NULL,
+    `name`   VARCHAR(30) NOT NULL,
+    language VARCHAR(35) NOT NULL,
+    PRIMARY KEY (id),
+    INDEX ix_city_alias_name (`name`),
+    CONSTRAINT uq_city_alias UNIQUE (city_id, `name`, language),
+    CONSTRAINT fk_alias_city FOREIGN KEY (city_id) REFERENCES cities(id) ON DELETE CASCADE
+);
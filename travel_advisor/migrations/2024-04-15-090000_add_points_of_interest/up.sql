@@ -0,0 +1,12 @@
+CREATE TABLE points_of_interest (
+    id          BIGINT       NOT NULL AUTO_INCREMENT,
+    city_id     BIGINT       NOT NULL,
+    `name`      VARCHAR(100) NOT NULL,
+    category    VARCHAR(20)  NOT NULL,
+    description TEXT         NOT NULL,
+    latitude    DOUBLE       NULL,
+    longitude   DOUBLE       NULL,
+    PRIMARY KEY (id),
+    INDEX ix_poi_city_category (city_id, category),
+    CONSTRAINT fk_poi_city FOREIGN KEY (city_id) REFERENCES cities(id) ON DELETE CASCADE
+);
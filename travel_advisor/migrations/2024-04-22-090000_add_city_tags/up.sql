@@ -0,0 +1,7 @@
+CREATE TABLE city_tags (
+    city_id BIGINT      NOT NULL,
+    tag     VARCHAR(30) NOT NULL,
+    PRIMARY KEY (city_id, tag),
+    INDEX ix_city_tag (tag),
+    CONSTRAINT fk_tag_city FOREIGN KEY (city_id) REFERENCES cities(id) ON DELETE CASCADE
+);
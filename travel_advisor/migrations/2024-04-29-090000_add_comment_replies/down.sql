@@ -0,0 +1,4 @@
+ALTER TABLE comments
+    DROP FOREIGN KEY fk_comment_parent,
+    DROP COLUMN parent_id,
+    DROP COLUMN deleted;
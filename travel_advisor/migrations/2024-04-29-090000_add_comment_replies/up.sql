@@ -0,0 +1,4 @@
+ALTER TABLE comments
+    ADD parent_id BIGINT  NULL,
+    ADD deleted   BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD CONSTRAINT fk_comment_parent FOREIGN KEY (parent_id) REFERENCES comments(id) ON DELETE CASCADE;
@@ -0,0 +1,4 @@
+DROP TABLE moderation_records;
+DROP TABLE comment_flags;
+ALTER TABLE comments
+    DROP COLUMN hidden;
@@ -0,0 +1,29 @@
+ALTER TABLE comments
+    ADD hidden BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE comment_flags (
+    id         BIGINT       NOT NULL AUTO_INCREMENT,
+    comment_id BIGINT       NOT NULL,
+    user_id    BIGINT       NOT NULL,
+    reason     VARCHAR(250) NOT NULL,
+    resolved   BOOLEAN      NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP(),
+    PRIMARY KEY (id),
+    CONSTRAINT uq_comment_flag UNIQUE (comment_id, user_id),
+    CONSTRAINT fk_flag_comment FOREIGN KEY (comment_id) REFERENCES comments(id) ON DELETE CASCADE,
+    CONSTRAINT fk_flag_user FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+-- no foreign key to comments, records outlive the comments they are about
+CREATE TABLE moderation_records (
+    id              BIGINT       NOT NULL AUTO_INCREMENT,
+    comment_id      BIGINT       NOT NULL,
+    comment_user_id BIGINT       NOT NULL,
+    comment_text    VARCHAR(250) NOT NULL,
+    moderator_id    BIGINT       NOT NULL,
+    `action`        VARCHAR(10)  NOT NULL,
+    created_at      TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP(),
+    PRIMARY KEY (id),
+    INDEX ix_moderation_comment (comment_id),
+    CONSTRAINT fk_moderation_moderator FOREIGN KEY (moderator_id) REFERENCES users(id)
+);
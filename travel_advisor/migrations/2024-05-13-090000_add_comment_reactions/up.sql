@@ -0,0 +1,8 @@
+CREATE TABLE comment_reactions (
+    comment_id BIGINT     NOT NULL,
+    user_id    BIGINT     NOT NULL,
+    kind       VARCHAR(4) NOT NULL,
+    PRIMARY KEY (comment_id, user_id),
+    CONSTRAINT fk_reaction_comment FOREIGN KEY (comment_id) REFERENCES comments(id) ON DELETE CASCADE,
+    CONSTRAINT fk_reaction_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,9 @@
+-- previous versions of comment text, written_at is when that version was posted or last edited
+CREATE TABLE comment_revisions (
+    id         BIGINT       NOT NULL AUTO_INCREMENT,
+    comment_id BIGINT       NOT NULL,
+    `text`     VARCHAR(250) NOT NULL,
+    written_at TIMESTAMP    NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_revision_comment FOREIGN KEY (comment_id) REFERENCES comments(id) ON DELETE CASCADE
+);
@@ -16,9 +16,15 @@ use actix_web::{
 use test_annotations::roles;
 
 use crate::{
+    model::{
+        AirportRef,
+        Coordinates,
+        Transfer,
+    },
     services::traits::{
         AirportService,
         AuthService,
+        RouteService,
     },
     util::Error,
 };
@@ -26,22 +32,35 @@ use super::{
     get_user_if_has_roles,
     dtos::{
         FromModel,
-        ToModel,
         AirportDto,
         CreateAirportDto,
+        NearbyAirportDto,
+        NearbyQueryParam,
+        SaveTransferDto,
+        TransferDto,
     },
     validations::get_number,
 };
 
+const DEFAULT_NEARBY_RADIUS_KM: f64 = 100.0;
+const MAX_NEARBY_RADIUS_KM: f64 = 1000.0;
+const DEFAULT_NEARBY_LIMIT: usize = 10;
+const MAX_NEARBY_LIMIT: usize = 50;
+
 pub(super) fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/v1/airports")
         .service(get_airports)
+        .service(get_nearby_airports)
+        .service(get_airport_by_code)
         .service(get_airport_by_id)
         .service(create_airpot)
         .service(update_airpot)
         .service(delete_airpot)
         .service(upload_airpots)
+        .service(get_transfers)
+        .service(save_transfer)
+        .service(delete_transfer)
     );
 }
 
@@ -59,13 +78,72 @@ async fn get_airports(
     Ok(web::Json(result))
 }
 
+#[get("/nearby")]
+async fn get_nearby_airports(
+    query: web::Query<NearbyQueryParam>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+) -> Result<web::Json<Vec<NearbyAirportDto>>, Error> {
+    // check params
+    let center = Coordinates {
+        latitude: parse_decimal("lat", &query.lat)?,
+        longitude: parse_decimal("lon", &query.lon)?,
+    };
+    if !center.is_valid() {
+        return Err(Error::bad_request("lat must be within [-90, 90] and lon within [-180, 180]".to_string()));
+    }
+    let radius_km = match &query.radius_km {
+        Some(radius) => parse_decimal("radius_km", radius)?,
+        None => DEFAULT_NEARBY_RADIUS_KM,
+    };
+    if radius_km <= 0.0 || radius_km > MAX_NEARBY_RADIUS_KM {
+        return Err(Error::bad_request(format!("radius_km must be greater than 0 and at most {}", MAX_NEARBY_RADIUS_KM)));
+    }
+    let limit = match &query.limit {
+        Some(limit) => get_number!(limit, usize, true),
+        None => DEFAULT_NEARBY_LIMIT,
+    };
+    if limit > MAX_NEARBY_LIMIT {
+        return Err(Error::bad_request(format!("limit must be at most {}", MAX_NEARBY_LIMIT)));
+    }
+    // look airports up
+    let nearby = airport_service.find_nearby(center, radius_km * 1000.0, limit)?;
+    Ok(web::Json(nearby.iter().map(NearbyAirportDto::from_model).collect()))
+}
+
+#[get("/by-code/{code}")]
+async fn get_airport_by_code(
+    code: web::Path<String>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+) -> Result<web::Json<AirportDto>, Error> {
+    match airport_service.get_by_code(&code)? {
+        Some(airport) => Ok(web::Json(AirportDto::from_model(&airport))),
+        None => Err(Error::not_found("airport not found".to_string())),
+    }
+}
+
+/// Reads an airport given in the path either by ID or by IATA or ICAO code
+fn airport_id_of(value: &str, airport_service: &Arc<dyn AirportService + Send + Sync>) -> Result<i64, Error> {
+    match AirportRef::parse(value) {
+        Some(AirportRef::Id(id)) if id <= 0 => Err(Error::bad_request("must be a positive number".to_string())),
+        Some(reference) => airport_service.resolve(&reference),
+        None => Err(Error::bad_request(format!("{} is neither an airport ID nor an IATA or ICAO code", value))),
+    }
+}
+
+fn parse_decimal(name: &str, value: &str) -> Result<f64, Error> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(Error::bad_request(format!("{} must be a decimal number", name))),
+    }
+}
+
 #[get("/{id}")]
 async fn get_airport_by_id(
     id: web::Path<String>,
     airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
 ) -> Result<web::Json<AirportDto>, Error> {
     // check param
-    let id = get_number!(id, i64, true);
+    let id = airport_id_of(&id, &airport_service)?;
     // load airport
     match airport_service.into_inner().get_by_id(id) {
         Ok(airport) => match airport {
@@ -81,6 +159,7 @@ async fn create_airpot(
     req: HttpRequest,
     payload: web::Bytes,
     airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: Data<Arc<dyn RouteService + Send + Sync>>,
     auth_service: Data<Arc<dyn AuthService + Send + Sync>>
 ) -> Result<web::Json<AirportDto>, Error> {
     // validate access right
@@ -90,12 +169,11 @@ async fn create_airpot(
         Ok(v) => v,
         Err(err) => return Err(Error::bad_request(format!("incorrect payload: {}", err.to_string()))),
     };
-    let airport = dto.to_model();
+    let airport = dto.to_model()?;
     // save new airport
-    match airport_service.into_inner().create(airport) {
-        Ok(final_airport) => Ok(web::Json(AirportDto::from_model(&final_airport))),
-        Err(err) => Err(err),
-    }
+    let final_airport = airport_service.into_inner().create(airport)?;
+    route_service.refresh_graph();
+    Ok(web::Json(AirportDto::from_model(&final_airport)))
 }
 
 #[put("/{id}")]
@@ -104,27 +182,27 @@ async fn update_airpot(
     payload: web::Bytes,
     id: web::Path<String>,
     airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: Data<Arc<dyn RouteService + Send + Sync>>,
     auth_service: Data<Arc<dyn AuthService + Send + Sync>>
 ) -> Result<impl Responder, Error> {
     // validate access right
     get_user_if_has_roles!(req, auth_service, vec!["admin"]);
     // get id
-    let airport_id = get_number!(id, i64);
+    let airport_id = airport_id_of(&id, &airport_service)?;
     // deserialize
     let dto: CreateAirportDto = match serde_json::from_slice(payload.to_vec().as_slice()) {
         Ok(v) => v,
         Err(err) => return Err(Error::bad_request(format!("incorrect payload: {}", err.to_string()))),
     };
     let airport = {
-        let mut airport_mut = dto.to_model();
+        let mut airport_mut = dto.to_model()?;
         airport_mut.id = airport_id;
         airport_mut
     };
     // save new airport
-    match airport_service.into_inner().update(airport) {
-        Ok(()) => Ok(HttpResponse::Created().finish()),
-        Err(err) => Err(err),
-    }
+    airport_service.into_inner().update(airport)?;
+    route_service.refresh_graph();
+    Ok(HttpResponse::Created().finish())
 }
 
 #[delete("/{id}")]
@@ -132,16 +210,16 @@ async fn delete_airpot(
     req: HttpRequest,
     id: web::Path<String>,
     airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: Data<Arc<dyn RouteService + Send + Sync>>,
     auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
 ) -> Result<impl Responder, Error> {
     get_user_if_has_roles!(req, auth_service, vec!["admin"]);
     // check param
-    let id = get_number!(id, i64, true);
+    let id = airport_id_of(&id, &airport_service)?;
     // delete airport
-    match airport_service.into_inner().delete(id) {
-        Ok(()) => Ok(HttpResponse::Ok().finish()),
-        Err(err) => Err(err),
-    }
+    airport_service.into_inner().delete(id)?;
+    route_service.refresh_graph();
+    Ok(HttpResponse::Ok().finish())
 }
 
 #[post("/upload")]
@@ -149,13 +227,73 @@ async fn delete_airpot(
 async fn upload_airpots(
     payload: web::Bytes,
     airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: Data<Arc<dyn RouteService + Send + Sync>>,
 ) -> Result<impl Responder, Error> {
     // validate access right
     // THIS IS NOT NEEDED !!! Proc macro "roles" does all we need
     // get_user_if_has_roles!(req, auth_service, vec!["admin"]);
     // save airports
-    match airport_service.into_inner().save_airports(payload.to_vec().as_slice()) {
+    let result = airport_service.into_inner().save_airports(payload.to_vec().as_slice());
+    // rows before a failing one are saved as well
+    route_service.refresh_graph();
+    match result {
         Ok(()) => Ok(HttpResponse::Ok().finish()),
         Err(err) => Err(err.wrap_str("failed to save all airports")),
     }
 }
+
+#[get("/{id}/transfers")]
+async fn get_transfers(
+    id: web::Path<String>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+) -> Result<web::Json<Vec<TransferDto>>, Error> {
+    let id = airport_id_of(&id, &airport_service)?;
+    match airport_service.into_inner().get_transfers(id) {
+        Ok(transfers) => Ok(web::Json(transfers.iter().map(TransferDto::from_model).collect())),
+        Err(err) => Err(err),
+    }
+}
+
+/// Sets price and duration of getting from one airport to another airport of the same city
+#[put("/{id}/transfers/{to_id}")]
+async fn save_transfer(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Json<SaveTransferDto>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: Data<Arc<dyn RouteService + Send + Sync>>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+) -> Result<web::Json<TransferDto>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let from_id = airport_id_of(&path.0, &airport_service)?;
+    let to_id = airport_id_of(&path.1, &airport_service)?;
+    if body.price < 0 || body.duration_minutes < 0 {
+        return Err(Error::bad_request("price and duration must not be negative".to_string()));
+    }
+    let transfer = Transfer {
+        id: 0,
+        from_airport_id: from_id,
+        to_airport_id: to_id,
+        price: body.price,
+        duration_minutes: body.duration_minutes,
+    };
+    let transfer = airport_service.into_inner().save_transfer(transfer)?;
+    route_service.refresh_graph();
+    Ok(web::Json(TransferDto::from_model(&transfer)))
+}
+
+#[delete("/{id}/transfers/{to_id}")]
+async fn delete_transfer(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: Data<Arc<dyn RouteService + Send + Sync>>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let from_id = airport_id_of(&path.0, &airport_service)?;
+    let to_id = airport_id_of(&path.1, &airport_service)?;
+    airport_service.into_inner().delete_transfer(from_id, to_id)?;
+    route_service.refresh_graph();
+    Ok(HttpResponse::Ok().finish())
+}
@@ -1,8 +1,10 @@
 use std::sync::Arc;
 
 use actix_web::{
+    delete,
     get,
     post,
+    put,
     web::{
         self,
         Data,
@@ -13,35 +15,68 @@ use actix_web::{
 };
 
 use crate::{
+    AirportService,
     AuthService,
     CityService,
+    RouteService,
+    services::traits::PoiService,
     util::Error,
-    
+
 };
 use super::{
     get_user_if_has_roles,
     dtos::{
         FromModel,
+        CityAliasDto,
         CityDto,
+        CityListQueryParam,
+        CityQueryParam,
+        CreateCityAliasDto,
+        PoiDto,
+        SetCityCountryDto,
+        SetCityTimeZoneDto,
+        UpdateCityDto,
+        DeleteCityQueryParam,
+        tag_of,
     },
+    headers::accepted_languages,
     validations::get_number,
 };
 
 pub(super) fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(get_cities)
         .service(get_city_by_id)
-        .service(upload_cities);
+        .service(upload_cities)
+        .service(update_city)
+        .service(delete_city)
+        .service(set_city_country)
+        .service(set_city_time_zone)
+        .service(get_city_aliases)
+        .service(add_city_alias)
+        .service(delete_city_alias)
+        .service(get_tags)
+        .service(add_city_tag)
+        .service(delete_city_tag);
 }
 
+/// Lists cities, optionally only those with all or any of the given tags
 #[get("/v1/cities")]
 async fn get_cities(
+    req: HttpRequest,
+    query: web::Query<CityListQueryParam>,
     city_service: Data<Arc<dyn CityService + Send + Sync>>,
 ) -> Result<web::Json<Vec<CityDto>>, Error> {
+    let (tags, tag_match) = query.to_model()?;
     // load cities
-    let result = match city_service.into_inner().get_all() {
+    let cities = match tags.is_empty() {
+        true => city_service.get_all(),
+        false => city_service.find_by_tags(tags, tag_match),
+    };
+    let mut result = match cities {
         Ok(cities) => cities,
         Err(err) => return Err(err),
     };
+    city_service.localize(&mut result, &accepted_languages(&req))?;
     // convert to DTOs
     let dtos: Vec<CityDto> = result.iter()
         .map(|c| CityDto::from_model(c))
@@ -52,23 +87,73 @@ async fn get_cities(
 
 #[get("/v1/cities/{id}")]
 async fn  get_city_by_id(
+    req: HttpRequest,
     id: web::Path<String>,
+    query: web::Query<CityQueryParam>,
     city_service: Data<Arc<dyn CityService + Send + Sync>>,
+    poi_service: Data<Arc<dyn PoiService + Send + Sync>>,
 ) -> Result<web::Json<CityDto>, Error> {
     // get id
     let city_id = get_number!(id, i64, true);
+    let embed_pois = query.embeds_pois()?;
     // load city
-    let city = match city_service.into_inner().get_full(city_id) {
+    let mut city = match city_service.into_inner().get_full(city_id) {
         Ok(city) => match city {
-            Some(city) => CityDto::from_model(&city),
+            Some(mut city) => {
+                // aliases come with the full city
+                city.name = city.localized_name(&accepted_languages(&req)).to_string();
+                CityDto::from_model(&city)
+            },
             None => return Err(Error::not_found("city not found".to_string())),
         },
         Err(err) => return Err(err),
     };
+    if embed_pois {
+        let pois = poi_service.list_for_city(city_id, vec![])?;
+        city.pois = Some(pois.iter().map(PoiDto::from_model).collect());
+    }
 
     Ok(web::Json(city))
 }
 
+#[put("/v1/cities/{id}")]
+async fn update_city(
+    req: HttpRequest,
+    id: web::Path<String>,
+    body: web::Json<UpdateCityDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(id, i64, true);
+    let city = body.to_model(city_id)?;
+    city_service.update(city)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Deletes the city. Airports, their routes, comments and points of interest of the city block deletion
+/// unless `policy` is `cascade` or `reassign`, in which case they are deleted or moved.
+#[delete("/v1/cities/{id}")]
+async fn delete_city(
+    req: HttpRequest,
+    id: web::Path<String>,
+    query: web::Query<DeleteCityQueryParam>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: Data<Arc<dyn RouteService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(id, i64, true);
+    let policy = query.to_model()?;
+    city_service.delete(city_id, policy)?;
+    airport_service.refresh_index();
+    route_service.refresh_graph();
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Saves cities from CSV rows of name, country code and IANA time zone, with a header row.
+/// Country code and time zone are optional and may be left empty or out.
 #[post("/v1/cities")]
 //#[roles("admin")]
 async fn upload_cities(
@@ -82,4 +167,110 @@ async fn upload_cities(
         Ok(()) => Ok(HttpResponse::Created().body("saved all cities")),
         Err(err) => Err(err),
     }
-}
\ No newline at end of file
+}
+
+/// Links the city to a country and optionally one of its regions, or unlinks it when no country is given
+#[put("/v1/cities/{id}/country")]
+async fn set_city_country(
+    req: HttpRequest,
+    id: web::Path<String>,
+    body: web::Json<SetCityCountryDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(id, i64, true);
+    let country_code = body.country_code.as_ref().map(|c| c.to_ascii_uppercase());
+    city_service.set_country(city_id, country_code, body.region_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Sets the IANA time zone of the city used to render local times, or clears it
+#[put("/v1/cities/{id}/time-zone")]
+async fn set_city_time_zone(
+    req: HttpRequest,
+    id: web::Path<String>,
+    body: web::Json<SetCityTimeZoneDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(id, i64, true);
+    let time_zone = body.to_model()?;
+    city_service.set_time_zone(city_id, time_zone)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/v1/cities/{id}/aliases")]
+async fn get_city_aliases(
+    id: web::Path<String>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<Vec<CityAliasDto>>, Error> {
+    let city_id = get_number!(id, i64, true);
+    let aliases = city_service.get_aliases(city_id)?;
+    Ok(web::Json(aliases.iter().map(CityAliasDto::from_model).collect()))
+}
+
+#[post("/v1/cities/{id}/aliases")]
+async fn add_city_alias(
+    req: HttpRequest,
+    id: web::Path<String>,
+    body: web::Json<CreateCityAliasDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(id, i64, true);
+    let alias = city_service.add_alias(body.to_model(city_id)?)?;
+    Ok(HttpResponse::Created().json(CityAliasDto::from_model(&alias)))
+}
+
+#[delete("/v1/cities/{id}/aliases/{alias_id}")]
+async fn delete_city_alias(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(path.0, i64, true);
+    let alias_id = get_number!(path.1, i64, true);
+    city_service.delete_alias(city_id, alias_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Lists every tag on at least one city
+#[get("/v1/tags")]
+async fn get_tags(
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<Vec<String>>, Error> {
+    Ok(web::Json(city_service.all_tags()?))
+}
+
+#[put("/v1/cities/{id}/tags/{tag}")]
+async fn add_city_tag(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(path.0, i64, true);
+    let tag = tag_of(&path.1)?;
+    city_service.add_tag(city_id, tag)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/v1/cities/{id}/tags/{tag}")]
+async fn delete_city_tag(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(path.0, i64, true);
+    let tag = tag_of(&path.1)?;
+    city_service.delete_tag(city_id, tag)?;
+    Ok(HttpResponse::Ok().finish())
+}
@@ -13,11 +13,16 @@ use actix_web::{
     HttpRequest,
     HttpResponse,
 };
+use chrono_tz::Tz;
 
 use crate::{
     AuthService,
+    CityService,
     CommentService,
-    model::Comment,
+    model::{
+        Comment,
+        CommentThread,
+    },
     util::{
         Error,
         ErrorCode,
@@ -25,7 +30,15 @@ use crate::{
 };
 use super::{
     get_user_if_has_roles,
-    dtos::CommentDto,
+    dtos::{
+        FromModel,
+        CityCommentsQueryParam,
+        CommentDto,
+        CommentRevisionDto,
+        ReactionCountsDto,
+        ReactionDto,
+        SaveCommentDto,
+    },
     validations::get_number,
 };
 
@@ -39,6 +52,10 @@ pub(super) fn init(cfg: &mut web::ServiceConfig) {
                     .service(save_comment)
             ).service(
                 web::scope("/comments")
+                    .service(get_replies)
+                    .service(get_history)
+                    .service(set_reaction)
+                    .service(delete_reaction)
                     .service(update_comment)
                     .service(delete_comment)
             )
@@ -49,53 +66,131 @@ pub(super) fn init(cfg: &mut web::ServiceConfig) {
 pub async fn get_comments_for_user(
     id: web::Path<String>,
     comment_service: Data<Arc<dyn CommentService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
 ) -> Result<web::Json<Vec<CommentDto>>, Error> {
     // check param
     let id = get_number!(id, i64, true);
-    handle_comment_vec(comment_service.into_inner().list_for_user(id))
+    handle_comment_vec(comment_service.into_inner().list_for_user(id), city_service.get_ref())
 }
 
+/// Lists comments of the city, either all in one list or as threads with `view=nested`.
+/// With `sort=helpful` the best rated comments, and in the nested view the best rated replies, come first.
 #[get("/{id}/comments")]
 pub async fn get_comments_for_city(
     id: web::Path<String>,
+    query: web::Query<CityCommentsQueryParam>,
     comment_service: Data<Arc<dyn CommentService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
 ) -> Result<web::Json<Vec<CommentDto>>, Error> {
     // check param
     let id = get_number!(id, i64, true);
-    handle_comment_vec(comment_service.into_inner().list_for_city(id))
+    let sort = query.to_sort()?;
+    if !query.is_nested()? {
+        return handle_comment_vec(comment_service.into_inner().list_for_city(id, sort), city_service.get_ref());
+    }
+    let comments = match comment_service.list_for_city(id, sort) {
+        Ok(comments) => comments,
+        Err(err) => return Err(err.wrap_str("failed to load comments")),
+    };
+    let time_zone = time_zone_of(city_service.get_ref(), id)?;
+    Ok(web::Json(CommentThread::build(comments).iter()
+        .map(|t| CommentDto::from_thread(t, time_zone))
+        .collect()))
+}
+
+#[get("/{comment_id}/replies")]
+async fn get_replies(
+    comment_id: web::Path<String>,
+    comment_service: Data<Arc<dyn CommentService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<Vec<CommentDto>>, Error> {
+    let comment_id = get_number!(comment_id, i64, true);
+    handle_comment_vec(comment_service.list_replies(comment_id), city_service.get_ref())
+}
+
+/// Earlier versions of the comment text, visible to the poster and admins
+#[get("/{comment_id}/history")]
+async fn get_history(
+    req: HttpRequest,
+    comment_id: web::Path<String>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    comment_service: Data<Arc<dyn CommentService + Send + Sync>>,
+) -> Result<web::Json<Vec<CommentRevisionDto>>, Error> {
+    let user = get_user_if_has_roles!(req, auth_service, vec!["admin", "user"]);
+    let comment_id = get_number!(comment_id, i64, true);
+    let revisions = comment_service.history(comment_id, user)?;
+    Ok(web::Json(revisions.iter().map(CommentRevisionDto::from_model).collect()))
 }
 
-fn handle_comment_vec(promise: Result<Vec<Comment>, Error>) -> Result<web::Json<Vec<CommentDto>>, Error> {
+/// Upvotes or downvotes the comment, replacing an earlier vote of the user
+#[put("/{comment_id}/reactions")]
+async fn set_reaction(
+    req: HttpRequest,
+    comment_id: web::Path<String>,
+    payload: web::Json<ReactionDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    comment_service: Data<Arc<dyn CommentService + Send + Sync>>,
+) -> Result<web::Json<ReactionCountsDto>, Error> {
+    let user = get_user_if_has_roles!(req, auth_service, vec!["admin", "user"]);
+    let comment_id = get_number!(comment_id, i64, true);
+    let counts = comment_service.react(payload.to_model(comment_id, user.id)?)?;
+    Ok(web::Json(ReactionCountsDto::from_model(&counts)))
+}
+
+#[delete("/{comment_id}/reactions")]
+async fn delete_reaction(
+    req: HttpRequest,
+    comment_id: web::Path<String>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    comment_service: Data<Arc<dyn CommentService + Send + Sync>>,
+) -> Result<web::Json<ReactionCountsDto>, Error> {
+    let user = get_user_if_has_roles!(req, auth_service, vec!["admin", "user"]);
+    let comment_id = get_number!(comment_id, i64, true);
+    let counts = comment_service.remove_reaction(comment_id, user.id)?;
+    Ok(web::Json(ReactionCountsDto::from_model(&counts)))
+}
+
+fn handle_comment_vec(
+    promise: Result<Vec<Comment>, Error>,
+    city_service: &Arc<dyn CityService + Send + Sync>,
+) -> Result<web::Json<Vec<CommentDto>>, Error> {
     let comments = match promise {
         Ok(comments) => comments,
         Err(err) => return Err(err.wrap_str("failed to load comments")),
     };
-    let comments: Vec<CommentDto> = comments.iter().map(|c| CommentDto::from_model(c)).collect();
+    let time_zones = city_service.time_zones(comments.iter().map(|c| c.city_id).collect())?;
+    let comments: Vec<CommentDto> = comments.iter()
+        .map(|c| CommentDto::from_model(c, time_zones.get(&c.city_id).copied()))
+        .collect();
     Ok(web::Json(comments))
 }
 
+/// Time zone of the city a comment was posted for, if the city has one
+fn time_zone_of(city_service: &Arc<dyn CityService + Send + Sync>, city_id: i64) -> Result<Option<Tz>, Error> {
+    Ok(city_service.time_zones(vec![city_id])?.remove(&city_id))
+}
+
 #[post("/{city_id}/comments")]
 async fn save_comment(
     req: HttpRequest,
     city_id: web::Path<String>,
-    payload: web::Json<CommentDto>,
+    payload: web::Json<SaveCommentDto>,
     auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
     comment_service: Data<Arc<dyn CommentService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
 ) -> Result<web::Json<CommentDto>, Error> {
     let user = get_user_if_has_roles!(req, auth_service, vec!["admin", "user"]);
     // check path params
     let city_id = get_number!(city_id, i64, true);
     // extract payload
-    let mut comment = payload.0;
-    comment.city_id = city_id;
-    let mut comment = comment.to_model();
-    comment.user_id = user.id.clone();
+    let mut comment = payload.to_model(user.id.clone(), city_id);
     // save comment
     comment = match comment_service.into_inner().create(user.id.clone(), comment) {
         Ok(comment) => comment,
         Err(err) => return Err(err), 
     };
-    let mut dto = CommentDto::from_model(&comment);
+    let time_zone = time_zone_of(city_service.get_ref(), city_id)?;
+    let mut dto = CommentDto::from_model(&comment, time_zone);
     dto.user_name = Some(user.email.clone());
     Ok(web::Json(dto))
 }
@@ -104,9 +199,10 @@ async fn save_comment(
 async fn update_comment(
     req: HttpRequest,
     comment_id: web::Path<String>, 
-    payload: web::Json<CommentDto>,
+    payload: web::Json<SaveCommentDto>,
     auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
     comment_service: Data<Arc<dyn CommentService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
 ) -> Result<impl Responder, Error> {
     let user = get_user_if_has_roles!(req, auth_service, vec!["admin", "user"]);
     // extract path parameters
@@ -124,7 +220,8 @@ async fn update_comment(
     // update comment
     match comment_service.update(user.id.clone(), comment) {
         Ok(comment) => {
-            let mut dto = CommentDto::from_model(&comment);
+            let time_zone = time_zone_of(city_service.get_ref(), comment.city_id)?;
+            let mut dto = CommentDto::from_model(&comment, time_zone);
             dto.user_name = Some(user.email.clone());
             match serde_json::to_string(&dto) {
                 Ok(json) => Ok(HttpResponse::Created().body(json)),
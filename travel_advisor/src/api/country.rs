@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    post,
+    web::{
+        self,
+        Data,
+    },
+    HttpRequest,
+};
+
+use crate::{
+    model::Region,
+    services::traits::{
+        AuthService,
+        CityService,
+        CountryService,
+    },
+    util::Error,
+};
+use super::{
+    get_user_if_has_roles,
+    dtos::{
+        FromModel,
+        CityDto,
+        CountryCitiesQueryParam,
+        CountryDto,
+        CreateCountryDto,
+        CreateRegionDto,
+        RegionDto,
+    },
+    headers::accepted_languages,
+    validations::get_number,
+};
+
+pub(super) fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/v1/countries")
+        .service(get_countries)
+        .service(create_country)
+        .service(get_country)
+        .service(create_region)
+        .service(get_country_cities)
+    );
+}
+
+#[get("")]
+async fn get_countries(
+    country_service: Data<Arc<dyn CountryService + Send + Sync>>,
+) -> Result<web::Json<Vec<CountryDto>>, Error> {
+    let countries = country_service.get_all()?;
+    Ok(web::Json(countries.iter().map(CountryDto::from_model).collect()))
+}
+
+#[post("")]
+async fn create_country(
+    req: HttpRequest,
+    body: web::Json<CreateCountryDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    country_service: Data<Arc<dyn CountryService + Send + Sync>>,
+) -> Result<web::Json<CountryDto>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let country = country_service.create(body.to_model()?)?;
+    Ok(web::Json(CountryDto::from_model(&country)))
+}
+
+/// Returns the country with its regions
+#[get("/{code}")]
+async fn get_country(
+    code: web::Path<String>,
+    country_service: Data<Arc<dyn CountryService + Send + Sync>>,
+) -> Result<web::Json<CountryDto>, Error> {
+    match country_service.get_by_code(&code.to_ascii_uppercase())? {
+        Some(country) => Ok(web::Json(CountryDto::from_model(&country))),
+        None => Err(Error::not_found("country not found".to_string())),
+    }
+}
+
+#[post("/{code}/regions")]
+async fn create_region(
+    req: HttpRequest,
+    code: web::Path<String>,
+    body: web::Json<CreateRegionDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    country_service: Data<Arc<dyn CountryService + Send + Sync>>,
+) -> Result<web::Json<RegionDto>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > 60 {
+        return Err(Error::bad_request("name must have between 1 and 60 characters".to_string()));
+    }
+    let region = country_service.create_region(Region {
+        id: 0,
+        country_code: code.to_ascii_uppercase(),
+        name: name.to_string(),
+    })?;
+    Ok(web::Json(RegionDto::from_model(&region)))
+}
+
+/// Lists cities of the country, optionally only those of one region
+#[get("/{code}/cities")]
+async fn get_country_cities(
+    req: HttpRequest,
+    code: web::Path<String>,
+    query: web::Query<CountryCitiesQueryParam>,
+    country_service: Data<Arc<dyn CountryService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<Vec<CityDto>>, Error> {
+    let region_id = match &query.region_id {
+        Some(region_id) => Some(get_number!(region_id, i64, true)),
+        None => None,
+    };
+    let mut cities = country_service.get_cities(&code.to_ascii_uppercase(), region_id)?;
+    city_service.localize(&mut cities, &accepted_languages(&req))?;
+    Ok(web::Json(cities.iter().map(CityDto::from_model).collect()))
+}
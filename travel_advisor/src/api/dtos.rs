@@ -1,5 +1,14 @@
 use std::time::SystemTime;
 
+use chrono::{
+    DateTime,
+    NaiveDateTime,
+    NaiveTime,
+    SecondsFormat,
+    TimeZone,
+    Utc,
+};
+use chrono_tz::Tz;
 use serde::{
     Serialize,
     Deserialize,
@@ -8,9 +17,47 @@ use serde::{
 use crate::{
     model::{
         Airport,
+        AirportRef,
         City,
+        CityAlias,
+        CityDeletePolicy,
         Comment,
+        CommentFlag,
+        CommentRevision,
+        CommentSort,
+        CommentThread,
+        Coordinates,
+        Country,
+        Criterion,
+        FlightTimes,
+        FlaggedComment,
+        Itinerary,
+        MatchKind,
+        ModerationAction,
+        ModerationRecord,
+        NearbyAirport,
+        ParetoItinerary,
+        Place,
+        PoiCategory,
+        PointOfInterest,
+        ReachableCity,
+        Reaction,
+        ReactionCounts,
+        ReactionKind,
+        Region,
+        RoundTrip,
         Route,
+        RouteConstraints,
+        Schedule,
+        SearchResults,
+        TagMatch,
+        Tour,
+        Transfer,
+        is_country_code,
+        is_iata_code,
+        is_icao_code,
+        is_language_tag,
+        is_tag,
     },
     util::Error,
 };
@@ -27,7 +74,23 @@ pub trait FromModel<T> {
 pub struct CityDto {
     pub id: i64,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<CountryDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<RegionDto>,
+    /// IANA time zone name such as `Europe/Belgrade`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<CityAliasDto>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     pub airports: Vec<AirportDto>,
+    /// Present only when asked for with `embed=pois`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pois: Option<Vec<PoiDto>>,
 }
 
 impl FromModel<City> for CityDto {
@@ -39,11 +102,329 @@ impl FromModel<City> for CityDto {
         CityDto {
             id: c.id,
             name: c.name.clone(),
+            country_code: c.country_code.clone(),
+            country: c.country.as_ref().map(CountryDto::from_model),
+            region: c.region.as_ref().map(RegionDto::from_model),
+            time_zone: c.time_zone.map(|tz| tz.name().to_string()),
+            aliases: c.aliases.iter().map(CityAliasDto::from_model).collect(),
+            tags: c.tags.clone(),
             airports: airports,
+            pois: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CityAliasDto {
+    pub id: i64,
+    pub name: String,
+    pub language: String,
+}
+
+impl FromModel<CityAlias> for CityAliasDto {
+    fn from_model(a: &CityAlias) -> Self {
+        CityAliasDto {
+            id: a.id,
+            name: a.name.clone(),
+            language: a.language.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateCityAliasDto {
+    pub name: String,
+    /// BCP 47 language tag, like `sr-Latn`
+    pub language: String,
+}
+
+impl CreateCityAliasDto {
+    pub fn to_model(&self, city_id: i64) -> Result<CityAlias, Error> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > 30 {
+            return Err(Error::bad_request("name must have between 1 and 30 characters".to_string()));
+        }
+        if !is_language_tag(&self.language) {
+            return Err(Error::bad_request(format!("{} is not a language tag", self.language)));
+        }
+        Ok(CityAlias {
+            id: 0,
+            city_id,
+            name: name.to_string(),
+            language: self.language.clone(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CityListQueryParam {
+    /// Comma separated tags cities must have
+    pub tags: Option<String>,
+    /// `all` (default) when cities need every tag, `any` when one is enough
+    pub r#match: Option<String>,
+}
+
+impl CityListQueryParam {
+    pub fn to_model(&self) -> Result<(Vec<String>, TagMatch), Error> {
+        let tag_match = match self.r#match.as_deref() {
+            None | Some("all") => TagMatch::All,
+            Some("any") => TagMatch::Any,
+            Some(other) => return Err(Error::bad_request(format!("unknown match {}, expected all or any", other))),
+        };
+        let tags = self.tags.iter()
+            .flat_map(|t| t.split(','))
+            .filter(|t| !t.trim().is_empty())
+            .map(tag_of)
+            .collect::<Result<Vec<String>, Error>>()?;
+        Ok((tags, tag_match))
+    }
+}
+
+/// Normalizes a tag given by a client, like ` Beach` to `beach`
+pub fn tag_of(tag: &str) -> Result<String, Error> {
+    let tag = tag.trim().to_ascii_lowercase();
+    match is_tag(&tag) {
+        true => Ok(tag),
+        false => Err(Error::bad_request(format!("{} is not a tag, expected up to 30 letters, digits and dashes", tag))),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CityQueryParam {
+    /// Comma separated parts to include, only `pois` for now
+    pub embed: Option<String>,
+}
+
+impl CityQueryParam {
+    pub fn embeds_pois(&self) -> Result<bool, Error> {
+        let mut pois = false;
+        for part in self.embed.iter().flat_map(|e| e.split(',')).map(str::trim).filter(|p| !p.is_empty()) {
+            match part {
+                "pois" => pois = true,
+                _ => return Err(Error::bad_request(format!("cannot embed {}, expected pois", part))),
+            }
+        }
+        Ok(pois)
+    }
+}
+
+#[derive(Serialize)]
+pub struct PoiDto {
+    pub id: i64,
+    pub city_id: i64,
+    pub name: String,
+    pub category: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+}
+
+impl FromModel<PointOfInterest> for PoiDto {
+    fn from_model(p: &PointOfInterest) -> Self {
+        PoiDto {
+            id: p.id,
+            city_id: p.city_id,
+            name: p.name.clone(),
+            category: p.category.as_str().to_string(),
+            description: p.description.clone(),
+            latitude: p.location.map(|l| l.latitude),
+            longitude: p.location.map(|l| l.longitude),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SavePoiDto {
+    pub name: String,
+    /// One of `attraction`, `hotel` or `restaurant`
+    pub category: String,
+    #[serde(default)]
+    pub description: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+impl SavePoiDto {
+    pub fn to_model(&self, id: i64, city_id: i64) -> Result<PointOfInterest, Error> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > 100 {
+            return Err(Error::bad_request("name must have between 1 and 100 characters".to_string()));
         }
+        if self.description.chars().count() > 2000 {
+            return Err(Error::bad_request("description must have at most 2000 characters".to_string()));
+        }
+        Ok(PointOfInterest {
+            id,
+            city_id,
+            name: name.to_string(),
+            category: poi_category(&self.category)?,
+            description: self.description.trim().to_string(),
+            location: location_of(self.latitude, self.longitude)?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PoiQueryParam {
+    /// Comma separated categories, all categories when missing
+    pub category: Option<String>,
+}
+
+impl PoiQueryParam {
+    pub fn to_model(&self) -> Result<Vec<PoiCategory>, Error> {
+        self.category.iter()
+            .flat_map(|c| c.split(','))
+            .filter(|c| !c.trim().is_empty())
+            .map(poi_category)
+            .collect()
     }
 }
 
+fn poi_category(name: &str) -> Result<PoiCategory, Error> {
+    PoiCategory::parse(name).ok_or_else(|| Error::bad_request(
+        format!("unknown category {}, expected attraction, hotel or restaurant", name.trim())
+    ))
+}
+
+#[derive(Serialize)]
+pub struct CountryDto {
+    pub code: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<RegionDto>,
+}
+
+impl FromModel<Country> for CountryDto {
+    fn from_model(c: &Country) -> Self {
+        CountryDto {
+            code: c.code.clone(),
+            name: c.name.clone(),
+            regions: c.regions.iter().map(RegionDto::from_model).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RegionDto {
+    pub id: i64,
+    pub name: String,
+}
+
+impl FromModel<Region> for RegionDto {
+    fn from_model(r: &Region) -> Self {
+        RegionDto {
+            id: r.id,
+            name: r.name.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateCountryDto {
+    pub code: String,
+    pub name: String,
+}
+
+impl CreateCountryDto {
+    pub fn to_model(&self) -> Result<Country, Error> {
+        let code = self.code.to_ascii_uppercase();
+        if !is_country_code(&code) {
+            return Err(Error::bad_request("code must be an ISO 3166-1 alpha-2 code".to_string()));
+        }
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > 60 {
+            return Err(Error::bad_request("name must have between 1 and 60 characters".to_string()));
+        }
+        Ok(Country {
+            code,
+            name: name.to_string(),
+            regions: vec![],
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateRegionDto {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct SetCityCountryDto {
+    pub country_code: Option<String>,
+    pub region_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateCityDto {
+    pub name: String,
+    pub country_code: Option<String>,
+    pub region_id: Option<i64>,
+    /// IANA time zone name
+    pub time_zone: Option<String>,
+}
+
+impl UpdateCityDto {
+    pub fn to_model(&self, id: i64) -> Result<City, Error> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > 30 {
+            return Err(Error::bad_request("name must have between 1 and 30 characters".to_string()));
+        }
+        let time_zone = SetCityTimeZoneDto { time_zone: self.time_zone.clone() }.to_model()?;
+        Ok(City {
+            country_code: self.country_code.as_ref().map(|c| c.to_ascii_uppercase()),
+            region_id: self.region_id,
+            time_zone,
+            ..City::new(id, name.to_string())
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeleteCityQueryParam {
+    /// One of `restrict` (default), `cascade` or `reassign`
+    pub policy: Option<String>,
+    /// City that airports, comments and points of interest move to, required by `reassign`
+    pub target_city_id: Option<i64>,
+}
+
+impl DeleteCityQueryParam {
+    pub fn to_model(&self) -> Result<CityDeletePolicy, Error> {
+        match (self.policy.as_deref(), self.target_city_id) {
+            (None, None) | (Some("restrict"), None) => Ok(CityDeletePolicy::Restrict),
+            (Some("cascade"), None) => Ok(CityDeletePolicy::Cascade),
+            (Some("reassign"), Some(target_city_id)) => Ok(CityDeletePolicy::Reassign(target_city_id)),
+            (Some("reassign"), None) => Err(Error::bad_request("reassign policy requires target_city_id".to_string())),
+            (_, Some(_)) => Err(Error::bad_request("target_city_id is only allowed with reassign policy".to_string())),
+            (Some(policy), None) => Err(Error::bad_request(format!("unknown policy {}, expected restrict, cascade or reassign", policy))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SetCityTimeZoneDto {
+    /// IANA time zone name, none clears the time zone
+    pub time_zone: Option<String>,
+}
+
+impl SetCityTimeZoneDto {
+    pub fn to_model(&self) -> Result<Option<Tz>, Error> {
+        match &self.time_zone {
+            Some(name) => match name.parse::<Tz>() {
+                Ok(tz) => Ok(Some(tz)),
+                Err(_) => Err(Error::bad_request(format!("{} is not an IANA time zone", name))),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CountryCitiesQueryParam {
+    pub region_id: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct UserDto {
     pub id: i64,
@@ -67,23 +448,59 @@ pub struct LoginResponse {
 pub struct CreateAirportDto {
     pub city_id: i64,
     pub name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub iata_code: Option<String>,
+    pub icao_code: Option<String>,
 }
 
-impl ToModel<Airport> for CreateAirportDto {
-    fn to_model(&self) -> Airport {
-        Airport {
+impl CreateAirportDto {
+    pub fn to_model(&self) -> Result<Airport, Error> {
+        let location = location_of(self.latitude, self.longitude)?;
+        let iata_code = self.iata_code.as_ref().map(|c| c.to_ascii_uppercase());
+        if iata_code.as_ref().is_some_and(|c| !is_iata_code(c)) {
+            return Err(Error::bad_request("iata_code must be three letters".to_string()));
+        }
+        let icao_code = self.icao_code.as_ref().map(|c| c.to_ascii_uppercase());
+        if icao_code.as_ref().is_some_and(|c| !is_icao_code(c)) {
+            return Err(Error::bad_request("icao_code must be four letters or digits".to_string()));
+        }
+        Ok(Airport {
             id: 0,
-            city_id: self.city_id.clone(),
+            city_id: self.city_id,
             name: self.name.clone(),
-        }
+            location,
+            iata_code,
+            icao_code,
+        })
     }
 }
 
+/// Coordinates given as optional latitude and longitude, which must come together
+fn location_of(latitude: Option<f64>, longitude: Option<f64>) -> Result<Option<Coordinates>, Error> {
+    if latitude.is_some() != longitude.is_some() {
+        return Err(Error::bad_request("latitude and longitude must be given together".to_string()));
+    }
+    let location = Coordinates::from_parts(latitude, longitude);
+    if location.is_some_and(|l| !l.is_valid()) {
+        return Err(Error::bad_request("latitude must be within [-90, 90] and longitude within [-180, 180]".to_string()));
+    }
+    Ok(location)
+}
+
 #[derive(Serialize)]
 pub struct AirportDto {
     pub id: i64,
     pub city_id: i64,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iata_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icao_code: Option<String>,
 }
 
 impl FromModel<Airport> for AirportDto {
@@ -92,43 +509,377 @@ impl FromModel<Airport> for AirportDto {
             id: a.id.clone(),
             city_id: a.city_id.clone(),
             name: a.name.clone(),
+            latitude: a.location.map(|l| l.latitude),
+            longitude: a.location.map(|l| l.longitude),
+            iata_code: a.iata_code.clone(),
+            icao_code: a.icao_code.clone(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Deserialize)]
+pub struct NearbyQueryParam {
+    pub lat: String,
+    pub lon: String,
+    pub radius_km: Option<String>,
+    pub limit: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct NearbyAirportDto {
+    #[serde(flatten)]
+    pub airport: AirportDto,
+    pub distance_km: f64,
+}
+
+impl FromModel<NearbyAirport> for NearbyAirportDto {
+    fn from_model(nearby: &NearbyAirport) -> Self {
+        NearbyAirportDto {
+            airport: AirportDto::from_model(&nearby.airport),
+            distance_km: (nearby.distance / 100.0).round() / 10.0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SearchQueryParam {
+    pub q: String,
+    /// Most cities and most airports to return
+    pub limit: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all="kebab-case")]
+pub enum MatchKindDto {
+    Code,
+    Exact,
+    Prefix,
+    WordPrefix,
+    Fuzzy,
+}
+
+impl FromModel<MatchKind> for MatchKindDto {
+    fn from_model(kind: &MatchKind) -> Self {
+        match kind {
+            MatchKind::Code => MatchKindDto::Code,
+            MatchKind::Exact => MatchKindDto::Exact,
+            MatchKind::Prefix => MatchKindDto::Prefix,
+            MatchKind::WordPrefix => MatchKindDto::WordPrefix,
+            MatchKind::Fuzzy => MatchKindDto::Fuzzy,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CitySearchHitDto {
+    pub id: i64,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(rename = "match")]
+    pub match_kind: MatchKindDto,
+}
+
+#[derive(Serialize)]
+pub struct AirportSearchHitDto {
+    #[serde(flatten)]
+    pub airport: AirportDto,
+    #[serde(rename = "match")]
+    pub match_kind: MatchKindDto,
+}
+
+/// Search results grouped by entity type, each group ranked best match first
+#[derive(Serialize)]
+pub struct SearchResultsDto {
+    pub cities: Vec<CitySearchHitDto>,
+    pub airports: Vec<AirportSearchHitDto>,
+}
+
+impl FromModel<SearchResults> for SearchResultsDto {
+    fn from_model(results: &SearchResults) -> Self {
+        SearchResultsDto {
+            cities: results.cities.iter()
+                .map(|hit| CitySearchHitDto {
+                    id: hit.item.id,
+                    name: hit.item.name.clone(),
+                    country_code: hit.item.country_code.clone(),
+                    match_kind: MatchKindDto::from_model(&hit.kind),
+                })
+                .collect(),
+            airports: results.airports.iter()
+                .map(|hit| AirportSearchHitDto {
+                    airport: AirportDto::from_model(&hit.item),
+                    match_kind: MatchKindDto::from_model(&hit.kind),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
 pub struct CommentDto {
     pub id: i64,
     pub user_id: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_name: Option<String>,
     pub city_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<i64>,
     pub content: String,
-    pub created_at: SystemTime,
-    pub updated_at: SystemTime,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    pub upvotes: i64,
+    pub downvotes: i64,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at_local: Option<String>,
+    pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at_local: Option<String>,
+    /// Present only in the nested view
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replies: Option<Vec<CommentDto>>,
 }
 
+/// Text shown in place of a deleted comment that still has replies
+const DELETED_COMMENT: &str = "[deleted]";
+
 impl CommentDto {
-    pub fn from_model(c: &Comment) -> Self {
+    /// Timestamps are rendered in UTC and, when the city has a time zone, in city local time
+    pub fn from_model(c: &Comment, time_zone: Option<Tz>) -> Self {
+        let created_at = DateTime::<Utc>::from(c.created_at).naive_utc();
+        let updated_at = DateTime::<Utc>::from(c.updated_at).naive_utc();
         CommentDto {
             id: c.id.clone(),
             user_id: c.user_id.clone(),
             user_name: None,
             city_id: c.city_id.clone(),
-            content: c.content.clone(),
-            created_at: c.created_at.clone(),
-            updated_at: c.updated_at.clone(),
+            parent_id: c.parent_id,
+            content: match c.deleted {
+                true => DELETED_COMMENT.to_string(),
+                false => c.content.clone(),
+            },
+            deleted: c.deleted,
+            hidden: c.hidden,
+            upvotes: c.reactions.up,
+            downvotes: c.reactions.down,
+            created_at: utc_timestamp(&created_at),
+            created_at_local: local_timestamp(&created_at, time_zone),
+            updated_at: utc_timestamp(&updated_at),
+            updated_at_local: local_timestamp(&updated_at, time_zone),
+            replies: None,
         }
     }
 
-    pub fn to_model(&self) -> Comment {
+    pub fn from_thread(thread: &CommentThread, time_zone: Option<Tz>) -> Self {
+        CommentDto {
+            replies: Some(thread.replies.iter().map(|r| CommentDto::from_thread(r, time_zone)).collect()),
+            ..CommentDto::from_model(&thread.comment, time_zone)
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CommentRevisionDto {
+    pub id: i64,
+    pub content: String,
+    pub written_at: String,
+}
+
+impl FromModel<CommentRevision> for CommentRevisionDto {
+    fn from_model(r: &CommentRevision) -> Self {
+        CommentRevisionDto {
+            id: r.id,
+            content: r.content.clone(),
+            written_at: utc_timestamp(&DateTime::<Utc>::from(r.written_at).naive_utc()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReactionDto {
+    /// `up` or `down`
+    pub kind: String,
+}
+
+impl ReactionDto {
+    pub fn to_model(&self, comment_id: i64, user_id: i64) -> Result<Reaction, Error> {
+        match ReactionKind::parse(&self.kind) {
+            Some(kind) => Ok(Reaction {
+                comment_id,
+                user_id,
+                kind,
+            }),
+            None => Err(Error::bad_request(format!("unknown reaction {}, expected up or down", self.kind))),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReactionCountsDto {
+    pub upvotes: i64,
+    pub downvotes: i64,
+}
+
+impl FromModel<ReactionCounts> for ReactionCountsDto {
+    fn from_model(r: &ReactionCounts) -> Self {
+        ReactionCountsDto {
+            upvotes: r.up,
+            downvotes: r.down,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FlagCommentDto {
+    pub reason: String,
+}
+
+impl FlagCommentDto {
+    pub fn to_model(&self, comment_id: i64, user_id: i64) -> Result<CommentFlag, Error> {
+        let reason = self.reason.trim();
+        if reason.is_empty() || reason.chars().count() > 250 {
+            return Err(Error::bad_request("reason must have between 1 and 250 characters".to_string()));
+        }
+        Ok(CommentFlag {
+            id: 0,
+            comment_id,
+            user_id,
+            reason: reason.to_string(),
+            created_at: SystemTime::now(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct CommentFlagDto {
+    pub id: i64,
+    pub user_id: i64,
+    pub reason: String,
+    pub created_at: String,
+}
+
+impl FromModel<CommentFlag> for CommentFlagDto {
+    fn from_model(f: &CommentFlag) -> Self {
+        CommentFlagDto {
+            id: f.id,
+            user_id: f.user_id,
+            reason: f.reason.clone(),
+            created_at: utc_timestamp(&DateTime::<Utc>::from(f.created_at).naive_utc()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FlaggedCommentDto {
+    pub comment: CommentDto,
+    pub flags: Vec<CommentFlagDto>,
+}
+
+impl FromModel<FlaggedComment> for FlaggedCommentDto {
+    fn from_model(f: &FlaggedComment) -> Self {
+        FlaggedCommentDto {
+            // moderators see times in UTC only
+            comment: CommentDto::from_model(&f.comment, None),
+            flags: f.flags.iter().map(CommentFlagDto::from_model).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ModerateCommentDto {
+    /// One of `approve`, `hide` or `delete`
+    pub action: String,
+}
+
+impl ModerateCommentDto {
+    pub fn to_model(&self) -> Result<ModerationAction, Error> {
+        match ModerationAction::parse(&self.action) {
+            Some(action) => Ok(action),
+            None => Err(Error::bad_request(format!("unknown action {}, expected approve, hide or delete", self.action))),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ModerationRecordDto {
+    pub id: i64,
+    pub comment_id: i64,
+    pub comment_user_id: i64,
+    pub comment_text: String,
+    pub moderator_id: i64,
+    pub action: String,
+    pub created_at: String,
+}
+
+impl FromModel<ModerationRecord> for ModerationRecordDto {
+    fn from_model(r: &ModerationRecord) -> Self {
+        ModerationRecordDto {
+            id: r.id,
+            comment_id: r.comment_id,
+            comment_user_id: r.comment_user_id,
+            comment_text: r.comment_text.clone(),
+            moderator_id: r.moderator_id,
+            action: r.action.as_str().to_string(),
+            created_at: utc_timestamp(&DateTime::<Utc>::from(r.created_at).naive_utc()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ModerationRecordsQueryParam {
+    pub comment_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CityCommentsQueryParam {
+    /// `flat` (default) lists all comments, `nested` lists threads with replies inside their parents
+    pub view: Option<String>,
+    /// `helpful`, `newest` or `oldest` (default)
+    pub sort: Option<String>,
+}
+
+impl CityCommentsQueryParam {
+    pub fn is_nested(&self) -> Result<bool, Error> {
+        match self.view.as_deref() {
+            None | Some("flat") => Ok(false),
+            Some("nested") => Ok(true),
+            Some(view) => Err(Error::bad_request(format!("unknown view {}, expected flat or nested", view))),
+        }
+    }
+
+    pub fn to_sort(&self) -> Result<CommentSort, Error> {
+        match self.sort.as_deref() {
+            None => Ok(CommentSort::Oldest),
+            Some(sort) => CommentSort::parse(sort).ok_or_else(|| Error::bad_request(
+                format!("unknown sort {}, expected helpful, newest or oldest", sort))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SaveCommentDto {
+    pub content: String,
+    /// Comment this one replies to, ignored on update
+    pub parent_id: Option<i64>,
+}
+
+impl SaveCommentDto {
+    pub fn to_model(&self, user_id: i64, city_id: i64) -> Comment {
+        let now = SystemTime::now();
         Comment {
-            id: self.id.clone(),
-            user_id: self.user_id.clone(),
-            city_id: self.city_id.clone(),
+            id: 0,
+            user_id,
+            city_id,
+            parent_id: self.parent_id,
             content: self.content.clone(),
-            created_at: self.created_at.clone(),
-            updated_at: self.updated_at.clone(),
+            deleted: false,
+            hidden: false,
+            reactions: ReactionCounts::default(),
+            created_at: now,
+            updated_at: now,
         }
     }
 }
@@ -154,15 +905,271 @@ impl FromModel<Route> for RouteDto {
 
 #[derive(Deserialize)]
 pub struct SaveRouteDto {
-    pub start: i64,
-    pub finish: i64,
+    pub start: AirportRefDto,
+    pub finish: AirportRefDto,
     pub price: i64,
 }
 
+/// Airport given either by numeric ID or by IATA or ICAO code
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum AirportRefDto {
+    Id(i64),
+    Code(String),
+}
+
+impl AirportRefDto {
+    pub fn to_model(&self) -> Result<AirportRef, Error> {
+        match self {
+            AirportRefDto::Id(id) => Ok(AirportRef::Id(*id)),
+            AirportRefDto::Code(code) => match AirportRef::parse(code) {
+                Some(reference) => Ok(reference),
+                None => Err(Error::bad_request(format!("{} is neither an airport ID nor an IATA or ICAO code", code))),
+            },
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct CalculateCheapestRouteRequestDto {
+    pub starting_city_id: Option<i64>,
+    /// Departs from any city of the country instead of a single city
+    pub starting_country_code: Option<String>,
+    /// Departs from any city with the tag instead of a single city
+    pub starting_tag: Option<String>,
+    pub destination_city_id: Option<i64>,
+    /// Arrives to any city of the country instead of a single city
+    pub destination_country_code: Option<String>,
+    /// Arrives to any city with the tag instead of a single city
+    pub destination_tag: Option<String>,
+    pub count: Option<usize>,
+    pub max_flights: Option<usize>,
+    pub max_price: Option<i64>,
+    pub excluded_city_ids: Option<Vec<i64>>,
+    pub excluded_airport_ids: Option<Vec<AirportRefDto>>,
+    pub via_city_ids: Option<Vec<i64>>,
+    pub mode: Option<SearchMode>,
+    /// RFC 3339 timestamp, defaults to now
+    pub departure_after: Option<String>,
+    pub min_connection_minutes: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct RoundTripRequestDto {
     pub starting_city_id: i64,
     pub destination_city_id: i64,
+    /// City the return starts from, defaults to the destination
+    pub return_from_city_id: Option<i64>,
+    /// City the return ends in, defaults to the starting city
+    pub return_to_city_id: Option<i64>,
+    pub count: Option<usize>,
+    /// Limits each direction separately
+    pub max_flights: Option<usize>,
+    /// Limits both directions together
+    pub max_price: Option<i64>,
+    pub excluded_city_ids: Option<Vec<i64>>,
+    pub excluded_airport_ids: Option<Vec<AirportRefDto>>,
+}
+
+impl ToModel<RouteConstraints> for RoundTripRequestDto {
+    fn to_model(&self) -> RouteConstraints {
+        RouteConstraints {
+            max_flights: self.max_flights,
+            max_price: self.max_price,
+            excluded_city_ids: self.excluded_city_ids.clone().unwrap_or_default(),
+            // may be given by code, resolved by the handler
+            excluded_airport_ids: vec![],
+            via_city_ids: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ReachableQueryParam {
+    pub from: String,
+    pub budget: String,
+    /// Only cities with the tag
+    pub tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TourRequestDto {
+    pub origin_city_id: i64,
+    pub city_ids: Vec<i64>,
+    pub return_to_origin: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all="kebab-case")]
+pub enum SearchMode {
+    Cheapest,
+    EarliestArrival,
+    ShortestDistance,
+}
+
+impl CalculateCheapestRouteRequestDto {
+    /// Reads the starting and destination place, each given by city, by country or by tag
+    pub fn places(&self) -> Result<(Place, Place), Error> {
+        Ok((
+            place_of("starting", self.starting_city_id, &self.starting_country_code, &self.starting_tag)?,
+            place_of("destination", self.destination_city_id, &self.destination_country_code, &self.destination_tag)?,
+        ))
+    }
+}
+
+fn place_of(role: &str, city_id: Option<i64>, country_code: &Option<String>, tag: &Option<String>) -> Result<Place, Error> {
+    match (city_id, country_code, tag) {
+        (Some(city_id), None, None) => Ok(Place::City(city_id)),
+        (None, Some(code), None) => {
+            let code = code.to_ascii_uppercase();
+            match is_country_code(&code) {
+                true => Ok(Place::Country(code)),
+                false => Err(Error::bad_request(format!("{} country code must be two letters", role))),
+            }
+        },
+        (None, None, Some(tag)) => Ok(Place::Tagged(tag_of(tag)?)),
+        _ => Err(Error::bad_request(format!("exactly one of {0}_city_id, {0}_country_code and {0}_tag is required", role))),
+    }
+}
+
+impl ToModel<RouteConstraints> for CalculateCheapestRouteRequestDto {
+    fn to_model(&self) -> RouteConstraints {
+        RouteConstraints {
+            max_flights: self.max_flights,
+            max_price: self.max_price,
+            excluded_city_ids: self.excluded_city_ids.clone().unwrap_or_default(),
+            // may be given by code, resolved by the handler
+            excluded_airport_ids: vec![],
+            via_city_ids: self.via_city_ids.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ScheduleDto {
+    pub id: i64,
+    pub route_id: i64,
+    /// Time of day in UTC, formatted as `HH:MM`
+    pub departure_time: String,
+    pub duration_minutes: i32,
+    /// ISO week days, 1 being Monday
+    pub days_of_week: Vec<u32>,
+    /// Departure in local time of the departure city, as of today
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub departure_time_local: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub departure_time_zone: Option<String>,
+    /// Arrival in local time of the arrival city, as of today
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_time_local: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_time_zone: Option<String>,
+}
+
+impl FromModel<Schedule> for ScheduleDto {
+    fn from_model(model: &Schedule) -> Self {
+        ScheduleDto {
+            id: model.id,
+            route_id: model.route_id,
+            departure_time: model.departure_time.format("%H:%M").to_string(),
+            duration_minutes: model.duration_minutes,
+            days_of_week: (0..7)
+                .filter(|day| model.days_of_week & (1 << day) != 0)
+                .map(|day| day + 1)
+                .collect(),
+            departure_time_local: None,
+            departure_time_zone: None,
+            arrival_time_local: None,
+            arrival_time_zone: None,
+        }
+    }
+}
+
+impl ScheduleDto {
+    /// Adds local times of today's departure. Offsets change with daylight saving time,
+    /// so local times of other days may differ.
+    pub fn from_model_in_zones(model: &Schedule, departure_tz: Option<Tz>, arrival_tz: Option<Tz>) -> Self {
+        let mut dto = ScheduleDto::from_model(model);
+        let departure = Utc::now().date_naive().and_time(model.departure_time);
+        let arrival = model.arrival(departure);
+        if let Some(tz) = departure_tz {
+            dto.departure_time_local = Some(tz.from_utc_datetime(&departure).format("%H:%M").to_string());
+            dto.departure_time_zone = Some(tz.name().to_string());
+        }
+        if let Some(tz) = arrival_tz {
+            dto.arrival_time_local = Some(tz.from_utc_datetime(&arrival).format("%H:%M").to_string());
+            dto.arrival_time_zone = Some(tz.name().to_string());
+        }
+        dto
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SaveScheduleDto {
+    pub departure_time: String,
+    pub duration_minutes: i32,
+    /// Defaults to every day
+    pub days_of_week: Option<Vec<u32>>,
+}
+
+impl SaveScheduleDto {
+    pub fn to_model(&self, route_id: i64) -> Result<Schedule, Error> {
+        let departure_time = match NaiveTime::parse_from_str(&self.departure_time, "%H:%M") {
+            Ok(time) => time,
+            Err(err) => return Err(Error::bad_request(format!("departure time must be formatted as HH:MM: {}", err))),
+        };
+        if self.duration_minutes <= 0 {
+            return Err(Error::bad_request("duration must be a positive number of minutes".to_string()));
+        }
+        let days_of_week = match &self.days_of_week {
+            None => crate::model::schedule::ALL_DAYS,
+            Some(days) => {
+                let mut mask: u8 = 0;
+                for day in days {
+                    if !(1..=7).contains(day) {
+                        return Err(Error::bad_request(format!("{} is not a day of week between 1 and 7", day)));
+                    }
+                    mask |= 1 << (day - 1);
+                }
+                mask
+            },
+        };
+        if days_of_week == 0 {
+            return Err(Error::bad_request("schedule must operate on at least one day".to_string()));
+        }
+        Ok(Schedule {
+            id: 0,
+            route_id,
+            departure_time,
+            duration_minutes: self.duration_minutes,
+            days_of_week,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct TransferDto {
+    pub from_airport_id: i64,
+    pub to_airport_id: i64,
+    pub price: i64,
+    pub duration_minutes: i32,
+}
+
+impl FromModel<Transfer> for TransferDto {
+    fn from_model(model: &Transfer) -> Self {
+        TransferDto {
+            from_airport_id: model.from_airport_id,
+            to_airport_id: model.to_airport_id,
+            price: model.price,
+            duration_minutes: model.duration_minutes,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SaveTransferDto {
+    pub price: i64,
+    pub duration_minutes: i32,
 }
 
 #[derive(Serialize)]
@@ -180,6 +1187,10 @@ pub struct PaginationQueryParam {
 
 #[derive(Serialize)]
 pub struct BestPathDto {
+    total_price: i64,
+    /// Sum of great-circle distances of all flights, missing if any airport has no coordinates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_distance_km: Option<f64>,
     steps: Vec<PathStepDto>,
 }
 
@@ -193,9 +1204,48 @@ pub struct PathStepDto {
     route_id: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     price: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    departure_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arrival_time: Option<String>,
+    /// Departure in local time of the city the flight leaves from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    departure_time_local: Option<String>,
+    /// Arrival in local time of the city the flight lands in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arrival_time_local: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_minutes: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance_km: Option<f64>,
     step_type: PathStepType,
 }
 
+/// Great-circle distance between two airports in kilometers, rounded to one decimal
+fn distance_km(from: &Airport, to: &Airport) -> Option<f64> {
+    from.distance_to(to).map(|meters| (meters / 100.0).round() / 10.0)
+}
+
+fn utc_timestamp(time: &NaiveDateTime) -> String {
+    DateTime::<Utc>::from_naive_utc_and_offset(*time, Utc).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn local_timestamp(time: &NaiveDateTime, time_zone: Option<Tz>) -> Option<String> {
+    time_zone.map(|tz| tz.from_utc_datetime(time).to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+impl PathStepDto {
+    fn with_times(mut self, times: Option<&FlightTimes>, departure_tz: Option<Tz>, arrival_tz: Option<Tz>) -> Self {
+        if let Some(times) = times {
+            self.departure_time = Some(utc_timestamp(&times.departure));
+            self.arrival_time = Some(utc_timestamp(&times.arrival));
+            self.departure_time_local = local_timestamp(&times.departure, departure_tz);
+            self.arrival_time_local = local_timestamp(&times.arrival, arrival_tz);
+        }
+        self
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all="kebab-case")]
 pub enum PathStepType {
@@ -206,11 +1256,14 @@ pub enum PathStepType {
 
 impl BestPathDto {
     pub fn from_models(
-        routes: Vec<Route>,
-        airports: Vec<Airport>,
-        cities: Vec<City>,
+        itinerary: &Itinerary,
+        airports: &[Airport],
+        cities: &[City],
     ) -> Result<BestPathDto, Error> {
+        let routes = &itinerary.routes;
         let mut path = BestPathDto {
+            total_price: itinerary.price,
+            total_distance_km: None,
             steps: vec![],
         };
         let mut prev_route = &routes[0];
@@ -231,8 +1284,16 @@ impl BestPathDto {
             city_name: prev_city.name.clone(),
             price: None,
             route_id: None,
+            departure_time: None,
+            arrival_time: None,
+            departure_time_local: None,
+            arrival_time_local: None,
+            duration_minutes: None,
+            distance_km: None,
             step_type: PathStepType::Start,
         });
+        let start_airport = prev_airport;
+        let start_tz = prev_city.time_zone;
         prev_airport = match airports.iter().find(|a| a.id.clone() == prev_route.finish.clone()) {
             Some(opt) => opt,
             None => return Err(Error::not_found(format!("airport with ID={} not found", airport_id.clone()))),
@@ -248,10 +1309,16 @@ impl BestPathDto {
             city_name: prev_city.name.clone(),
             price: Some(prev_route.price.clone()),
             route_id: Some(prev_route.id.clone()),
+            departure_time: None,
+            arrival_time: None,
+            departure_time_local: None,
+            arrival_time_local: None,
+            duration_minutes: None,
+            distance_km: distance_km(start_airport, prev_airport),
             step_type: PathStepType::Flight,
-        });
+        }.with_times(itinerary.times.first(), start_tz, prev_city.time_zone));
         // rest of steps
-        for route in routes.iter().skip(1) {
+        for (index, route) in routes.iter().enumerate().skip(1) {
             if route.start.clone() != prev_route.finish.clone() {
                 prev_airport = match airports.iter().find(|a| a.id.clone() == route.start.clone()) {
                     Some(opt) => opt,
@@ -261,16 +1328,26 @@ impl BestPathDto {
                     Some(opt) => opt,
                     None => return Err(Error::not_found(format!("city with ID={} not found", prev_airport.city_id.clone()))),
                 };
+                let transfer = itinerary.transfers.iter()
+                    .find(|t| t.from_airport_id == prev_route.finish && t.to_airport_id == route.start);
                 path.steps.push(PathStepDto {
                     airport_id: route.start.clone(),
                     airport_name: prev_airport.name.clone(),
                     city_id: prev_city.id.clone(),
                     city_name: prev_city.name.clone(),
                     route_id: None,
-                    price: None,
+                    price: transfer.map(|t| t.price),
+                    departure_time: None,
+                    arrival_time: None,
+                    departure_time_local: None,
+                    arrival_time_local: None,
+                    duration_minutes: transfer.map(|t| t.duration_minutes),
+                    distance_km: None,
                     step_type: PathStepType::CityCommute,
                 });
             }
+            let start_airport = prev_airport;
+            let start_tz = prev_city.time_zone;
             prev_airport = match airports.iter().find(|a| a.id.clone() == route.finish.clone()) {
                 Some(opt) => opt,
                 None => return Err(Error::not_found(format!("airport with ID={} not found", route.finish.clone()))),
@@ -286,11 +1363,134 @@ impl BestPathDto {
                 city_name: prev_city.name.clone(),
                 route_id: Some(route.id.clone()),
                 price: Some(route.price),
+                departure_time: None,
+                arrival_time: None,
+                departure_time_local: None,
+                arrival_time_local: None,
+                duration_minutes: None,
+                distance_km: distance_km(start_airport, prev_airport),
                 step_type: PathStepType::Flight,
-            });
+            }.with_times(itinerary.times.get(index), start_tz, prev_city.time_zone));
             prev_route = route;
         }
+        path.total_distance_km = routes.iter()
+            .map(|route| {
+                let start = airports.iter().find(|a| a.id == route.start)?;
+                let finish = airports.iter().find(|a| a.id == route.finish)?;
+                start.distance_to(finish)
+            })
+            .sum::<Option<f64>>()
+            .map(|meters| (meters / 100.0).round() / 10.0);
 
         Ok(path)
     }
 }
+
+#[derive(Serialize)]
+pub struct ParetoPathDto {
+    wins: Vec<CriterionDto>,
+    legs: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    travel_minutes: Option<i64>,
+    #[serde(flatten)]
+    path: BestPathDto,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all="kebab-case")]
+pub enum CriterionDto {
+    Price,
+    Legs,
+    Time,
+}
+
+impl FromModel<Criterion> for CriterionDto {
+    fn from_model(model: &Criterion) -> Self {
+        match model {
+            Criterion::Price => CriterionDto::Price,
+            Criterion::Legs => CriterionDto::Legs,
+            Criterion::Time => CriterionDto::Time,
+        }
+    }
+}
+
+impl ParetoPathDto {
+    pub fn from_models(
+        pareto: &ParetoItinerary,
+        airports: &[Airport],
+        cities: &[City],
+    ) -> Result<ParetoPathDto, Error> {
+        Ok(ParetoPathDto {
+            wins: pareto.wins.iter().map(CriterionDto::from_model).collect(),
+            legs: pareto.itinerary.routes.len(),
+            travel_minutes: pareto.travel_minutes,
+            path: BestPathDto::from_models(&pareto.itinerary, airports, cities)?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct RoundTripDto {
+    total_price: i64,
+    outbound: BestPathDto,
+    inbound: BestPathDto,
+}
+
+impl RoundTripDto {
+    pub fn from_models(
+        trip: &RoundTrip,
+        airports: &[Airport],
+        cities: &[City],
+    ) -> Result<RoundTripDto, Error> {
+        Ok(RoundTripDto {
+            total_price: trip.price,
+            outbound: BestPathDto::from_models(&trip.outbound, airports, cities)?,
+            inbound: BestPathDto::from_models(&trip.inbound, airports, cities)?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct TourDto {
+    total_price: i64,
+    legs: Vec<BestPathDto>,
+}
+
+impl TourDto {
+    pub fn from_models(
+        tour: &Tour,
+        airports: &[Airport],
+        cities: &[City],
+    ) -> Result<TourDto, Error> {
+        Ok(TourDto {
+            total_price: tour.price,
+            legs: tour.legs.iter()
+                .map(|leg| BestPathDto::from_models(leg, airports, cities))
+                .collect::<Result<Vec<BestPathDto>, Error>>()?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReachableCityDto {
+    city_id: i64,
+    city_name: String,
+    price: i64,
+    /// Landings before reaching the city, zero for a direct flight
+    stops: usize,
+}
+
+impl ReachableCityDto {
+    pub fn from_models(reachable: &ReachableCity, cities: &[City]) -> Result<ReachableCityDto, Error> {
+        let city = match cities.iter().find(|c| c.id == reachable.city_id) {
+            Some(city) => city,
+            None => return Err(Error::not_found(format!("city with ID={} not found", reachable.city_id))),
+        };
+        Ok(ReachableCityDto {
+            city_id: city.id,
+            city_name: city.name.clone(),
+            price: reachable.price,
+            stops: reachable.flights - 1,
+        })
+    }
+}
@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod dtos_tests {
+    use crate::{
+        model::TagMatch,
+        util::Error,
+    };
+    use super::super::dtos::{
+        CityListQueryParam,
+        CreateCityAliasDto,
+    };
+
+    fn query(tags: Option<&str>, tag_match: Option<&str>) -> CityListQueryParam {
+        CityListQueryParam {
+            tags: tags.map(|t| t.to_string()),
+            r#match: tag_match.map(|m| m.to_string()),
+        }
+    }
+
+    #[test]
+    fn city_list_query_normalizes_tags_and_defaults_to_all() {
+        let (tags, tag_match) = query(Some(" Beach,,mountain "), None).to_model().unwrap();
+
+        assert_eq!(vec!["beach".to_string(), "mountain".to_string()], tags);
+        assert_eq!(TagMatch::All, tag_match);
+    }
+
+    #[test]
+    fn city_list_query_without_tags() {
+        let (tags, tag_match) = query(None, Some("any")).to_model().unwrap();
+
+        assert!(tags.is_empty());
+        assert_eq!(TagMatch::Any, tag_match);
+    }
+
+    #[test]
+    fn city_list_query_rejects_unknown_match_and_bad_tags() {
+        assert!(matches!(query(Some("beach"), Some("some")).to_model(), Err(Error::BadRequest(_))));
+        assert!(matches!(query(Some("beach bar"), None).to_model(), Err(Error::BadRequest(_))));
+    }
+
+    fn alias(name: &str) -> CreateCityAliasDto {
+        CreateCityAliasDto {
+            name: name.to_string(),
+            language: "sr-Cyrl".to_string(),
+        }
+    }
+
+    #[test]
+    fn city_alias_name_length_counts_characters() {
+        // 20 characters, but 40 bytes
+        let name = "Београдбеоградбеогра";
+
+        assert_eq!(name, alias(name).to_model(1).unwrap().name);
+        assert!(matches!(alias(&"б".repeat(31)).to_model(1), Err(Error::BadRequest(_))));
+    }
+
+}
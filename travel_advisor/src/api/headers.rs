@@ -0,0 +1,31 @@
+use actix_web::{
+    http::header,
+    HttpRequest,
+};
+
+use crate::model::is_language_tag;
+
+/// Language tags from the `Accept-Language` header, most preferred first.
+/// Wildcards, invalid tags and tags with zero quality are left out.
+pub(super) fn accepted_languages(req: &HttpRequest) -> Vec<String> {
+    let value = match req.headers().get(header::ACCEPT_LANGUAGE).map(|h| h.to_str()) {
+        Some(Ok(value)) => value,
+        _ => return vec![],
+    };
+    let mut languages: Vec<(String, f32)> = value.split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            match is_language_tag(tag) && quality > 0.0 {
+                true => Some((tag.to_string(), quality)),
+                false => None,
+            }
+        })
+        .collect();
+    // stable, so that tags of equal quality keep the order they were sent in
+    languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+    languages.into_iter().map(|(tag, _)| tag).collect()
+}
@@ -1,11 +1,18 @@
 mod airport;
 mod city;
 mod comment;
+mod country;
 mod dtos;
+mod headers;
 mod hello;
+mod moderation;
+mod poi;
 mod users;
 mod validations;
 mod routes;
+mod search;
+
+mod dtos_test;
 
 pub fn init_hello(cfg: &mut actix_web::web::ServiceConfig) {
     hello::init(cfg);
@@ -15,6 +22,10 @@ pub fn init_cities(cfg: &mut actix_web::web::ServiceConfig) {
     city::init(cfg);
 }
 
+pub fn init_pois(cfg: &mut actix_web::web::ServiceConfig) {
+    poi::init(cfg);
+}
+
 pub fn init_users(cfg: &mut actix_web::web::ServiceConfig) {
     users::init(cfg);
 }
@@ -27,10 +38,22 @@ pub fn init_comments(cfg: &mut actix_web::web::ServiceConfig) {
     comment::init(cfg);
 }
 
+pub fn init_moderation(cfg: &mut actix_web::web::ServiceConfig) {
+    moderation::init(cfg);
+}
+
+pub fn init_countries(cfg: &mut actix_web::web::ServiceConfig) {
+    country::init(cfg);
+}
+
 pub fn init_routes(cfg: &mut actix_web::web::ServiceConfig) {
     routes::init(cfg);
 }
 
+pub fn init_search(cfg: &mut actix_web::web::ServiceConfig) {
+    search::init(cfg);
+}
+
 #[macro_use]
 pub mod auth_macro {
 
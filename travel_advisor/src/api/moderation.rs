@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    post,
+    web::{
+        self,
+        Data,
+    },
+    Responder,
+    HttpRequest,
+    HttpResponse,
+};
+
+use crate::{
+    AuthService,
+    services::traits::ModerationService,
+    util::Error,
+};
+use super::{
+    get_user_if_has_roles,
+    dtos::{
+        FromModel,
+        CommentFlagDto,
+        FlagCommentDto,
+        FlaggedCommentDto,
+        ModerateCommentDto,
+        ModerationRecordDto,
+        ModerationRecordsQueryParam,
+    },
+    validations::get_number,
+};
+
+pub(super) fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(flag_comment)
+        .service(
+            web::scope("/v1/moderation")
+                .service(get_queue)
+                .service(moderate_comment)
+                .service(get_records)
+        );
+}
+
+/// Reports the comment to moderators
+#[post("/v1/comments/{comment_id}/flags")]
+async fn flag_comment(
+    req: HttpRequest,
+    comment_id: web::Path<String>,
+    payload: web::Json<FlagCommentDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    moderation_service: Data<Arc<dyn ModerationService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    let user = get_user_if_has_roles!(req, auth_service, vec!["admin", "moderator", "user"]);
+    let comment_id = get_number!(comment_id, i64, true);
+    let flag = moderation_service.flag(payload.to_model(comment_id, user.id)?)?;
+    Ok(HttpResponse::Created().json(CommentFlagDto::from_model(&flag)))
+}
+
+/// Lists flagged comments waiting for a decision
+#[get("/queue")]
+async fn get_queue(
+    req: HttpRequest,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    moderation_service: Data<Arc<dyn ModerationService + Send + Sync>>,
+) -> Result<web::Json<Vec<FlaggedCommentDto>>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin", "moderator"]);
+    let queue = moderation_service.queue()?;
+    Ok(web::Json(queue.iter().map(FlaggedCommentDto::from_model).collect()))
+}
+
+#[post("/comments/{comment_id}")]
+async fn moderate_comment(
+    req: HttpRequest,
+    comment_id: web::Path<String>,
+    payload: web::Json<ModerateCommentDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    moderation_service: Data<Arc<dyn ModerationService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    let moderator = get_user_if_has_roles!(req, auth_service, vec!["admin", "moderator"]);
+    let comment_id = get_number!(comment_id, i64, true);
+    moderation_service.moderate(comment_id, payload.to_model()?, moderator)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Audit of moderator decisions, newest first, optionally about a single comment
+#[get("/records")]
+async fn get_records(
+    req: HttpRequest,
+    query: web::Query<ModerationRecordsQueryParam>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    moderation_service: Data<Arc<dyn ModerationService + Send + Sync>>,
+) -> Result<web::Json<Vec<ModerationRecordDto>>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin", "moderator"]);
+    let comment_id = match &query.comment_id {
+        Some(comment_id) => Some(get_number!(comment_id, i64, true)),
+        None => None,
+    };
+    let records = moderation_service.records(comment_id)?;
+    Ok(web::Json(records.iter().map(ModerationRecordDto::from_model).collect()))
+}
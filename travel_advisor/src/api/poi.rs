@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use actix_web::{
+    delete,
+    get,
+    post,
+    put,
+    web::{
+        self,
+        Data,
+    },
+    Responder,
+    HttpRequest,
+    HttpResponse,
+};
+
+use crate::{
+    services::traits::{
+        AuthService,
+        PoiService,
+    },
+    util::Error,
+};
+use super::{
+    get_user_if_has_roles,
+    dtos::{
+        FromModel,
+        PoiDto,
+        PoiQueryParam,
+        SavePoiDto,
+    },
+    validations::get_number,
+};
+
+pub(super) fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_city_pois)
+        .service(get_city_poi)
+        .service(create_city_poi)
+        .service(update_city_poi)
+        .service(delete_city_poi);
+}
+
+/// Lists points of interest of the city, optionally only those in the given categories
+#[get("/v1/cities/{id}/pois")]
+async fn get_city_pois(
+    id: web::Path<String>,
+    query: web::Query<PoiQueryParam>,
+    poi_service: Data<Arc<dyn PoiService + Send + Sync>>,
+) -> Result<web::Json<Vec<PoiDto>>, Error> {
+    let city_id = get_number!(id, i64, true);
+    let categories = query.to_model()?;
+    let pois = poi_service.list_for_city(city_id, categories)?;
+    Ok(web::Json(pois.iter().map(PoiDto::from_model).collect()))
+}
+
+#[get("/v1/cities/{id}/pois/{poi_id}")]
+async fn get_city_poi(
+    path: web::Path<(String, String)>,
+    poi_service: Data<Arc<dyn PoiService + Send + Sync>>,
+) -> Result<web::Json<PoiDto>, Error> {
+    let city_id = get_number!(path.0, i64, true);
+    let poi_id = get_number!(path.1, i64, true);
+    match poi_service.get_by_id(city_id, poi_id)? {
+        Some(poi) => Ok(web::Json(PoiDto::from_model(&poi))),
+        None => Err(Error::not_found("point of interest not found".to_string())),
+    }
+}
+
+#[post("/v1/cities/{id}/pois")]
+async fn create_city_poi(
+    req: HttpRequest,
+    id: web::Path<String>,
+    body: web::Json<SavePoiDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    poi_service: Data<Arc<dyn PoiService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(id, i64, true);
+    let poi = poi_service.create(body.to_model(0, city_id)?)?;
+    Ok(HttpResponse::Created().json(PoiDto::from_model(&poi)))
+}
+
+#[put("/v1/cities/{id}/pois/{poi_id}")]
+async fn update_city_poi(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Json<SavePoiDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    poi_service: Data<Arc<dyn PoiService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(path.0, i64, true);
+    let poi_id = get_number!(path.1, i64, true);
+    poi_service.update(body.to_model(poi_id, city_id)?)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/v1/cities/{id}/pois/{poi_id}")]
+async fn delete_city_poi(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    poi_service: Data<Arc<dyn PoiService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let city_id = get_number!(path.0, i64, true);
+    let poi_id = get_number!(path.1, i64, true);
+    poi_service.delete(city_id, poi_id)?;
+    Ok(HttpResponse::Ok().finish())
+}
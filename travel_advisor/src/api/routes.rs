@@ -14,37 +14,80 @@ use actix_web::{
     put,
 };
 
+use chrono::{
+    DateTime,
+    Duration,
+    Utc,
+};
+
 use crate::{
-    model::Route,
+    model::{
+        Objective,
+        Route,
+        RouteConstraints,
+    },
     services::traits::{
+        AirportService,
         AuthService,
+        CityService,
         RouteService,
     },
     util::Error
 };
 
+/// Upper limit for `count` in cheapest path requests
+const MAX_ALTERNATIVE_PATHS: usize = 10;
+
+/// Upper limit for the number of cities a tour may visit
+const MAX_TOUR_CITIES: usize = 20;
+
+/// Time needed to change flights when the request does not say otherwise
+const DEFAULT_MIN_CONNECTION_MINUTES: i64 = 60;
+
 use super::{
     get_user_if_has_roles,
     dtos::{
         FromModel,
+        ToModel,
+        AirportRefDto,
         BestPathDto,
         CalculateCheapestRouteRequestDto,
         PaginationQueryParam,
+        ParetoPathDto,
+        ReachableCityDto,
+        ReachableQueryParam,
+        RoundTripDto,
+        RoundTripRequestDto,
         RouteDto,
         SaveRouteDto,
+        SaveScheduleDto,
+        ScheduleDto,
+        SearchMode,
+        tag_of,
+        TourDto,
+        TourRequestDto,
     },
+    headers::accepted_languages,
     validations::get_number,
 };
 
 pub(super) fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/v1/routes")
+            // registered before "/{id}" so that it is not taken for a route ID
+            .service(find_reachable_cities)
             .service(get_all)
             .service(find_by_id)
             .service(save_routes)
             .service(update_route)
             .service(delete_route)
             .service(find_cheapest_route)
+            .service(find_pareto_routes)
+            .service(find_round_trips)
+            .service(plan_tour)
+            .service(get_schedules)
+            .service(add_schedule)
+            .service(delete_schedule)
     );
 }
 
@@ -104,15 +147,16 @@ async fn update_route(
     id: web::Path<String>,
     body: web::Json<SaveRouteDto>,
     auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
     route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
 ) -> Result<impl Responder, Error> {
     get_user_if_has_roles!(req, auth_service, vec!["admin"]);
     let route_id = get_number!(id.to_string(), i64);
     let route = Route {
         id: route_id,
-        start: body.start.clone(),
-        finish: body.finish.clone(),
-        price: body.price.clone(),
+        start: airport_service.resolve(&body.start.to_model()?)?,
+        finish: airport_service.resolve(&body.finish.to_model()?)?,
+        price: body.price,
     };
     match route_service.update(route) {
         Ok(()) => Ok(HttpResponse::Ok().finish()),
@@ -140,18 +184,250 @@ async fn find_cheapest_route(
     req: HttpRequest,
     auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
     body: web::Json<CalculateCheapestRouteRequestDto>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
     route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
-) -> Result<web::Json<BestPathDto>, Error> {
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<Vec<BestPathDto>>, Error> {
     get_user_if_has_roles!(req, auth_service, vec!["admin"]);
-    let (routes, airports, cities) = match route_service.find_cheapest_route(
-        body.starting_city_id.clone(),
-        body.destination_city_id.clone()
+    let count = count_of(body.count)?;
+    let (start, finish) = body.places()?;
+    let objective = objective_of(&body)?;
+    let (itineraries, airports, mut cities) = match route_service.find_best_routes(
+        start,
+        finish,
+        count,
+        constraints_of(body.to_model(), &body.excluded_airport_ids, &airport_service)?,
+        objective,
     ) {
-        Ok((r, a, c)) => (r, a, c),
+        Ok((i, a, c)) => (i, a, c),
         Err(err) => return Err(err),
     };
-    match BestPathDto::from_models(routes, airports, cities) {
-        Ok(path) => Ok(web::Json(path)),
+    city_service.localize(&mut cities, &accepted_languages(&req))?;
+    match itineraries.iter()
+        .map(|i| BestPathDto::from_models(i, &airports, &cities))
+        .collect::<Result<Vec<BestPathDto>, Error>>() {
+            Ok(paths) => Ok(web::Json(paths)),
+            Err(err) => Err(err),
+        }
+}
+
+/// Returns every path that no other path beats on price, number of legs and,
+/// in `earliest-arrival` mode, travel time all at once
+#[post("/pareto-paths")]
+async fn find_pareto_routes(
+    req: HttpRequest,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    body: web::Json<CalculateCheapestRouteRequestDto>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<Vec<ParetoPathDto>>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let objective = objective_of(&body)?;
+    let (start, finish) = body.places()?;
+    let (front, airports, mut cities) = route_service.find_pareto_routes(
+        start,
+        finish,
+        constraints_of(body.to_model(), &body.excluded_airport_ids, &airport_service)?,
+        objective,
+    )?;
+    city_service.localize(&mut cities, &accepted_languages(&req))?;
+    match front.iter()
+        .map(|p| ParetoPathDto::from_models(p, &airports, &cities))
+        .collect::<Result<Vec<ParetoPathDto>, Error>>() {
+            Ok(paths) => Ok(web::Json(paths)),
+            Err(err) => Err(err),
+        }
+}
+
+/// Prices an outbound and a return path as one itinerary. The return may start
+/// and end in different cities than the outbound path (open-jaw).
+#[post("/round-trip")]
+async fn find_round_trips(
+    req: HttpRequest,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    body: web::Json<RoundTripRequestDto>,
+    airport_service: Data<Arc<dyn AirportService + Send + Sync>>,
+    route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<Vec<RoundTripDto>>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let count = count_of(body.count)?;
+    let (trips, airports, mut cities) = route_service.find_round_trips(
+        (body.starting_city_id, body.destination_city_id),
+        (
+            body.return_from_city_id.unwrap_or(body.destination_city_id),
+            body.return_to_city_id.unwrap_or(body.starting_city_id),
+        ),
+        count,
+        constraints_of(body.to_model(), &body.excluded_airport_ids, &airport_service)?,
+    )?;
+    city_service.localize(&mut cities, &accepted_languages(&req))?;
+    match trips.iter()
+        .map(|t| RoundTripDto::from_models(t, &airports, &cities))
+        .collect::<Result<Vec<RoundTripDto>, Error>>() {
+            Ok(trips) => Ok(web::Json(trips)),
+            Err(err) => Err(err),
+        }
+}
+
+/// Orders cities to visit after leaving the origin so that the trip is cheap.
+/// Every leg is the cheapest path between two consecutive cities.
+#[post("/tour")]
+async fn plan_tour(
+    req: HttpRequest,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    body: web::Json<TourRequestDto>,
+    route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<TourDto>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    if body.city_ids.is_empty() || body.city_ids.len() > MAX_TOUR_CITIES {
+        return Err(Error::bad_request(format!("between 1 and {} cities must be visited", MAX_TOUR_CITIES)));
+    }
+    for (index, city_id) in body.city_ids.iter().enumerate() {
+        if *city_id == body.origin_city_id {
+            return Err(Error::bad_request(format!("origin city {} cannot be listed as a destination", city_id)));
+        }
+        if body.city_ids[..index].contains(city_id) {
+            return Err(Error::bad_request(format!("city {} is listed more than once", city_id)));
+        }
+    }
+    let (tour, airports, mut cities) = route_service.plan_tour(
+        body.origin_city_id,
+        body.city_ids.clone(),
+        body.return_to_origin.unwrap_or(false),
+    )?;
+    city_service.localize(&mut cities, &accepted_languages(&req))?;
+    match TourDto::from_models(&tour, &airports, &cities) {
+        Ok(tour) => Ok(web::Json(tour)),
         Err(err) => Err(err),
     }
-}
\ No newline at end of file
+}
+
+/// Lists every city reachable from `from` for at most `budget`, cheapest first,
+/// optionally only those with `tag`
+#[get("/anywhere")]
+async fn find_reachable_cities(
+    req: HttpRequest,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    query: web::Query<ReachableQueryParam>,
+    route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
+    city_service: Data<Arc<dyn CityService + Send + Sync>>,
+) -> Result<web::Json<Vec<ReachableCityDto>>, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let start = get_number!(query.from, i64, true);
+    let budget = get_number!(query.budget, i64, true);
+    let tag = match &query.tag {
+        Some(tag) => Some(tag_of(tag)?),
+        None => None,
+    };
+    let (reachable, mut cities) = route_service.find_reachable_cities(start, budget, tag)?;
+    city_service.localize(&mut cities, &accepted_languages(&req))?;
+    match reachable.iter()
+        .map(|r| ReachableCityDto::from_models(r, &cities))
+        .collect::<Result<Vec<ReachableCityDto>, Error>>() {
+            Ok(reachable) => Ok(web::Json(reachable)),
+            Err(err) => Err(err),
+        }
+}
+
+/// Adds excluded airports, which may be given by code, to the constraints
+fn constraints_of(
+    mut constraints: RouteConstraints,
+    excluded_airports: &Option<Vec<AirportRefDto>>,
+    airport_service: &Arc<dyn AirportService + Send + Sync>,
+) -> Result<RouteConstraints, Error> {
+    for reference in excluded_airports.iter().flatten() {
+        constraints.excluded_airport_ids.push(airport_service.resolve(&reference.to_model()?)?);
+    }
+    Ok(constraints)
+}
+
+fn count_of(count: Option<usize>) -> Result<usize, Error> {
+    let count = count.unwrap_or(1);
+    if count == 0 || count > MAX_ALTERNATIVE_PATHS {
+        return Err(Error::bad_request(format!("count must be between 1 and {}", MAX_ALTERNATIVE_PATHS)));
+    }
+    Ok(count)
+}
+
+fn objective_of(body: &CalculateCheapestRouteRequestDto) -> Result<Objective, Error> {
+    match body.mode.unwrap_or(SearchMode::Cheapest) {
+        SearchMode::Cheapest => Ok(Objective::Cheapest),
+        SearchMode::ShortestDistance => Ok(Objective::ShortestDistance),
+        SearchMode::EarliestArrival => {
+            let departure = match &body.departure_after {
+                Some(value) => match DateTime::parse_from_rfc3339(value) {
+                    Ok(time) => time.naive_utc(),
+                    Err(err) => return Err(Error::bad_request(format!("departure_after is not an RFC 3339 timestamp: {}", err))),
+                },
+                None => Utc::now().naive_utc(),
+            };
+            let min_connection = body.min_connection_minutes.unwrap_or(DEFAULT_MIN_CONNECTION_MINUTES);
+            if min_connection < 0 {
+                return Err(Error::bad_request("min_connection_minutes must not be negative".to_string()));
+            }
+            Ok(Objective::EarliestArrival {
+                departure,
+                min_connection: Duration::minutes(min_connection),
+            })
+        },
+    }
+}
+
+#[get("/{id}/schedules")]
+async fn get_schedules(
+    id: web::Path<String>,
+    route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
+) -> Result<web::Json<Vec<ScheduleDto>>, Error> {
+    let route_id = get_number!(id.to_string(), i64, true);
+    let (departure_tz, arrival_tz) = route_service.time_zones_of(route_id)?;
+    match route_service.get_schedules(route_id) {
+        Ok(schedules) => Ok(web::Json(
+            schedules.iter()
+                .map(|s| ScheduleDto::from_model_in_zones(s, departure_tz, arrival_tz))
+                .collect()
+        )),
+        Err(err) => Err(err),
+    }
+}
+
+#[post("/{id}/schedules")]
+async fn add_schedule(
+    req: HttpRequest,
+    id: web::Path<String>,
+    body: web::Json<SaveScheduleDto>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let route_id = get_number!(id.to_string(), i64, true);
+    let schedule = match body.to_model(route_id) {
+        Ok(schedule) => schedule,
+        Err(err) => return Err(err),
+    };
+    match route_service.add_schedule(schedule) {
+        Ok(schedule) => {
+            let (departure_tz, arrival_tz) = route_service.time_zones_of(route_id)?;
+            Ok(HttpResponse::Created().json(ScheduleDto::from_model_in_zones(&schedule, departure_tz, arrival_tz)))
+        },
+        Err(err) => Err(err),
+    }
+}
+
+#[delete("/{id}/schedules/{schedule_id}")]
+async fn delete_schedule(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    auth_service: Data<Arc<dyn AuthService + Send + Sync>>,
+    route_service: web::Data<Arc<dyn RouteService + Send + Sync>>,
+) -> Result<impl Responder, Error> {
+    get_user_if_has_roles!(req, auth_service, vec!["admin"]);
+    let route_id = get_number!(path.0, i64, true);
+    let schedule_id = get_number!(path.1, i64, true);
+    match route_service.delete_schedule(route_id, schedule_id) {
+        Ok(()) => Ok(HttpResponse::Ok().finish()),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use actix_web::{
+    get,
+    web::{
+        self,
+        Data,
+    },
+};
+
+use crate::{
+    services::traits::SearchService,
+    util::Error,
+};
+use super::{
+    dtos::{
+        FromModel,
+        SearchQueryParam,
+        SearchResultsDto,
+    },
+    validations::get_number,
+};
+
+const DEFAULT_SEARCH_LIMIT: usize = 5;
+const MAX_SEARCH_LIMIT: usize = 20;
+const MAX_QUERY_LENGTH: usize = 60;
+
+pub(super) fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(search);
+}
+
+/// Autocomplete for cities and airports by name prefix, by name with typos and by airport code
+#[get("/v1/search")]
+async fn search(
+    query: web::Query<SearchQueryParam>,
+    search_service: Data<Arc<dyn SearchService + Send + Sync>>,
+) -> Result<web::Json<SearchResultsDto>, Error> {
+    // check params
+    let text = query.q.trim();
+    if text.is_empty() || text.chars().count() > MAX_QUERY_LENGTH {
+        return Err(Error::bad_request(format!("q must have between 1 and {} characters", MAX_QUERY_LENGTH)));
+    }
+    let limit = match &query.limit {
+        Some(limit) => get_number!(limit, usize, true),
+        None => DEFAULT_SEARCH_LIMIT,
+    };
+    if limit > MAX_SEARCH_LIMIT {
+        return Err(Error::bad_request(format!("limit must be at most {}", MAX_SEARCH_LIMIT)));
+    }
+    // search
+    let results = search_service.search(text, limit)?;
+    Ok(web::Json(SearchResultsDto::from_model(&results)))
+}
@@ -37,4 +37,4 @@ pub mod validation_macros {
     }
 }
 
-pub(super) use get_number;
\ No newline at end of file
+pub(super) use get_number;
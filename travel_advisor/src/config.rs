@@ -16,10 +16,33 @@ struct DaoConfig {
     database: String,
 }
 
+#[derive(Deserialize)]
+struct ContentPolicyConfig {
+    #[serde(default)]
+    banned_words: Vec<String>,
+    #[serde(default = "default_max_links")]
+    max_links: usize,
+}
+
+fn default_max_links() -> usize {
+    2
+}
+
+impl Default for ContentPolicyConfig {
+    fn default() -> Self {
+        ContentPolicyConfig {
+            banned_words: vec![],
+            max_links: default_max_links(),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct Config {
     app: AppConfig,
     dao: DaoConfig,
+    #[serde(default)]
+    content_policy: ContentPolicyConfig,
 }    
 
 impl Config {
@@ -42,4 +65,14 @@ impl Config {
     pub fn key(&self) -> String {
         self.app.key.clone()
     }
+
+    /// Words not allowed in comments
+    pub fn banned_words(&self) -> Vec<String> {
+        self.content_policy.banned_words.clone()
+    }
+
+    /// Most links a comment may contain
+    pub fn max_links(&self) -> usize {
+        self.content_policy.max_links
+    }
 }
@@ -31,13 +31,22 @@ use crate::{
         new_auth_service,
         new_city_service,
         new_comment_service,
+        new_country_service,
+        new_default_content_policy,
+        new_moderation_service,
+        new_poi_service,
         new_route_service,
+        new_search_service,
         traits::{
             AirportService,
             AuthService,
             CityService,
             CommentService,
+            CountryService,
+            ModerationService,
+            PoiService,
             RouteService,
+            SearchService,
         },
     },
     storage::{
@@ -45,12 +54,22 @@ use crate::{
         AirportRepository,
         CityRepository,
         CommentRepository,
+        CountryRepository,
+        ModerationRepository,
+        PoiRepository,
         UserRepository,
+        ScheduleRepository,
+        TransferRepository,
         routes::RouteRepository,
         new_airport_repository,
         new_city_repository,
         new_comment_repository,
+        new_country_repository,
+        new_moderation_repository,
+        new_poi_repository,
         new_user_repository,
+        new_schedule_repository,
+        new_transfer_repository,
         routes::new_route_repository,
     },
 };
@@ -84,25 +103,61 @@ async fn main() -> std::io::Result<()>{
     let airport_repo: Arc<dyn AirportRepository + Sync + Send> = new_airport_repository(db_arc.clone());
     let city_repo: Arc<dyn CityRepository + Sync + Send> = new_city_repository(db_arc.clone());
     let comment_repo: Arc<dyn CommentRepository + Sync + Send> = new_comment_repository(db_arc.clone());
+    let country_repo: Arc<dyn CountryRepository + Sync + Send> = new_country_repository(db_arc.clone());
+    let moderation_repo: Arc<dyn ModerationRepository + Sync + Send> = new_moderation_repository(db_arc.clone());
+    let poi_repo: Arc<dyn PoiRepository + Sync + Send> = new_poi_repository(db_arc.clone());
     let user_repo: Arc<dyn UserRepository + Sync + Send> = new_user_repository(db_arc.clone());
     let route_repo: Arc<dyn RouteRepository + Sync + Send> = new_route_repository(db_arc.clone());
+    let schedule_repo: Arc<dyn ScheduleRepository + Sync + Send> = new_schedule_repository(db_arc.clone());
+    let transfer_repo: Arc<dyn TransferRepository + Sync + Send> = new_transfer_repository(db_arc.clone());
 
     let auth_service = new_auth_service(config.key(), user_repo.clone()).expect("could not instantiate auth service");
     let auth_service_data: Data<Arc<dyn AuthService + Send + Sync>> = Data::new(auth_service.clone());
 
-    let airport_service = new_airport_service(city_repo.clone(), airport_repo.clone());
+    let search_service = new_search_service(city_repo.clone(), airport_repo.clone());
+    let search_service_data: Data<Arc<dyn SearchService + Send + Sync>> = Data::new(search_service.clone());
+
+    let airport_service = new_airport_service(
+        city_repo.clone(),
+        airport_repo.clone(),
+        transfer_repo.clone(),
+        search_service.clone(),
+    );
     let airport_service_data: Data<Arc<dyn AirportService + Send + Sync>> = Data::new(airport_service.clone());
 
-    let city_service = new_city_service(city_repo.clone(), airport_repo.clone());
+    let city_service = new_city_service(
+        city_repo.clone(),
+        airport_repo.clone(),
+        country_repo.clone(),
+        route_repo.clone(),
+        comment_repo.clone(),
+        poi_repo.clone(),
+        search_service.clone(),
+    );
     let city_service_data: Data<Arc<dyn CityService + Send + Sync>> = Data::new(city_service.clone());
 
-    let comment_service = new_comment_service(comment_repo.clone());
+    let country_service = new_country_service(country_repo.clone(), city_repo.clone());
+    let country_service_data: Data<Arc<dyn CountryService + Send + Sync>> = Data::new(country_service.clone());
+
+    let content_policy = new_default_content_policy(config.banned_words(), config.max_links());
+    let comment_service = new_comment_service(comment_repo.clone(), content_policy.clone());
     let comment_service_data: Data<Arc<dyn CommentService + Send + Sync>> = Data::new(comment_service.clone());
 
+    let moderation_service = new_moderation_service(
+        comment_repo.clone(),
+        moderation_repo.clone(),
+    );
+    let moderation_service_data: Data<Arc<dyn ModerationService + Send + Sync>> = Data::new(moderation_service.clone());
+
+    let poi_service = new_poi_service(city_repo.clone(), poi_repo.clone());
+    let poi_service_data: Data<Arc<dyn PoiService + Send + Sync>> = Data::new(poi_service.clone());
+
     let route_service = new_route_service(
         route_repo.clone(),
         airport_repo.clone(),
-        city_repo.clone()
+        city_repo.clone(),
+        schedule_repo.clone(),
+        transfer_repo.clone(),
     );
     let route_service_data: Data<Arc<dyn RouteService + Send + Sync>> = Data::new(route_service.clone());
 
@@ -116,16 +171,25 @@ async fn main() -> std::io::Result<()>{
             .app_data(auth_service_data.clone())
             .app_data(city_service_data.clone())
             .app_data(comment_service_data.clone())
+            .app_data(country_service_data.clone())
+            .app_data(moderation_service_data.clone())
+            .app_data(poi_service_data.clone())
             .app_data(user_repo_data.clone())
             .app_data(route_service_data.clone())
+            .app_data(search_service_data.clone())
             .wrap(RequestId)
             //.wrap(jwt_extractor)
             .configure(crate::api::init_hello)
             .configure(crate::api::init_cities)
+            .configure(crate::api::init_pois)
             .configure(crate::api::init_users)
             .configure(crate::api::init_airports)
-            .configure(crate::api::init_comments)
+            .configure(crate::api::init_countries)
             .configure(crate::api::init_routes)
+            .configure(crate::api::init_search)
+            .configure(crate::api::init_moderation)
+            // comments are served from a catch-all /v1 scope, keep them last
+            .configure(crate::api::init_comments)
         }
     ).bind(config.get_app_url())?;
 
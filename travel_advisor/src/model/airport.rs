@@ -1,6 +1,50 @@
+use super::geo::Coordinates;
+
 #[derive(Clone)]
 pub struct Airport {
     pub id: i64,
     pub city_id: i64,
-    pub name: String
+    pub name: String,
+    pub location: Option<Coordinates>,
+    pub iata_code: Option<String>,
+    pub icao_code: Option<String>,
+}
+
+impl Airport {
+    /// Great-circle distance in meters, if both airports have known coordinates
+    pub fn distance_to(&self, other: &Airport) -> Option<f64> {
+        Some(self.location?.distance_to(&other.location?))
+    }
+}
+
+/// Three uppercase letters, like `BEG`
+pub fn is_iata_code(code: &str) -> bool {
+    code.len() == 3 && code.bytes().all(|b| b.is_ascii_uppercase())
+}
+
+/// Four uppercase letters or digits, like `LYBE`
+pub fn is_icao_code(code: &str) -> bool {
+    code.len() == 4 && code.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+}
+
+/// Airport as referred to by clients, either by ID or by IATA or ICAO code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AirportRef {
+    Id(i64),
+    Code(String),
+}
+
+impl AirportRef {
+    /// Reads a numeric ID or a code in any letter case. Returns `None` for anything else.
+    pub fn parse(value: &str) -> Option<AirportRef> {
+        let value = value.trim();
+        if let Ok(id) = value.parse::<i64>() {
+            return Some(AirportRef::Id(id));
+        }
+        let code = value.to_ascii_uppercase();
+        match is_iata_code(&code) || is_icao_code(&code) {
+            true => Some(AirportRef::Code(code)),
+            false => None,
+        }
+    }
 }
@@ -8,8 +8,8 @@ mod com_tests {
     #[test]
     fn test_index() {
         let mut q = Airports::new();
-        q <<= Airport {id: 1, name: "1".to_string(), city_id: 1};
-        q <<= Airport {id: 2, name: "2".to_string(), city_id: 2};
+        q <<= Airport {id: 1, name: "1".to_string(), city_id: 1, location: None, iata_code: None, icao_code: None};
+        q <<= Airport {id: 2, name: "2".to_string(), city_id: 2, location: None, iata_code: None, icao_code: None};
         assert_eq!(2, q.len());
         let a = &q[0];
         assert_eq!(1, a.id.clone());
@@ -1,155 +1,808 @@
-use std::sync::Arc;
-
-use crate::{
-    util::Error,
-    storage::{
-        routes::RouteRepository,
-        AirportRepository,
+use std::{
+    cmp::Reverse,
+    collections::{
+        BinaryHeap,
+        HashMap,
+        HashSet,
     },
 };
-use super::Route;
-
-pub struct BestRoute {
-    lowest_price: Option<i64>,
-    best_path: Option<Vec<i64>>,
-    expanded_during_this_turn: bool,
-    destination: Vec<i64>,
-    route_repo: Arc<dyn RouteRepository + Sync + Send>,
-    airport_repo: Arc<dyn AirportRepository + Sync + Send>,
-}
-
-pub struct Stop {
-    accumulated_price: i64,
-    incomming_route: Option<Box<Route>>,
-    destinations: Option<Vec<Box<Stop>>>,
-    dead_end: bool,
-}
-
-pub struct DoExpandInput {
-    previous_stops: Box<Vec<i64>>,
-    accumulated_price: i64,
-}
-
-impl BestRoute {
-    pub fn new(
-        start_id: i64,
-        destination: Vec<i64>,
-        route_repo: Arc<dyn RouteRepository + Sync + Send>,
-        airport_repo: Arc<dyn AirportRepository + Sync + Send>,
-    ) -> (BestRoute, Stop) {
-        (BestRoute {
-            lowest_price: None,
-            best_path: None,
-            destination: destination,
-            expanded_during_this_turn: false,
-            route_repo: route_repo,
-            airport_repo: airport_repo,
-        }, Stop {
-            accumulated_price: 0,
-            dead_end: false,
-            destinations: None,
-            incomming_route: Some(Box::new(Route{
-                id: 0,
-                price: 0,
-                start: 0,
-                finish: start_id,
-            })),
+
+use chrono::{
+    Duration,
+    NaiveDateTime,
+};
+
+use super::{
+    Airport,
+    Route,
+    Schedule,
+    Transfer,
+    tour,
+};
+
+/// In-memory graph of airports connected by routes.
+///
+/// Airports are nodes and every route is an edge from its `start` airport to its `finish`
+/// airport, weighted by price. After landing, a traveller may depart from any airport of the
+/// same city, so the edges leaving an airport are all routes departing from its city.
+/// Getting to another airport costs as much as the transfer between them, or nothing if
+/// no transfer is known.
+pub struct RouteGraph {
+    airports: HashMap<i64, Airport>,
+    airports_by_city: HashMap<i64, Vec<i64>>,
+    departures: HashMap<i64, Vec<Route>>,
+    schedules: HashMap<i64, Vec<Schedule>>,
+    transfers: HashMap<(i64, i64), Transfer>,
+}
+
+/// Flights of a found path in travel order, together with the total price
+#[derive(Clone)]
+pub struct Itinerary {
+    pub routes: Vec<Route>,
+    pub price: i64,
+    /// Departure and arrival of every flight, filled only when searching by schedule
+    pub times: Vec<FlightTimes>,
+    /// Known transfers taken between flights landing and departing at different airports
+    pub transfers: Vec<Transfer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlightTimes {
+    pub departure: NaiveDateTime,
+    pub arrival: NaiveDateTime,
+}
+
+/// Value a route search minimizes
+#[derive(Clone, Copy)]
+pub enum Objective {
+    Cheapest,
+    /// Arrive as soon as possible when leaving no sooner than `departure` and having
+    /// at least `min_connection` between landing and the next flight.
+    /// Routes without schedules are not used.
+    EarliestArrival {
+        departure: NaiveDateTime,
+        min_connection: Duration,
+    },
+    /// Fly as few meters as possible. Routes between airports without coordinates are not used.
+    ShortestDistance,
+}
+
+/// Outbound and return itineraries priced together
+pub struct RoundTrip {
+    pub outbound: Itinerary,
+    pub inbound: Itinerary,
+    pub price: i64,
+}
+
+/// Cheapest paths between consecutive cities of a tour, in visiting order
+pub struct Tour {
+    pub legs: Vec<Itinerary>,
+    pub price: i64,
+}
+
+/// City reachable within a budget, with the cheapest way to get there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReachableCity {
+    pub city_id: i64,
+    pub price: i64,
+    /// Flights taken by the cheapest path, fewest of them when several paths cost the same
+    pub flights: usize,
+}
+
+/// Measure a Pareto-optimal itinerary can be the best at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    Price,
+    Legs,
+    /// Time from the requested departure until arrival
+    Time,
+}
+
+/// Itinerary not worse than any other in all criteria at once
+pub struct ParetoItinerary {
+    pub itinerary: Itinerary,
+    /// Minutes from the requested departure until arrival, known only when searching by schedule
+    pub travel_minutes: Option<i64>,
+    /// Criteria no other itinerary of the front is better at
+    pub wins: Vec<Criterion>,
+}
+
+/// Optional limits a route search has to respect
+#[derive(Default, Clone)]
+pub struct RouteConstraints {
+    pub max_flights: Option<usize>,
+    pub max_price: Option<i64>,
+    pub excluded_city_ids: Vec<i64>,
+    pub excluded_airport_ids: Vec<i64>,
+    /// Cities the path has to land in, in the given order
+    pub via_city_ids: Vec<i64>,
+}
+
+impl RouteConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.max_flights.is_none()
+            && self.max_price.is_none()
+            && self.excluded_city_ids.is_empty()
+            && self.excluded_airport_ids.is_empty()
+            && self.via_city_ids.is_empty()
+    }
+
+    /// Explains why no path between the two cities can satisfy the constraints,
+    /// when that is obvious without searching.
+    pub fn contradiction(&self, start_city_id: i64, finish_city_id: i64) -> Option<String> {
+        if self.excluded_city_ids.contains(&start_city_id) {
+            return Some("starting city is excluded".to_string());
+        }
+        if self.excluded_city_ids.contains(&finish_city_id) {
+            return Some("destination city is excluded".to_string());
+        }
+        for (index, via_id) in self.via_city_ids.iter().enumerate() {
+            if self.excluded_city_ids.contains(via_id) {
+                return Some(format!("via city {} is excluded", via_id));
+            }
+            if *via_id == start_city_id || *via_id == finish_city_id {
+                return Some(format!("via city {} is the starting or destination city", via_id));
+            }
+            if self.via_city_ids[..index].contains(via_id) {
+                return Some(format!("via city {} is listed more than once", via_id));
+            }
+        }
+        if self.max_flights.is_some_and(|max| max <= self.via_city_ids.len()) {
+            return Some(format!("at least {} flights are needed", self.via_city_ids.len() + 1));
+        }
+        if self.max_price.is_some_and(|max| max < 0) {
+            return Some("maximum price is negative".to_string());
+        }
+        None
+    }
+}
+
+/// Search state: the airport a traveller is at and the progress made so far
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Stop {
+    airport_id: i64,
+    /// Number of via cities already visited
+    stage: usize,
+    /// Flights taken so far, tracked only when the number of flights is limited
+    flights: usize,
+}
+
+/// Cost minimized by the search next to the price paid so far
+#[derive(Default, Clone, Copy)]
+struct Label {
+    cost: i64,
+    price: i64,
+}
+
+/// Partial path kept by the Pareto search, linked to the label it was extended from
+struct ParetoLabel {
+    stop: Stop,
+    price: i64,
+    legs: usize,
+    minutes: i64,
+    arrival: Option<(Route, Option<FlightTimes>, usize)>,
+}
+
+impl ParetoLabel {
+    fn dominates(&self, other: &ParetoLabel) -> bool {
+        self.price <= other.price && self.legs <= other.legs && self.minutes <= other.minutes
+    }
+}
+
+/// Price paid to land at each airport, with the route landing there and the airport it was taken from
+type Arrivals = HashMap<i64, (i64, Option<(Route, i64)>)>;
+
+/// Edges a single search is not allowed to use
+#[derive(Default)]
+struct Restrictions {
+    /// Pairs of city ID and stage the path may not land in
+    landings: HashSet<(i64, usize)>,
+    routes: HashSet<i64>,
+}
+
+impl RouteGraph {
+    pub fn new(routes: Vec<Route>, airports: Vec<Airport>) -> RouteGraph {
+        let mut airports_by_city: HashMap<i64, Vec<i64>> = HashMap::new();
+        for airport in airports.iter() {
+            airports_by_city.entry(airport.city_id).or_default().push(airport.id);
+        }
+        let mut departures: HashMap<i64, Vec<Route>> = HashMap::new();
+        for route in routes {
+            departures.entry(route.start).or_default().push(route);
+        }
+        RouteGraph {
+            airports: airports.into_iter().map(|a| (a.id, a)).collect(),
+            airports_by_city,
+            departures,
+            schedules: HashMap::new(),
+            transfers: HashMap::new(),
+        }
+    }
+
+    pub fn with_schedules(mut self, schedules: Vec<Schedule>) -> RouteGraph {
+        for schedule in schedules {
+            self.schedules.entry(schedule.route_id).or_default().push(schedule);
+        }
+        self
+    }
+
+    pub fn with_transfers(mut self, transfers: Vec<Transfer>) -> RouteGraph {
+        for transfer in transfers {
+            self.transfers.insert((transfer.from_airport_id, transfer.to_airport_id), transfer);
+        }
+        self
+    }
+
+    pub fn airport(&self, id: i64) -> Option<&Airport> {
+        self.airports.get(&id)
+    }
+
+    /// Finds up to `count` loopless paths ordered by the objective, using Yen's algorithm.
+    ///
+    /// A path is loopless when it never lands in the same city twice between two via cities
+    /// and never comes back to the city it started from before the first via city.
+    pub fn best_paths(
+        &self,
+        sources: &[i64],
+        destinations: &[i64],
+        count: usize,
+        constraints: &RouteConstraints,
+        objective: &Objective,
+    ) -> Vec<Itinerary> {
+        let destinations: HashSet<i64> = destinations.iter().copied().collect();
+        let origins: Vec<(Stop, Label)> = sources.iter()
+            .map(|id| (Stop { airport_id: *id, stage: 0, flights: 0 }, Label::default()))
+            .collect();
+        let origin_cities: Vec<i64> = sources.iter().filter_map(|id| self.city_of(*id)).collect();
+        let mut found: Vec<Itinerary> = vec![];
+        let mut candidates: Vec<Itinerary> = vec![];
+        match self.search(&origins, false, &destinations, constraints, objective, &Restrictions::default()) {
+            Some(itinerary) => found.push(itinerary),
+            None => return found,
+        };
+
+        while found.len() < count {
+            let previous = found[found.len() - 1].clone();
+            for spur_index in 0..previous.routes.len() {
+                let root = &previous.routes[..spur_index];
+                let mut restrictions = Restrictions::default();
+                restrictions.landings.extend(origin_cities.iter().map(|c| (*c, 0)));
+                let mut spur_source = Stop { airport_id: 0, stage: 0, flights: 0 };
+                for route in root {
+                    spur_source = self.land(&spur_source, route, constraints);
+                    if let Some(city_id) = self.city_of(route.finish) {
+                        restrictions.landings.insert((city_id, spur_source.stage));
+                    }
+                }
+                restrictions.routes.extend(found.iter()
+                    .filter(|i| i.routes.len() > spur_index && i.routes[..spur_index] == *root)
+                    .map(|i| i.routes[spur_index].id));
+                let spur_sources = match root.is_empty() {
+                    true => origins.clone(),
+                    false => {
+                        let price = self.price_of(root);
+                        let cost = match objective {
+                            Objective::Cheapest => price,
+                            Objective::EarliestArrival { departure, .. } =>
+                                (previous.times[spur_index - 1].arrival - *departure).num_minutes(),
+                            Objective::ShortestDistance => self.distance_of(root).unwrap_or(0),
+                        };
+                        vec![(spur_source, Label { cost, price })]
+                    },
+                };
+                let spur = match self.search(
+                    &spur_sources,
+                    !root.is_empty(),
+                    &destinations,
+                    constraints,
+                    objective,
+                    &restrictions,
+                ) {
+                    Some(spur) => spur,
+                    None => continue,
+                };
+                let mut routes = root.to_vec();
+                routes.extend(spur.routes);
+                if found.iter().chain(candidates.iter()).any(|i| i.routes == routes) {
+                    continue;
+                }
+                let mut times = match previous.times.is_empty() {
+                    true => vec![],
+                    false => previous.times[..spur_index].to_vec(),
+                };
+                times.extend(spur.times);
+                candidates.push(self.itinerary(routes, spur.price, times));
+            }
+            let best = candidates.iter()
+                .enumerate()
+                .min_by_key(|(_, i)| (self.score(i, objective), i.price, i.routes.len()))
+                .map(|(index, _)| index);
+            match best {
+                Some(index) => found.push(candidates.swap_remove(index)),
+                None => break,
+            };
+        }
+
+        found
+    }
+
+    /// Finds up to `count` cheapest pairs of an outbound and a return itinerary.
+    ///
+    /// Maximum price in constraints limits the price of both itineraries together.
+    pub fn round_trips(
+        &self,
+        outbound: (&[i64], &[i64]),
+        inbound: (&[i64], &[i64]),
+        count: usize,
+        constraints: &RouteConstraints,
+    ) -> Vec<RoundTrip> {
+        let outbounds = self.best_paths(outbound.0, outbound.1, count, constraints, &Objective::Cheapest);
+        let inbounds = self.best_paths(inbound.0, inbound.1, count, constraints, &Objective::Cheapest);
+        let mut pairs: Vec<(usize, usize)> = (0..outbounds.len())
+            .flat_map(|o| (0..inbounds.len()).map(move |i| (o, i)))
+            .filter(|(o, i)| match constraints.max_price {
+                Some(max) => outbounds[*o].price + inbounds[*i].price <= max,
+                None => true,
+            })
+            .collect();
+        pairs.sort_by_key(|(o, i)| (outbounds[*o].price + inbounds[*i].price, *o, *i));
+        pairs.into_iter()
+            .take(count)
+            .map(|(o, i)| RoundTrip {
+                price: outbounds[o].price + inbounds[i].price,
+                outbound: outbounds[o].clone(),
+                inbound: inbounds[i].clone(),
+            })
+            .collect()
+    }
+
+    /// Finds every city reachable from `sources` for at most `budget`, ordered by price.
+    /// Cities of the sources are not included.
+    pub fn reachable_cities(&self, sources: &[i64], budget: i64) -> Vec<ReachableCity> {
+        let origin_cities: HashSet<i64> = sources.iter().filter_map(|id| self.city_of(*id)).collect();
+        let mut best: HashMap<i64, (i64, usize)> = HashMap::new();
+        let mut queue: BinaryHeap<Reverse<(i64, usize, i64)>> = BinaryHeap::new();
+        for source in sources {
+            best.insert(*source, (0, 0));
+            queue.push(Reverse((0, 0, *source)));
+        }
+        let mut cities: HashMap<i64, ReachableCity> = HashMap::new();
+
+        while let Some(Reverse((price, flights, airport_id))) = queue.pop() {
+            if best.get(&airport_id).is_some_and(|b| *b < (price, flights)) {
+                continue;
+            }
+            if let Some(city_id) = self.city_of(airport_id) {
+                if flights > 0 && !origin_cities.contains(&city_id) {
+                    cities.entry(city_id).or_insert(ReachableCity { city_id, price, flights });
+                }
+            }
+            for route in self.departures_from(airport_id) {
+                let next = (price + self.leg_price(airport_id, route), flights + 1);
+                if next.0 > budget || best.get(&route.finish).is_some_and(|b| *b <= next) {
+                    continue;
+                }
+                best.insert(route.finish, next);
+                queue.push(Reverse((next.0, next.1, route.finish)));
+            }
+        }
+
+        let mut cities: Vec<ReachableCity> = cities.into_values().collect();
+        cities.sort_by_key(|c| (c.price, c.flights, c.city_id));
+        cities
+    }
+
+    /// Finds a cheap order of visiting cities, given by airports of each city with the origin first.
+    /// Every leg is the cheapest path between two consecutive cities, found with one search per city.
+    pub fn plan_tour(&self, stops: &[Vec<i64>], return_to_origin: bool) -> Option<Tour> {
+        let legs: Vec<Vec<Option<Itinerary>>> = stops.iter()
+            .enumerate()
+            .map(|(from, sources)| {
+                let arrivals = self.cheapest_arrivals(sources);
+                stops.iter()
+                    .enumerate()
+                    .map(|(to, destinations)| match from == to {
+                        true => None,
+                        false => self.cheapest_leg(&arrivals, destinations),
+                    })
+                    .collect()
+            })
+            .collect();
+        let costs: Vec<Vec<Option<i64>>> = legs.iter()
+            .map(|row| row.iter().map(|leg| leg.as_ref().map(|i| i.price)).collect())
+            .collect();
+        let mut order = tour::visiting_order(&costs, return_to_origin)?;
+        if return_to_origin {
+            order.push(0);
+        }
+        let legs: Vec<Itinerary> = order.windows(2)
+            .filter_map(|pair| legs[pair[0]][pair[1]].clone())
+            .collect();
+        Some(Tour {
+            price: legs.iter().map(|i| i.price).sum(),
+            legs,
         })
     }
 
-    pub fn search_for_best_path(&mut self, root: &mut Stop) -> Result<Option<Vec<i64>>, Error> {
-        let mut keep_searching = true;
-        while keep_searching {
-            self.expanded_during_this_turn = false;
-            let start_id = root.incomming_route.as_ref().unwrap().finish.clone();
-            match self.do_expand(root, DoExpandInput {
-                accumulated_price: 0,
-                previous_stops: Box::new(vec![start_id]),
-            }) {
-                Ok(()) => keep_searching = self.expanded_during_this_turn,
-                Err(err) => return Err(err),
+    /// Finds all itineraries for which no other is cheaper, shorter in legs and (when searching
+    /// by schedule) arriving sooner at the same time, ordered by price.
+    ///
+    /// Partial paths are compared per city and stage, so a path that comes back to a city
+    /// is always dominated by the one that got there first.
+    pub fn pareto_paths(
+        &self,
+        sources: &[i64],
+        destinations: &[i64],
+        constraints: &RouteConstraints,
+        objective: &Objective,
+    ) -> Vec<ParetoItinerary> {
+        let timed = matches!(objective, Objective::EarliestArrival { .. });
+        let destinations: HashSet<i64> = destinations.iter().copied().collect();
+        let mut labels: Vec<ParetoLabel> = vec![];
+        let mut bags: HashMap<(i64, usize), Vec<usize>> = HashMap::new();
+        let mut queue: BinaryHeap<Reverse<(i64, usize, i64, usize)>> = BinaryHeap::new();
+        let mut front: Vec<usize> = vec![];
+        for source in sources {
+            let label = ParetoLabel {
+                stop: Stop { airport_id: *source, stage: 0, flights: 0 },
+                price: 0,
+                legs: 0,
+                minutes: 0,
+                arrival: None,
             };
+            self.insert_label(label, &mut labels, &mut bags, &mut queue);
         }
-        match self.best_path.as_ref() {
-            Some(q) => Ok(Some(q.iter().map(|id| id.clone()).collect())),
-            None => Ok(None),
-        }
-    }
-
-    fn do_expand(&mut self, 
-        stop: &mut Stop,
-        input: DoExpandInput,
-    ) -> Result<(), Error> {
-        if stop.dead_end {
-            return Ok(());
-        }
-        if (self.lowest_price.is_none() || self.lowest_price.as_ref().unwrap().clone() > stop.accumulated_price) 
-            && stop.incomming_route.is_some() 
-            && self.destination.contains(&(stop.incomming_route.as_ref().unwrap().finish.clone())) {
-                self.lowest_price = Some(stop.accumulated_price.clone());
-                self.destination = input.previous_stops.as_ref().iter().map(|id| id.clone()).collect();
-                stop.dead_end = true;
-                return Ok(());
-            }
-        if self.lowest_price.is_none() || self.lowest_price.as_ref().unwrap().clone() < stop.accumulated_price {
-            stop.dead_end = true;
-            return Ok(());
-        }
-        if stop.dead_end {
-            return Ok(());
-        } else {
-            if stop.destinations.is_none() {
-                let path_so_far = input.previous_stops.as_ref().iter().map(|id| id.clone()).collect();
-                let start = stop.incomming_route.as_ref().unwrap().finish.clone();
-                let start_points = match self.extend_id_vec(vec![start]) {
-                    Ok(ids) => ids,
-                    Err(err) => return Err(err),
+
+        while let Some(Reverse((_, _, _, index))) = queue.pop() {
+            let label = &labels[index];
+            if !bags.get(&self.bag_key(&label.stop)).is_some_and(|bag| bag.contains(&index)) {
+                continue;
+            }
+            if destinations.contains(&label.stop.airport_id)
+                && label.stop.stage == constraints.via_city_ids.len()
+                && label.arrival.is_some() {
+                    front.push(index);
+                    continue;
+                }
+            let mut extensions = vec![];
+            for route in self.departures_from(label.stop.airport_id) {
+                let next_stop = self.land(&label.stop, route, constraints);
+                let (minutes, times) = match timed {
+                    true => match self.take(label.stop.airport_id, route, label.minutes, label.arrival.is_some(), objective) {
+                        Some(taken) => taken,
+                        None => continue,
+                    },
+                    false => (0, None),
                 };
-                let routes = match self.get_possible_destinations(start_points, path_so_far) {
-                    Ok(r) => r,
-                    Err(err) => return Err(err),
+                let next = ParetoLabel {
+                    stop: next_stop,
+                    price: label.price + self.leg_price(label.stop.airport_id, route),
+                    legs: label.legs + 1,
+                    minutes,
+                    arrival: Some((*route, times, index)),
                 };
-                if routes.len() == 0 {
-                    stop.dead_end = true;
-                    return Ok(());
+                if !self.allows(route, &next_stop, constraints, &Restrictions::default())
+                    || constraints.max_price.is_some_and(|max| next.price > max)
+                    || constraints.max_flights.is_some_and(|max| next.legs > max) {
+                    continue;
                 }
-                let mut destinations: Vec<Box<Stop>> = vec![];
-                for route in routes {
-                    destinations.push(Box::new(Stop {
-                        dead_end: false,
-                        destinations: None,
-                        accumulated_price: stop.accumulated_price.clone() + route.price.clone(),
-                        incomming_route: Some(Box::new(route)),
-                    }));
+                extensions.push(next);
+            }
+            for next in extensions {
+                self.insert_label(next, &mut labels, &mut bags, &mut queue);
+            }
+        }
+
+        let mut front: Vec<ParetoItinerary> = front.iter()
+            .map(|index| {
+                let label = &labels[*index];
+                let mut routes = vec![];
+                let mut times = vec![];
+                let mut current = label;
+                while let Some((route, flight_times, previous)) = &current.arrival {
+                    routes.push(*route);
+                    times.extend(flight_times);
+                    current = &labels[*previous];
                 }
-                stop.destinations = Some(destinations);
-                self.expanded_during_this_turn = true;
-            } else {
-                for s in stop.destinations.as_mut().unwrap() {
-                    let mut previous_stops: Vec<i64> = input.previous_stops.as_ref().iter().map(|id| id.clone()).collect();
-                    previous_stops.push(s.incomming_route.as_ref().unwrap().finish.clone());
-                    match self.do_expand(
-                        s.as_mut(),
-                        DoExpandInput {
-                            accumulated_price: input.accumulated_price.clone(),
-                            previous_stops: Box::new(previous_stops),
-                        },
-                    ) {
-                        Ok(()) => (),
-                        Err(err) => return Err(err),
-                    };
+                routes.reverse();
+                times.reverse();
+                ParetoItinerary {
+                    itinerary: self.itinerary(routes, label.price, times),
+                    travel_minutes: match timed {
+                        true => Some(label.minutes),
+                        false => None,
+                    },
+                    wins: vec![],
                 }
+            })
+            .collect();
+        let cheapest = front.iter().map(|p| p.itinerary.price).min();
+        let fewest_legs = front.iter().map(|p| p.itinerary.routes.len()).min();
+        let fastest = front.iter().filter_map(|p| p.travel_minutes).min();
+        for pareto in front.iter_mut() {
+            if Some(pareto.itinerary.price) == cheapest {
+                pareto.wins.push(Criterion::Price);
+            }
+            if Some(pareto.itinerary.routes.len()) == fewest_legs {
+                pareto.wins.push(Criterion::Legs);
+            }
+            if pareto.travel_minutes.is_some() && pareto.travel_minutes == fastest {
+                pareto.wins.push(Criterion::Time);
             }
         }
-        Ok(())
+        front.sort_by_key(|p| (p.itinerary.price, p.itinerary.routes.len(), p.travel_minutes));
+        front
     }
 
-    fn get_possible_destinations(&self, start_points: Vec<i64>, ids_to_exclude: Vec<i64>) -> Result<Vec<Route>, Error> {
-        self.route_repo.find_by_start(start_points, Some(ids_to_exclude))
+    /// Adds the label to the bag of its city and stage unless a label already there dominates it,
+    /// dropping labels it dominates.
+    fn insert_label(
+        &self,
+        label: ParetoLabel,
+        labels: &mut Vec<ParetoLabel>,
+        bags: &mut HashMap<(i64, usize), Vec<usize>>,
+        queue: &mut BinaryHeap<Reverse<(i64, usize, i64, usize)>>,
+    ) {
+        let bag = bags.entry(self.bag_key(&label.stop)).or_default();
+        if bag.iter().any(|i| labels[*i].dominates(&label)) {
+            return;
+        }
+        bag.retain(|i| !label.dominates(&labels[*i]));
+        let index = labels.len();
+        bag.push(index);
+        queue.push(Reverse((label.price, label.legs, label.minutes, index)));
+        labels.push(label);
+    }
+
+    /// Groups stops by city and stage. Airports of unknown cities are kept apart by their negated ID.
+    fn bag_key(&self, stop: &Stop) -> (i64, usize) {
+        (self.city_of(stop.airport_id).unwrap_or(-stop.airport_id), stop.stage)
+    }
+
+    /// Runs Dijkstra from all `sources`, each given with the cost and price already paid to get there,
+    /// and stops at the first of `destinations` reached by a flight after visiting all via cities.
+    /// Sources are treated as landings when `connecting` is set.
+    /// Returned itinerary holds only the flights taken during this search, but the total price.
+    fn search(
+        &self,
+        sources: &[(Stop, Label)],
+        connecting: bool,
+        destinations: &HashSet<i64>,
+        constraints: &RouteConstraints,
+        objective: &Objective,
+        restrictions: &Restrictions,
+    ) -> Option<Itinerary> {
+        let mut labels: HashMap<Stop, Label> = HashMap::new();
+        // stop -> (route used to land there, stop the route was taken from, times of the flight)
+        let mut arrivals: HashMap<Stop, (Route, Stop, Option<FlightTimes>)> = HashMap::new();
+        let mut queue: BinaryHeap<Reverse<(i64, Stop)>> = BinaryHeap::new();
+        for (source, label) in sources {
+            labels.insert(*source, *label);
+            queue.push(Reverse((label.cost, *source)));
+        }
+
+        while let Some(Reverse((cost, stop))) = queue.pop() {
+            let label = match labels.get(&stop) {
+                Some(label) if label.cost == cost => *label,
+                _ => continue,
+            };
+            if destinations.contains(&stop.airport_id)
+                && stop.stage == constraints.via_city_ids.len()
+                && arrivals.contains_key(&stop) {
+                    let (routes, times) = Self::unwind(&arrivals, stop);
+                    return Some(self.itinerary(routes, label.price, times));
+                }
+            let landed = connecting || arrivals.contains_key(&stop);
+            for route in self.departures_from(stop.airport_id) {
+                let next_stop = self.land(&stop, route, constraints);
+                let (next_cost, times) = match self.take(stop.airport_id, route, label.cost, landed, objective) {
+                    Some(taken) => taken,
+                    None => continue,
+                };
+                let next_price = label.price + self.leg_price(stop.airport_id, route);
+                if !self.allows(route, &next_stop, constraints, restrictions)
+                    || constraints.max_price.is_some_and(|max| next_price > max)
+                    || labels.get(&next_stop).is_some_and(|l| l.cost <= next_cost) {
+                    continue;
+                }
+                labels.insert(next_stop, Label { cost: next_cost, price: next_price });
+                arrivals.insert(next_stop, (*route, stop, times));
+                queue.push(Reverse((next_cost, next_stop)));
+            }
+        }
+
+        None
+    }
+
+    /// Computes the cost after getting from `airport_id` to the start of `route` and flying it
+    /// when `cost` was reached before, together with the times of the flight when following schedules.
+    fn take(
+        &self,
+        airport_id: i64,
+        route: &Route,
+        cost: i64,
+        landed: bool,
+        objective: &Objective,
+    ) -> Option<(i64, Option<FlightTimes>)> {
+        match objective {
+            Objective::Cheapest => Some((cost + self.leg_price(airport_id, route), None)),
+            Objective::ShortestDistance => Some((cost + self.route_distance(route)?, None)),
+            Objective::EarliestArrival { departure, min_connection } => {
+                let mut ready = *departure + Duration::minutes(cost);
+                if landed {
+                    ready += *min_connection;
+                }
+                if let Some(transfer) = self.transfer(airport_id, route.start) {
+                    ready += Duration::minutes(transfer.duration_minutes as i64);
+                }
+                self.schedules.get(&route.id)?
+                    .iter()
+                    .filter_map(|s| s.next_departure(ready).map(|d| FlightTimes {
+                        departure: d,
+                        arrival: s.arrival(d),
+                    }))
+                    .min_by_key(|t| t.arrival)
+                    .map(|t| ((t.arrival - *departure).num_minutes(), Some(t)))
+            },
+        }
     }
 
-    fn extend_id_vec(&self, route_starts: Vec<i64>) -> Result<Vec<i64>, Error> {
-        self.airport_repo.get_ids_by_city_ids(route_starts)
+    fn score(&self, itinerary: &Itinerary, objective: &Objective) -> i64 {
+        match (objective, itinerary.times.last()) {
+            (Objective::EarliestArrival { departure, .. }, Some(last)) => (last.arrival - *departure).num_minutes(),
+            (Objective::ShortestDistance, _) => self.distance_of(&itinerary.routes).unwrap_or(i64::MAX),
+            _ => itinerary.price,
+        }
+    }
+
+    /// Computes the state after taking `route` from `stop`.
+    fn land(&self, stop: &Stop, route: &Route, constraints: &RouteConstraints) -> Stop {
+        let mut stage = stop.stage;
+        if stage < constraints.via_city_ids.len()
+            && self.city_of(route.finish) == Some(constraints.via_city_ids[stage]) {
+                stage += 1;
+            }
+        Stop {
+            airport_id: route.finish,
+            stage,
+            flights: match constraints.max_flights {
+                Some(_) => stop.flights + 1,
+                None => 0,
+            },
+        }
+    }
+
+    fn allows(&self, route: &Route, next_stop: &Stop, constraints: &RouteConstraints, restrictions: &Restrictions) -> bool {
+        if restrictions.routes.contains(&route.id)
+            || constraints.excluded_airport_ids.contains(&route.start)
+            || constraints.excluded_airport_ids.contains(&route.finish)
+            || constraints.max_flights.is_some_and(|max| next_stop.flights > max) {
+                return false;
+            }
+        let start_city = self.city_of(route.start);
+        let finish_city = self.city_of(route.finish);
+        !(start_city.is_some_and(|c| constraints.excluded_city_ids.contains(&c))
+            || finish_city.is_some_and(|c| constraints.excluded_city_ids.contains(&c))
+            || finish_city.is_some_and(|c| restrictions.landings.contains(&(c, next_stop.stage))))
+    }
+
+    /// Lists routes departing from the city of the given airport.
+    fn departures_from(&self, airport_id: i64) -> Vec<&Route> {
+        let departure_airports = self.city_of(airport_id)
+            .and_then(|c| self.airports_by_city.get(&c))
+            .map_or(std::slice::from_ref(&airport_id), Vec::as_slice);
+        departure_airports.iter()
+            .filter_map(|id| self.departures.get(id))
+            .flatten()
+            .collect()
+    }
+
+    fn city_of(&self, airport_id: i64) -> Option<i64> {
+        self.airports.get(&airport_id).map(|a| a.city_id)
+    }
+
+    fn transfer(&self, from_airport_id: i64, to_airport_id: i64) -> Option<&Transfer> {
+        match from_airport_id == to_airport_id {
+            true => None,
+            false => self.transfers.get(&(from_airport_id, to_airport_id)),
+        }
+    }
+
+    /// Price of the route together with the transfer to its starting airport
+    fn leg_price(&self, airport_id: i64, route: &Route) -> i64 {
+        route.price + self.transfer(airport_id, route.start).map_or(0, |t| t.price)
+    }
+
+    /// Length of the flight in whole meters
+    fn route_distance(&self, route: &Route) -> Option<i64> {
+        let start = self.airports.get(&route.start)?;
+        let finish = self.airports.get(&route.finish)?;
+        start.distance_to(finish).map(|meters| meters.round() as i64)
+    }
+
+    fn distance_of(&self, routes: &[Route]) -> Option<i64> {
+        routes.iter().map(|route| self.route_distance(route)).sum()
+    }
+
+    /// Price of consecutive routes including transfers between them
+    fn price_of(&self, routes: &[Route]) -> i64 {
+        routes.iter()
+            .enumerate()
+            .map(|(index, route)| match index {
+                0 => route.price,
+                _ => self.leg_price(routes[index - 1].finish, route),
+            })
+            .sum()
+    }
+
+    fn itinerary(&self, routes: Vec<Route>, price: i64, times: Vec<FlightTimes>) -> Itinerary {
+        let transfers = routes.windows(2)
+            .filter_map(|pair| self.transfer(pair[0].finish, pair[1].start).copied())
+            .collect();
+        Itinerary {
+            routes,
+            price,
+            times,
+            transfers,
+        }
+    }
+
+    /// Cheapest way of getting from `sources` to every reachable airport, found with Dijkstra's algorithm:
+    /// the price paid and the route that lands at the airport together with the airport it was taken from
+    fn cheapest_arrivals(&self, sources: &[i64]) -> Arrivals {
+        let mut best: Arrivals = sources.iter()
+            .map(|id| (*id, (0, None)))
+            .collect();
+        let mut queue: BinaryHeap<Reverse<(i64, i64)>> = sources.iter()
+            .map(|id| Reverse((0, *id)))
+            .collect();
+
+        while let Some(Reverse((price, airport_id))) = queue.pop() {
+            if best.get(&airport_id).is_some_and(|b| b.0 < price) {
+                continue;
+            }
+            for route in self.departures_from(airport_id) {
+                let next = price + self.leg_price(airport_id, route);
+                if best.get(&route.finish).is_some_and(|b| b.0 <= next) {
+                    continue;
+                }
+                best.insert(route.finish, (next, Some((*route, airport_id))));
+                queue.push(Reverse((next, route.finish)));
+            }
+        }
+
+        best
+    }
+
+    /// Cheapest itinerary to any of `destinations` out of those found by `cheapest_arrivals`
+    fn cheapest_leg(&self, arrivals: &Arrivals, destinations: &[i64]) -> Option<Itinerary> {
+        let (price, mut airport_id) = destinations.iter()
+            .filter_map(|id| arrivals.get(id).map(|(price, _)| (*price, *id)))
+            .min()?;
+        let mut routes = vec![];
+        while let Some((_, Some((route, previous)))) = arrivals.get(&airport_id) {
+            routes.push(*route);
+            airport_id = *previous;
+        }
+        routes.reverse();
+        Some(self.itinerary(routes, price, vec![]))
+    }
+
+    fn unwind(
+        arrivals: &HashMap<Stop, (Route, Stop, Option<FlightTimes>)>,
+        last_stop: Stop,
+    ) -> (Vec<Route>, Vec<FlightTimes>) {
+        let mut routes = vec![];
+        let mut times = vec![];
+        let mut stop = last_stop;
+        while let Some((route, previous, flight_times)) = arrivals.get(&stop) {
+            routes.push(*route);
+            times.extend(flight_times);
+            stop = *previous;
+        }
+        routes.reverse();
+        times.reverse();
+        (routes, times)
     }
 }
 
@@ -179,4 +832,4 @@ pub mod testing {
         println!("{}", boxed.num.clone());
     }
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,537 @@
+#[cfg(test)]
+mod best_route_tests {
+    use chrono::{
+        Duration,
+        NaiveDate,
+        NaiveDateTime,
+        NaiveTime,
+    };
+
+    use crate::model::{
+        Airport,
+        Coordinates,
+        Criterion,
+        Itinerary,
+        Objective,
+        ReachableCity,
+        Route,
+        RouteConstraints,
+        Schedule,
+        Transfer,
+    };
+    use super::super::best_route::RouteGraph;
+
+    fn airport(id: i64, city_id: i64) -> Airport {
+        Airport {
+            id,
+            city_id,
+            name: format!("airport {}", id),
+            location: None,
+            iata_code: None,
+            icao_code: None,
+        }
+    }
+
+    fn route(id: i64, start: i64, finish: i64, price: i64) -> Route {
+        Route {
+            id,
+            start,
+            finish,
+            price,
+        }
+    }
+
+    fn route_ids(itinerary: &Itinerary) -> Vec<i64> {
+        itinerary.routes.iter().map(|r| r.id).collect()
+    }
+
+    fn cheapest(graph: &RouteGraph, sources: &[i64], destinations: &[i64]) -> Option<Vec<i64>> {
+        graph.best_paths(sources, destinations, 1, &RouteConstraints::default(), &Objective::Cheapest).first().map(route_ids)
+    }
+
+    #[test]
+    fn test_cheapest_path_prefers_cheaper_connection() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 3, 500),
+                route(2, 1, 2, 100),
+                route(3, 2, 3, 150),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3)],
+        );
+
+        assert_eq!(Some(vec![2, 3]), cheapest(&graph, &[1], &[3]));
+    }
+
+    #[test]
+    fn test_cheapest_path_commutes_between_airports_of_same_city() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 2, 100),
+                route(2, 3, 4, 100),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 2), airport(4, 3)],
+        );
+
+        assert_eq!(Some(vec![1, 2]), cheapest(&graph, &[1], &[4]));
+    }
+
+    #[test]
+    fn test_cheapest_path_unreachable_destination() {
+        let graph = RouteGraph::new(
+            vec![route(1, 2, 1, 100)],
+            vec![airport(1, 1), airport(2, 2)],
+        );
+
+        assert!(cheapest(&graph, &[1], &[2]).is_none());
+    }
+
+    #[test]
+    fn test_cheapest_path_ignores_cycles() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 2, 10),
+                route(2, 2, 1, 10),
+                route(3, 2, 3, 10),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3)],
+        );
+
+        assert_eq!(Some(vec![1, 3]), cheapest(&graph, &[1], &[3]));
+    }
+
+    #[test]
+    fn test_cheapest_paths_ordered_by_price() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 4, 500),
+                route(2, 1, 2, 100),
+                route(3, 2, 4, 150),
+                route(4, 1, 3, 200),
+                route(5, 3, 4, 200),
+                route(6, 2, 3, 50),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3), airport(4, 4)],
+        );
+
+        let paths = graph.best_paths(&[1], &[4], 10, &RouteConstraints::default(), &Objective::Cheapest);
+
+        let ids: Vec<Vec<i64>> = paths.iter().map(route_ids).collect();
+        assert_eq!(vec![vec![2, 3], vec![2, 6, 5], vec![4, 5], vec![1]], ids);
+        let prices: Vec<i64> = paths.iter().map(|p| p.price).collect();
+        assert_eq!(vec![250, 350, 400, 500], prices);
+    }
+
+    #[test]
+    fn test_cheapest_paths_never_return_to_visited_city() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 2, 10),
+                route(2, 2, 3, 10),
+                route(3, 2, 1, 1),
+                route(4, 1, 3, 100),
+                route(5, 3, 4, 10),
+                route(6, 4, 2, 1),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3), airport(4, 2)],
+        );
+
+        let paths = graph.best_paths(&[1], &[3], 5, &RouteConstraints::default(), &Objective::Cheapest);
+
+        let ids: Vec<Vec<i64>> = paths.iter().map(route_ids).collect();
+        assert_eq!(vec![vec![1, 2], vec![4]], ids);
+    }
+
+    fn diamond_graph() -> RouteGraph {
+        RouteGraph::new(
+            vec![
+                route(1, 1, 4, 500),
+                route(2, 1, 2, 100),
+                route(3, 2, 4, 150),
+                route(4, 1, 3, 200),
+                route(5, 3, 4, 200),
+                route(6, 2, 3, 50),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3), airport(4, 4)],
+        )
+    }
+
+    fn constrained(graph: &RouteGraph, constraints: RouteConstraints) -> Vec<Vec<i64>> {
+        graph.best_paths(&[1], &[4], 10, &constraints, &Objective::Cheapest).iter().map(route_ids).collect()
+    }
+
+    #[test]
+    fn test_cheapest_paths_with_max_flights() {
+        let constraints = RouteConstraints {
+            max_flights: Some(1),
+            ..RouteConstraints::default()
+        };
+
+        assert_eq!(vec![vec![1]], constrained(&diamond_graph(), constraints));
+    }
+
+    #[test]
+    fn test_cheapest_paths_with_max_price() {
+        let constraints = RouteConstraints {
+            max_price: Some(350),
+            ..RouteConstraints::default()
+        };
+
+        assert_eq!(vec![vec![2, 3], vec![2, 6, 5]], constrained(&diamond_graph(), constraints));
+    }
+
+    #[test]
+    fn test_cheapest_paths_with_exclusions() {
+        let constraints = RouteConstraints {
+            excluded_city_ids: vec![2],
+            ..RouteConstraints::default()
+        };
+        assert_eq!(vec![vec![4, 5], vec![1]], constrained(&diamond_graph(), constraints));
+
+        let constraints = RouteConstraints {
+            excluded_airport_ids: vec![3],
+            ..RouteConstraints::default()
+        };
+        assert_eq!(vec![vec![2, 3], vec![1]], constrained(&diamond_graph(), constraints));
+    }
+
+    #[test]
+    fn test_cheapest_paths_through_via_city() {
+        let constraints = RouteConstraints {
+            via_city_ids: vec![3],
+            ..RouteConstraints::default()
+        };
+
+        assert_eq!(vec![vec![2, 6, 5], vec![4, 5]], constrained(&diamond_graph(), constraints));
+    }
+
+    #[test]
+    fn test_constraints_contradiction() {
+        let constraints = RouteConstraints {
+            via_city_ids: vec![2, 3],
+            max_flights: Some(2),
+            ..RouteConstraints::default()
+        };
+        assert!(constraints.contradiction(1, 4).is_some());
+
+        let constraints = RouteConstraints {
+            excluded_city_ids: vec![4],
+            ..RouteConstraints::default()
+        };
+        assert!(constraints.contradiction(1, 4).is_some());
+
+        let constraints = RouteConstraints {
+            via_city_ids: vec![2, 3],
+            max_flights: Some(3),
+            ..RouteConstraints::default()
+        };
+        assert!(constraints.contradiction(1, 4).is_none());
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2024-01-01 is a Monday
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn schedule(route_id: i64, hour: u32, minute: u32, duration_minutes: i32, days_of_week: u8) -> Schedule {
+        Schedule {
+            id: route_id,
+            route_id,
+            departure_time: NaiveTime::from_hms_opt(hour, minute, 0).unwrap(),
+            duration_minutes,
+            days_of_week,
+        }
+    }
+
+    fn scheduled_graph() -> RouteGraph {
+        RouteGraph::new(
+            vec![
+                route(1, 1, 3, 100),
+                route(2, 1, 2, 300),
+                route(3, 2, 3, 300),
+                route(4, 1, 3, 50),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3)],
+        ).with_schedules(vec![
+            schedule(1, 20, 0, 120, 0b111_1111),
+            schedule(2, 9, 0, 60, 0b111_1111),
+            schedule(3, 11, 30, 60, 0b111_1111),
+        ])
+    }
+
+    fn earliest(min_connection_minutes: i64, count: usize) -> Vec<Itinerary> {
+        let objective = Objective::EarliestArrival {
+            departure: at(1, 8, 0),
+            min_connection: Duration::minutes(min_connection_minutes),
+        };
+        scheduled_graph().best_paths(&[1], &[3], count, &RouteConstraints::default(), &objective)
+    }
+
+    #[test]
+    fn test_schedule_next_departure() {
+        // Tuesdays and Thursdays
+        let schedule = schedule(1, 10, 0, 90, 0b000_1010);
+
+        assert_eq!(Some(at(2, 10, 0)), schedule.next_departure(at(1, 8, 0)));
+        assert_eq!(Some(at(2, 10, 0)), schedule.next_departure(at(2, 10, 0)));
+        assert_eq!(Some(at(4, 10, 0)), schedule.next_departure(at(2, 10, 1)));
+        assert_eq!(at(4, 11, 30), schedule.arrival(at(4, 10, 0)));
+        assert_eq!(None, Schedule { days_of_week: 0, ..schedule }.next_departure(at(1, 8, 0)));
+    }
+
+    #[test]
+    fn test_earliest_arrival_prefers_faster_connection() {
+        let itineraries = earliest(60, 1);
+
+        assert_eq!(vec![vec![2, 3]], itineraries.iter().map(route_ids).collect::<Vec<Vec<i64>>>());
+        assert_eq!(600, itineraries[0].price);
+        assert_eq!(at(1, 9, 0), itineraries[0].times[0].departure);
+        assert_eq!(at(1, 12, 30), itineraries[0].times[1].arrival);
+    }
+
+    #[test]
+    fn test_earliest_arrival_respects_min_connection() {
+        let itineraries = earliest(120, 2);
+
+        assert_eq!(vec![vec![1], vec![2, 3]], itineraries.iter().map(route_ids).collect::<Vec<Vec<i64>>>());
+        assert_eq!(at(1, 22, 0), itineraries[0].times[0].arrival);
+        assert_eq!(at(2, 11, 30), itineraries[1].times[1].departure);
+    }
+
+    #[test]
+    fn test_pareto_paths_trade_price_for_legs() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 3, 500),
+                route(2, 1, 2, 100),
+                route(3, 2, 3, 150),
+                route(4, 1, 4, 50),
+                route(5, 4, 2, 50),
+                route(6, 1, 2, 200),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3), airport(4, 4)],
+        );
+
+        let front = graph.pareto_paths(&[1], &[3], &RouteConstraints::default(), &Objective::Cheapest);
+
+        assert_eq!(2, front.len());
+        assert_eq!(vec![2, 3], route_ids(&front[0].itinerary));
+        assert_eq!(vec![Criterion::Price], front[0].wins);
+        assert_eq!(vec![1], route_ids(&front[1].itinerary));
+        assert_eq!(vec![Criterion::Legs], front[1].wins);
+        assert_eq!(None, front[1].travel_minutes);
+    }
+
+    #[test]
+    fn test_pareto_paths_with_travel_time() {
+        let objective = Objective::EarliestArrival {
+            departure: at(1, 8, 0),
+            min_connection: Duration::minutes(60),
+        };
+
+        let front = scheduled_graph().pareto_paths(&[1], &[3], &RouteConstraints::default(), &objective);
+
+        assert_eq!(2, front.len());
+        assert_eq!(vec![1], route_ids(&front[0].itinerary));
+        assert_eq!(vec![Criterion::Price, Criterion::Legs], front[0].wins);
+        assert_eq!(Some(840), front[0].travel_minutes);
+        assert_eq!(vec![2, 3], route_ids(&front[1].itinerary));
+        assert_eq!(vec![Criterion::Time], front[1].wins);
+        assert_eq!(Some(270), front[1].travel_minutes);
+    }
+
+    fn round_trip_graph() -> RouteGraph {
+        RouteGraph::new(
+            vec![
+                route(1, 1, 2, 100),
+                route(2, 1, 3, 30),
+                route(3, 3, 2, 90),
+                route(4, 2, 1, 200),
+                route(5, 4, 1, 50),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3), airport(4, 4)],
+        )
+    }
+
+    #[test]
+    fn test_round_trips_priced_together() {
+        let trips = round_trip_graph().round_trips((&[1], &[2]), (&[2], &[1]), 3, &RouteConstraints::default());
+
+        assert_eq!(vec![300, 320], trips.iter().map(|t| t.price).collect::<Vec<i64>>());
+        assert_eq!(vec![1], route_ids(&trips[0].outbound));
+        assert_eq!(vec![2, 3], route_ids(&trips[1].outbound));
+        assert_eq!(vec![4], route_ids(&trips[1].inbound));
+
+        let constraints = RouteConstraints {
+            max_price: Some(310),
+            ..RouteConstraints::default()
+        };
+        let trips = round_trip_graph().round_trips((&[1], &[2]), (&[2], &[1]), 3, &constraints);
+        assert_eq!(vec![300], trips.iter().map(|t| t.price).collect::<Vec<i64>>());
+    }
+
+    #[test]
+    fn test_open_jaw_round_trip() {
+        let trips = round_trip_graph().round_trips((&[1], &[2]), (&[4], &[1]), 1, &RouteConstraints::default());
+
+        assert_eq!(1, trips.len());
+        assert_eq!(150, trips[0].price);
+        assert_eq!(vec![5], route_ids(&trips[0].inbound));
+    }
+
+    #[test]
+    fn test_plan_tour() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 2, 100),
+                route(2, 1, 3, 10),
+                route(3, 3, 2, 20),
+                route(4, 2, 3, 200),
+                route(5, 2, 1, 50),
+                route(6, 3, 1, 300),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3)],
+        );
+        let stops = vec![vec![1], vec![2], vec![3]];
+
+        let tour = graph.plan_tour(&stops, false).unwrap();
+        assert_eq!(vec![vec![2], vec![3]], tour.legs.iter().map(route_ids).collect::<Vec<Vec<i64>>>());
+        assert_eq!(30, tour.price);
+
+        let tour = graph.plan_tour(&stops, true).unwrap();
+        assert_eq!(vec![vec![2], vec![3], vec![5]], tour.legs.iter().map(route_ids).collect::<Vec<Vec<i64>>>());
+        assert_eq!(80, tour.price);
+    }
+
+    #[test]
+    fn test_plan_tour_legs_are_cheapest_paths() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 5, 40),
+                route(2, 5, 2, 30),
+                route(3, 1, 2, 100),
+                route(4, 4, 3, 20),
+                route(5, 2, 3, 80),
+                route(6, 3, 1, 60),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3), airport(4, 2), airport(5, 5)],
+        ).with_transfers(vec![transfer(2, 4, 5, 30)]);
+        let stops = vec![vec![1], vec![2, 4], vec![3]];
+
+        let tour = graph.plan_tour(&stops, false).unwrap();
+
+        assert_eq!(vec![vec![1, 2], vec![4]], tour.legs.iter().map(route_ids).collect::<Vec<Vec<i64>>>());
+        assert_eq!(Some(vec![1, 2]), cheapest(&graph, &stops[0], &stops[1]));
+        assert_eq!(Some(vec![4]), cheapest(&graph, &stops[1], &stops[2]));
+        assert_eq!(90, tour.price);
+    }
+
+    #[test]
+    fn test_reachable_cities_within_budget() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 2, 50),
+                route(2, 2, 3, 50),
+                route(3, 1, 3, 100),
+                route(4, 3, 4, 150),
+                route(5, 2, 1, 10),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 3), airport(4, 4)],
+        );
+
+        let reachable = graph.reachable_cities(&[1], 200);
+
+        assert_eq!(
+            vec![
+                ReachableCity { city_id: 2, price: 50, flights: 1 },
+                ReachableCity { city_id: 3, price: 100, flights: 1 },
+            ],
+            reachable,
+        );
+    }
+
+    fn transfer(from_airport_id: i64, to_airport_id: i64, price: i64, duration_minutes: i32) -> Transfer {
+        Transfer {
+            id: from_airport_id * 10 + to_airport_id,
+            from_airport_id,
+            to_airport_id,
+            price,
+            duration_minutes,
+        }
+    }
+
+    #[test]
+    fn test_cheapest_path_pays_for_transfers() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 2, 100),
+                route(2, 3, 4, 100),
+                route(3, 2, 4, 180),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 2), airport(4, 3)],
+        ).with_transfers(vec![transfer(2, 3, 50, 45)]);
+
+        let paths = graph.best_paths(&[1], &[4], 2, &RouteConstraints::default(), &Objective::Cheapest);
+
+        assert_eq!(vec![vec![1, 2], vec![1, 3]], paths.iter().map(route_ids).collect::<Vec<Vec<i64>>>());
+        assert_eq!(vec![250, 280], paths.iter().map(|i| i.price).collect::<Vec<i64>>());
+        assert_eq!(vec![transfer(2, 3, 50, 45)], paths[0].transfers);
+        assert!(paths[1].transfers.is_empty());
+
+        let graph = graph.with_transfers(vec![transfer(2, 3, 100, 45)]);
+        assert_eq!(Some(vec![1, 3]), cheapest(&graph, &[1], &[4]));
+    }
+
+    #[test]
+    fn test_earliest_arrival_waits_for_transfer() {
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 2, 100),
+                route(2, 3, 4, 100),
+            ],
+            vec![airport(1, 1), airport(2, 2), airport(3, 2), airport(4, 3)],
+        ).with_schedules(vec![
+            schedule(1, 9, 0, 60, 0b111_1111),
+            schedule(2, 11, 0, 60, 0b111_1111),
+        ]).with_transfers(vec![transfer(2, 3, 20, 90)]);
+        let objective = Objective::EarliestArrival {
+            departure: at(1, 8, 0),
+            min_connection: Duration::minutes(30),
+        };
+
+        let paths = graph.best_paths(&[1], &[4], 1, &RouteConstraints::default(), &objective);
+
+        // 10:00 landing, 30 minutes to leave the airport and 90 to get to the other one misses 11:00
+        assert_eq!(at(2, 11, 0), paths[0].times[1].departure);
+        assert_eq!(220, paths[0].price);
+    }
+
+    #[test]
+    fn test_shortest_distance_path() {
+        let located = |id: i64, latitude: f64, longitude: f64| Airport {
+            location: Some(Coordinates { latitude, longitude }),
+            ..airport(id, id)
+        };
+        let graph = RouteGraph::new(
+            vec![
+                route(1, 1, 4, 100),
+                route(2, 1, 2, 50),
+                route(3, 2, 4, 50),
+                route(4, 1, 3, 10),
+                route(5, 3, 4, 10),
+            ],
+            vec![
+                located(1, 0.0, 0.0),
+                located(2, 0.0, 30.0),
+                airport(3, 3),
+                located(4, 0.0, 10.0),
+            ],
+        );
+
+        let paths = graph.best_paths(&[1], &[4], 3, &RouteConstraints::default(), &Objective::ShortestDistance);
+
+        // direct flight first, detour over airport 2 next, airport 3 has no coordinates
+        assert_eq!(vec![vec![1], vec![2, 3]], paths.iter().map(route_ids).collect::<Vec<Vec<i64>>>());
+        assert_eq!(Some(vec![4, 5]), cheapest(&graph, &[1], &[4]));
+    }
+}
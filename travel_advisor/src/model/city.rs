@@ -1,13 +1,29 @@
+use chrono_tz::Tz;
+
 use super::{
     Airport,
     Comment,
+    Country,
+    Region,
 };
 
+#[derive(Clone)]
 pub struct City {
     pub id: i64,
     pub name: String,
+    pub country_code: Option<String>,
+    pub region_id: Option<i64>,
+    /// IANA time zone local times in the city are given in
+    pub time_zone: Option<Tz>,
+    /// Names of the city in other languages, filled only when needed
+    pub aliases: Vec<CityAlias>,
+    /// Sorted tags like `beach` or `ski`, filled only when needed
+    pub tags: Vec<String>,
     pub airports: Vec<Airport>,
     pub comments: Vec<Comment>,
+    /// Filled only when loading the city with everything around it
+    pub country: Option<Country>,
+    pub region: Option<Region>,
 }
 
 impl City {
@@ -15,8 +31,117 @@ impl City {
         City {
             id: id,
             name: name,
+            country_code: None,
+            region_id: None,
+            time_zone: None,
+            aliases: vec![],
+            tags: vec![],
             comments: vec![],
             airports: vec![],
+            country: None,
+            region: None,
+        }
+    }
+
+    /// Name in the first of the languages the city has an alias in, or the name itself.
+    /// An alias in `sr-Latn` serves a request for `sr` and the other way around.
+    pub fn localized_name(&self, languages: &[String]) -> &str {
+        for language in languages {
+            let exact = self.aliases.iter().find(|a| a.language.eq_ignore_ascii_case(language));
+            let alias = exact.or_else(|| self.aliases.iter()
+                .find(|a| primary_subtag(&a.language).eq_ignore_ascii_case(primary_subtag(language))));
+            if let Some(alias) = alias {
+                return &alias.name;
+            }
         }
+        &self.name
+    }
+}
+
+/// Name of a city in a language, like `Beograd` in `sr-Latn` for Belgrade
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CityAlias {
+    pub id: i64,
+    pub city_id: i64,
+    pub name: String,
+    pub language: String,
+}
+
+/// BCP 47 language tag as used by `Accept-Language`, like `de`, `de-AT` or `sr-Latn`
+pub fn is_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    tag.len() <= 35
+        && (2..=3).contains(&primary.len())
+        && primary.bytes().all(|b| b.is_ascii_alphabetic())
+        && subtags.all(|s| (1..=8).contains(&s.len()) && s.bytes().all(|b| b.is_ascii_alphanumeric()))
+}
+
+fn primary_subtag(tag: &str) -> &str {
+    tag.split('-').next().unwrap_or_default()
+}
+
+/// Tag on a city, like `beach` on Split
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CityTag {
+    pub city_id: i64,
+    pub tag: String,
+}
+
+/// Lowercase letters, digits and dashes, like `beach` or `old-town`
+pub fn is_tag(tag: &str) -> bool {
+    (1..=30).contains(&tag.len())
+        && tag.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+/// How many of the wanted tags a city needs to have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagMatch {
+    All,
+    Any,
+}
+
+impl TagMatch {
+    pub fn matches(&self, tags: &[String], wanted: &[String]) -> bool {
+        match self {
+            TagMatch::All => wanted.iter().all(|w| tags.contains(w)),
+            TagMatch::Any => wanted.iter().any(|w| tags.contains(w)),
+        }
+    }
+}
+
+/// What happens to airports, their routes, comments and points of interest of a deleted city
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CityDeletePolicy {
+    /// Refuse to delete a city anything still refers to
+    Restrict,
+    /// Delete airports with their routes, comments and points of interest together with the city
+    Cascade,
+    /// Move airports, comments and points of interest to the city with the given ID, routes follow their airports
+    Reassign(i64),
+}
+
+/// Everything stored that refers to a city
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CityReferences {
+    pub airport_ids: Vec<i64>,
+    pub route_ids: Vec<i64>,
+    pub comment_ids: Vec<i64>,
+    pub poi_ids: Vec<i64>,
+}
+
+impl CityReferences {
+    pub fn is_empty(&self) -> bool {
+        self.airport_ids.is_empty() && self.route_ids.is_empty() && self.comment_ids.is_empty()
+            && self.poi_ids.is_empty()
+    }
+
+    /// Lists references as `kind/id`, e.g. `airport/3`
+    pub fn describe(&self) -> Vec<String> {
+        let airports = self.airport_ids.iter().map(|id| format!("airport/{}", id));
+        let routes = self.route_ids.iter().map(|id| format!("route/{}", id));
+        let comments = self.comment_ids.iter().map(|id| format!("comment/{}", id));
+        let pois = self.poi_ids.iter().map(|id| format!("poi/{}", id));
+        airports.chain(routes).chain(comments).chain(pois).collect()
     }
 }
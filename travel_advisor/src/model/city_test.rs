@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod city_tests {
+    use crate::model::{
+        City,
+        CityAlias,
+        TagMatch,
+        is_language_tag,
+        is_tag,
+    };
+
+    fn alias(name: &str, language: &str) -> CityAlias {
+        CityAlias {
+            id: 0,
+            city_id: 1,
+            name: name.to_string(),
+            language: language.to_string(),
+        }
+    }
+
+    fn languages(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_localized_name_follows_preferences() {
+        let city = City {
+            aliases: vec![alias("Београд", "sr-Cyrl"), alias("Beograd", "sr-Latn"), alias("Belgrad", "de")],
+            ..City::new(1, "Belgrade".to_string())
+        };
+
+        assert_eq!("Beograd", city.localized_name(&languages(&["sr-latn", "de"])));
+        assert_eq!("Belgrad", city.localized_name(&languages(&["fr", "de-AT"])));
+        // first alias of the language when the region or script does not match
+        assert_eq!("Београд", city.localized_name(&languages(&["sr"])));
+        assert_eq!("Belgrade", city.localized_name(&languages(&["fr"])));
+        assert_eq!("Belgrade", city.localized_name(&[]));
+    }
+
+    #[test]
+    fn test_language_tags() {
+        assert!(is_language_tag("de"));
+        assert!(is_language_tag("sr-Latn"));
+        assert!(is_language_tag("zh-Hant-TW"));
+        assert!(!is_language_tag("d"));
+        assert!(!is_language_tag("deutsch"));
+        assert!(!is_language_tag("de_AT"));
+        assert!(!is_language_tag("de-"));
+    }
+
+    #[test]
+    fn test_tag_match() {
+        let tags = languages(&["beach", "nightlife"]);
+
+        assert!(TagMatch::All.matches(&tags, &languages(&["beach", "nightlife"])));
+        assert!(!TagMatch::All.matches(&tags, &languages(&["beach", "family"])));
+        assert!(TagMatch::Any.matches(&tags, &languages(&["beach", "family"])));
+        assert!(!TagMatch::Any.matches(&tags, &languages(&["ski", "family"])));
+        assert!(is_tag("old-town"));
+        assert!(!is_tag("Beach"));
+        assert!(!is_tag(""));
+    }
+}
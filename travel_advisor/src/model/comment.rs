@@ -1,11 +1,141 @@
-use std::time::SystemTime;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    time::SystemTime,
+};
 
 #[derive(Clone)]
 pub struct Comment {
     pub id: i64,
     pub user_id: i64,
     pub city_id: i64,
+    /// Comment this one replies to
+    pub parent_id: Option<i64>,
     pub content: String,
+    /// Deleted comments with replies stay as placeholders so that their threads hold together
+    pub deleted: bool,
+    /// Hidden by a moderator, left out of city listings
+    pub hidden: bool,
+    pub reactions: ReactionCounts,
     pub created_at: SystemTime,
     pub updated_at: SystemTime,
 }
+
+/// Earlier version of a comment's text
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentRevision {
+    pub id: i64,
+    pub comment_id: i64,
+    pub content: String,
+    /// When this version was posted or last edited
+    pub written_at: SystemTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactionKind {
+    Up,
+    Down,
+}
+
+impl ReactionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReactionKind::Up => "up",
+            ReactionKind::Down => "down",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<ReactionKind> {
+        [ReactionKind::Up, ReactionKind::Down].into_iter()
+            .find(|k| k.as_str() == name)
+    }
+}
+
+/// Vote of a user on a comment, a user has at most one per comment
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reaction {
+    pub comment_id: i64,
+    pub user_id: i64,
+    pub kind: ReactionKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReactionCounts {
+    pub up: i64,
+    pub down: i64,
+}
+
+impl ReactionCounts {
+    pub fn add(&mut self, kind: ReactionKind, count: i64) {
+        match kind {
+            ReactionKind::Up => self.up += count,
+            ReactionKind::Down => self.down += count,
+        }
+    }
+
+    pub fn score(&self) -> i64 {
+        self.up - self.down
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentSort {
+    /// Highest score first, more upvotes and then the older comment win ties
+    Helpful,
+    Newest,
+    Oldest,
+}
+
+impl CommentSort {
+    pub fn parse(name: &str) -> Option<CommentSort> {
+        match name {
+            "helpful" => Some(CommentSort::Helpful),
+            "newest" => Some(CommentSort::Newest),
+            "oldest" => Some(CommentSort::Oldest),
+            _ => None,
+        }
+    }
+
+    /// IDs grow with time, so they give the order of posting
+    pub fn apply(&self, comments: &mut [Comment]) {
+        match self {
+            CommentSort::Helpful => comments.sort_by(|a, b| b.reactions.score().cmp(&a.reactions.score())
+                .then(b.reactions.up.cmp(&a.reactions.up))
+                .then(a.id.cmp(&b.id))),
+            CommentSort::Newest => comments.sort_by_key(|c| Reverse(c.id)),
+            CommentSort::Oldest => comments.sort_by_key(|c| c.id),
+        }
+    }
+}
+
+/// Comment together with replies to it and replies to those
+#[derive(Clone)]
+pub struct CommentThread {
+    pub comment: Comment,
+    pub replies: Vec<CommentThread>,
+}
+
+impl CommentThread {
+    /// Arranges comments into threads, keeping their order. A reply to a comment
+    /// missing from the list starts a thread of its own.
+    pub fn build(comments: Vec<Comment>) -> Vec<CommentThread> {
+        let ids: Vec<i64> = comments.iter().map(|c| c.id).collect();
+        let mut roots: Vec<Comment> = vec![];
+        let mut replies: HashMap<i64, Vec<Comment>> = HashMap::new();
+        for comment in comments {
+            match comment.parent_id.filter(|p| ids.contains(p)) {
+                Some(parent_id) => replies.entry(parent_id).or_default().push(comment),
+                None => roots.push(comment),
+            }
+        }
+        roots.into_iter().map(|c| Self::attach(c, &mut replies)).collect()
+    }
+
+    fn attach(comment: Comment, replies: &mut HashMap<i64, Vec<Comment>>) -> CommentThread {
+        let children = replies.remove(&comment.id).unwrap_or_default();
+        CommentThread {
+            comment,
+            replies: children.into_iter().map(|c| Self::attach(c, replies)).collect(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod comment_tests {
+    use std::time::SystemTime;
+
+    use crate::model::{
+        Comment,
+        CommentSort,
+        CommentThread,
+        ReactionCounts,
+    };
+
+    fn comment(id: i64, parent_id: Option<i64>) -> Comment {
+        Comment {
+            id,
+            user_id: 1,
+            city_id: 1,
+            parent_id,
+            content: format!("comment {}", id),
+            deleted: false,
+            hidden: false,
+            reactions: ReactionCounts::default(),
+            created_at: SystemTime::now(),
+            updated_at: SystemTime::now(),
+        }
+    }
+
+    fn ids(threads: &[CommentThread]) -> Vec<(i64, Vec<i64>)> {
+        threads.iter()
+            .map(|t| (t.comment.id, t.replies.iter().map(|r| r.comment.id).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_build_threads() {
+        let threads = CommentThread::build(vec![
+            comment(1, None),
+            comment(2, Some(1)),
+            comment(3, None),
+            comment(4, Some(2)),
+            comment(5, Some(1)),
+        ]);
+
+        assert_eq!(vec![(1, vec![2, 5]), (3, vec![])], ids(&threads));
+        assert_eq!(vec![(4, vec![])], ids(&threads[0].replies[0].replies));
+    }
+
+    #[test]
+    fn test_reply_to_missing_comment_starts_thread() {
+        let threads = CommentThread::build(vec![comment(7, Some(6)), comment(8, Some(7))]);
+
+        assert_eq!(vec![(7, vec![8])], ids(&threads));
+    }
+
+    #[test]
+    fn test_helpful_sort() {
+        let rated = |id: i64, up: i64, down: i64| Comment {
+            reactions: ReactionCounts { up, down },
+            ..comment(id, None)
+        };
+        let mut comments = vec![rated(1, 0, 0), rated(2, 1, 3), rated(3, 2, 1), rated(4, 3, 2), rated(5, 0, 0)];
+
+        CommentSort::Helpful.apply(&mut comments);
+
+        assert_eq!(vec![4, 3, 1, 5, 2], comments.iter().map(|c| c.id).collect::<Vec<i64>>());
+    }
+}
@@ -0,0 +1,30 @@
+/// Country identified by its ISO 3166-1 alpha-2 code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Country {
+    pub code: String,
+    pub name: String,
+    pub regions: Vec<Region>,
+}
+
+/// Part of a country grouping cities, like a state or a province
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub id: i64,
+    pub country_code: String,
+    pub name: String,
+}
+
+/// Two uppercase letters, like `RS`
+pub fn is_country_code(code: &str) -> bool {
+    code.len() == 2 && code.bytes().all(|b| b.is_ascii_uppercase())
+}
+
+/// Start or destination of a route search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Place {
+    City(i64),
+    /// Any city of the country with the given code
+    Country(String),
+    /// Any city with the given tag
+    Tagged(String),
+}
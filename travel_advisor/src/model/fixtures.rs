@@ -0,0 +1,16 @@
+use super::{
+    Airport,
+    Coordinates,
+};
+
+/// Airport at the given coordinates, in the city with the same ID
+pub fn located_airport(id: i64, latitude: f64, longitude: f64) -> Airport {
+    Airport {
+        id,
+        city_id: id,
+        name: format!("airport {}", id),
+        location: Some(Coordinates { latitude, longitude }),
+        iata_code: None,
+        icao_code: None,
+    }
+}
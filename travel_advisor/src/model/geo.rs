@@ -0,0 +1,32 @@
+/// Mean Earth radius in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    /// Pairs the given latitude and longitude, if both are known
+    pub fn from_parts(latitude: Option<f64>, longitude: Option<f64>) -> Option<Coordinates> {
+        Some(Coordinates {
+            latitude: latitude?,
+            longitude: longitude?,
+        })
+    }
+
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.latitude) && (-180.0..=180.0).contains(&self.longitude)
+    }
+
+    /// Great-circle distance in meters, using the haversine formula
+    pub fn distance_to(&self, other: &Coordinates) -> f64 {
+        let lat_from = self.latitude.to_radians();
+        let lat_to = other.latitude.to_radians();
+        let half_lat = (lat_to - lat_from) / 2.0;
+        let half_lon = (other.longitude - self.longitude).to_radians() / 2.0;
+        let a = half_lat.sin().powi(2) + lat_from.cos() * lat_to.cos() * half_lon.sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+    }
+}
@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod geo_tests {
+    use super::super::geo::Coordinates;
+
+    #[test]
+    fn test_distance_between_airports() {
+        let belgrade = Coordinates { latitude: 44.8184, longitude: 20.3091 };
+        let london = Coordinates { latitude: 51.4700, longitude: -0.4543 };
+
+        let distance = belgrade.distance_to(&london);
+
+        assert!((distance - 1_701_000.0).abs() < 1_000.0, "distance was {}", distance);
+        assert_eq!(0.0, london.distance_to(&london));
+        assert!((distance - london.distance_to(&belgrade)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_coordinates_validity() {
+        assert!(Coordinates { latitude: -90.0, longitude: 180.0 }.is_valid());
+        assert!(!Coordinates { latitude: 90.5, longitude: 0.0 }.is_valid());
+        assert!(!Coordinates { latitude: 0.0, longitude: -180.1 }.is_valid());
+    }
+}
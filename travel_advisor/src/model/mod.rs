@@ -1,17 +1,84 @@
 mod airport;
 mod city;
 mod comment;
+mod country;
+mod moderation;
+pub(super) mod geo;
 pub(super) mod common;
+mod poi;
 mod route;
+mod search;
+mod spatial;
+pub(super) mod schedule;
+mod tour;
+mod transfer;
 mod user;
 pub(super) mod best_route;
 
 pub type Airport = airport::Airport;
+pub type AirportRef = airport::AirportRef;
+pub use airport::{
+    is_iata_code,
+    is_icao_code,
+};
+pub use city::{
+    is_language_tag,
+    is_tag,
+};
+pub use country::is_country_code;
+pub type Coordinates = geo::Coordinates;
 pub type User = user::User;
 pub type UserDB = user::UserDB;
 pub type City = city::City;
+pub type CityAlias = city::CityAlias;
+pub type CityDeletePolicy = city::CityDeletePolicy;
+pub type CityReferences = city::CityReferences;
+pub type CityTag = city::CityTag;
+pub type TagMatch = city::TagMatch;
+pub type Country = country::Country;
+pub type Region = country::Region;
+pub type Place = country::Place;
 pub type Comment = comment::Comment;
+pub type CommentThread = comment::CommentThread;
+pub type CommentRevision = comment::CommentRevision;
+pub type CommentSort = comment::CommentSort;
+pub type Reaction = comment::Reaction;
+pub type ReactionCounts = comment::ReactionCounts;
+pub type ReactionKind = comment::ReactionKind;
+pub type CommentFlag = moderation::CommentFlag;
+pub type FlaggedComment = moderation::FlaggedComment;
+pub type ModerationAction = moderation::ModerationAction;
+pub type ModerationRecord = moderation::ModerationRecord;
+pub type PointOfInterest = poi::PointOfInterest;
+pub type PoiCategory = poi::PoiCategory;
 pub type Route = route::Route;
+pub type RouteRecord = route::RouteRecord;
+pub type Schedule = schedule::Schedule;
+pub type Transfer = transfer::Transfer;
+pub type Itinerary = best_route::Itinerary;
+pub type RouteConstraints = best_route::RouteConstraints;
+pub type Objective = best_route::Objective;
+pub type FlightTimes = best_route::FlightTimes;
+pub type Criterion = best_route::Criterion;
+pub type ParetoItinerary = best_route::ParetoItinerary;
+pub type RoundTrip = best_route::RoundTrip;
+pub type Tour = best_route::Tour;
+pub type ReachableCity = best_route::ReachableCity;
+pub type AirportIndex = spatial::AirportIndex;
+pub type NearbyAirport = spatial::NearbyAirport;
+pub type SearchIndex = search::SearchIndex;
+pub type SearchHit<T> = search::SearchHit<T>;
+pub type SearchResults = search::SearchResults;
+pub type MatchKind = search::MatchKind;
 
 mod airports;
-mod airports_test;
\ No newline at end of file
+mod airports_test;
+mod best_route_test;
+mod city_test;
+mod comment_test;
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod geo_test;
+mod search_test;
+mod spatial_test;
+mod tour_test;
\ No newline at end of file
@@ -0,0 +1,56 @@
+use std::time::SystemTime;
+
+use super::Comment;
+
+/// Report of a comment by a user who thinks it breaks the rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommentFlag {
+    pub id: i64,
+    pub comment_id: i64,
+    pub user_id: i64,
+    pub reason: String,
+    pub created_at: SystemTime,
+}
+
+/// Comment waiting for a moderator together with its open flags
+#[derive(Clone)]
+pub struct FlaggedComment {
+    pub comment: Comment,
+    pub flags: Vec<CommentFlag>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModerationAction {
+    /// Keeps the comment visible and dismisses its flags
+    Approve,
+    /// Removes the comment from city listings
+    Hide,
+    Delete,
+}
+
+impl ModerationAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationAction::Approve => "approve",
+            ModerationAction::Hide => "hide",
+            ModerationAction::Delete => "delete",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<ModerationAction> {
+        [ModerationAction::Approve, ModerationAction::Hide, ModerationAction::Delete].into_iter()
+            .find(|a| a.as_str() == name)
+    }
+}
+
+/// Audit entry of a moderator decision, keeping the comment as it was at the time
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModerationRecord {
+    pub id: i64,
+    pub comment_id: i64,
+    pub comment_user_id: i64,
+    pub comment_text: String,
+    pub moderator_id: i64,
+    pub action: ModerationAction,
+    pub created_at: SystemTime,
+}
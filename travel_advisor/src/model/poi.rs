@@ -0,0 +1,36 @@
+use super::geo::Coordinates;
+
+/// Attraction, hotel or restaurant in a city
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointOfInterest {
+    pub id: i64,
+    pub city_id: i64,
+    pub name: String,
+    pub category: PoiCategory,
+    pub description: String,
+    pub location: Option<Coordinates>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PoiCategory {
+    Attraction,
+    Hotel,
+    Restaurant,
+}
+
+impl PoiCategory {
+    pub const ALL: [PoiCategory; 3] = [PoiCategory::Attraction, PoiCategory::Hotel, PoiCategory::Restaurant];
+
+    /// Name used both in the database and by clients
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PoiCategory::Attraction => "attraction",
+            PoiCategory::Hotel => "hotel",
+            PoiCategory::Restaurant => "restaurant",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<PoiCategory> {
+        PoiCategory::ALL.into_iter().find(|c| c.as_str().eq_ignore_ascii_case(name.trim()))
+    }
+}
@@ -1,11 +1,13 @@
-
 use crate::util::{
     Error,
     ErrorCode::TextRowParse,
 };
-use super::common::FromStringRecord;
+use super::{
+    airport::AirportRef,
+    common::FromStringRecord,
+};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Route {
     pub id: i64,
     pub start: i64,
@@ -13,23 +15,31 @@ pub struct Route {
     pub price: i64,
 }
 
-impl FromStringRecord for Route {
-    type Output = Route;
+/// Route as uploaded in CSV, with airports given by ID or code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteRecord {
+    pub start: AirportRef,
+    pub finish: AirportRef,
+    pub price: i64,
+}
+
+impl FromStringRecord for RouteRecord {
+    type Output = RouteRecord;
 
     fn from_string_record(record: csv::StringRecord) -> Result<Self::Output, Error> {
-        let start = match record[0].parse::<i64>() {
-            Ok(v) => v,
-            Err(err) => return Err(Error::internal(
+        let start = match AirportRef::parse(&record[0]) {
+            Some(v) => v,
+            None => return Err(Error::internal(
                 TextRowParse,
-                format!("bad start city ID: {}", err.to_string())
+                format!("bad start airport ID or code: {}", &record[0])
             )),
         };
 
-        let finish = match record[1].parse::<i64>() {
-            Ok(v) => v,
-            Err(err) => return Err(Error::internal(
+        let finish = match AirportRef::parse(&record[1]) {
+            Some(v) => v,
+            None => return Err(Error::internal(
                 TextRowParse,
-                format!("bad finish city ID: {}", err.to_string())
+                format!("bad finish airport ID or code: {}", &record[1])
             )),
         };
 
@@ -41,11 +51,10 @@ impl FromStringRecord for Route {
             )),
         };
         
-        Ok(Route {
-            id: 0,
+        Ok(RouteRecord {
             start: start,
             finish: finish,
             price: price,
         })
     }
-}
\ No newline at end of file
+}
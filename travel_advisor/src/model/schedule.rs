@@ -0,0 +1,36 @@
+use chrono::{
+    Datelike,
+    Duration,
+    NaiveDateTime,
+    NaiveTime,
+};
+
+/// Recurring departure of a route. Times are in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub id: i64,
+    pub route_id: i64,
+    pub departure_time: NaiveTime,
+    pub duration_minutes: i32,
+    /// Bit mask of operating days, bit 0 being Monday and bit 6 Sunday
+    pub days_of_week: u8,
+}
+
+pub const ALL_DAYS: u8 = 0b111_1111;
+
+impl Schedule {
+    /// Finds the first departure at or after `ready`, if the schedule operates on any day.
+    pub fn next_departure(&self, ready: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=7)
+            .map(|days| (ready.date() + Duration::days(days)).and_time(self.departure_time))
+            .find(|departure| *departure >= ready && self.operates_on(departure))
+    }
+
+    pub fn arrival(&self, departure: NaiveDateTime) -> NaiveDateTime {
+        departure + Duration::minutes(self.duration_minutes as i64)
+    }
+
+    fn operates_on(&self, departure: &NaiveDateTime) -> bool {
+        self.days_of_week & (1 << departure.weekday().num_days_from_monday()) != 0
+    }
+}
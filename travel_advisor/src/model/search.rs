@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use super::{
+    Airport,
+    City,
+};
+
+/// How a searched text matched a name or code, better matches first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchKind {
+    /// Query is the IATA or ICAO code of an airport
+    Code,
+    /// Query is the whole name
+    Exact,
+    /// Name starts with the query
+    Prefix,
+    /// A word within the name starts with the query
+    WordPrefix,
+    /// Query is a few typos away from the name, a word of it or its beginning
+    Fuzzy,
+}
+
+/// Found entity with the way it matched and the number of typos it took
+#[derive(Clone)]
+pub struct SearchHit<T> {
+    pub item: T,
+    pub kind: MatchKind,
+    pub distance: usize,
+}
+
+pub struct SearchResults {
+    pub cities: Vec<SearchHit<City>>,
+    pub airports: Vec<SearchHit<Airport>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Entry {
+    City(usize),
+    Airport(usize),
+}
+
+/// Lowercased name, or one of its words, pointing to the city or airport it belongs to
+struct Key {
+    text: String,
+    whole_name: bool,
+    entry: Entry,
+}
+
+/// Queries shorter than this are only matched by prefix
+const MIN_FUZZY_LENGTH: usize = 3;
+
+/// Names and aliases of cities, names of airports and airport codes, for autocomplete.
+///
+/// Names and each of their words are kept sorted, so that prefix matches are found
+/// by binary search. Typo-tolerant matching compares the query with every key.
+pub struct SearchIndex {
+    cities: Vec<City>,
+    airports: Vec<Airport>,
+    keys: Vec<Key>,
+}
+
+impl SearchIndex {
+    pub fn new(cities: Vec<City>, airports: Vec<Airport>) -> SearchIndex {
+        let mut keys = vec![];
+        let names = cities.iter().enumerate()
+            .flat_map(|(i, c)| std::iter::once(&c.name)
+                .chain(c.aliases.iter().map(|a| &a.name))
+                .map(move |name| (name, Entry::City(i))))
+            .chain(airports.iter().enumerate().map(|(i, a)| (&a.name, Entry::Airport(i))));
+        for (name, entry) in names {
+            let text = normalize(name);
+            let words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+            // the first word is already covered by prefixes of the whole name
+            for word in words.iter().skip(1) {
+                keys.push(Key { text: word.to_string(), whole_name: false, entry });
+            }
+            keys.push(Key { text, whole_name: true, entry });
+        }
+        keys.sort_by(|a, b| a.text.cmp(&b.text));
+        SearchIndex { cities, airports, keys }
+    }
+
+    /// Finds up to `limit` cities and up to `limit` airports matching the query, best matches first.
+    pub fn search(&self, query: &str, limit: usize) -> SearchResults {
+        let query = normalize(query);
+        if query.is_empty() {
+            return SearchResults { cities: vec![], airports: vec![] };
+        }
+        let mut best: HashMap<Entry, (MatchKind, usize)> = HashMap::new();
+        let mut offer = |entry: Entry, kind: MatchKind, distance: usize| {
+            let found = best.entry(entry).or_insert((kind, distance));
+            if (kind, distance) < *found {
+                *found = (kind, distance);
+            }
+        };
+
+        let code = query.to_ascii_uppercase();
+        for (index, airport) in self.airports.iter().enumerate() {
+            if airport.iata_code.as_ref() == Some(&code) || airport.icao_code.as_ref() == Some(&code) {
+                offer(Entry::Airport(index), MatchKind::Code, 0);
+            }
+        }
+
+        let start = self.keys.partition_point(|k| k.text.as_str() < query.as_str());
+        for key in self.keys[start..].iter().take_while(|k| k.text.starts_with(&query)) {
+            let kind = match (key.whole_name, key.text == query) {
+                (true, true) => MatchKind::Exact,
+                (true, false) => MatchKind::Prefix,
+                (false, _) => MatchKind::WordPrefix,
+            };
+            offer(key.entry, kind, 0);
+        }
+
+        let query_length = query.chars().count();
+        if query_length >= MIN_FUZZY_LENGTH {
+            let max_distance = if query_length < 6 { 1 } else { 2 };
+            for key in self.keys.iter() {
+                let beginning: String = key.text.chars().take(query_length).collect();
+                let distance = edit_distance(&query, &key.text).min(edit_distance(&query, &beginning));
+                if distance > 0 && distance <= max_distance {
+                    offer(key.entry, MatchKind::Fuzzy, distance);
+                }
+            }
+        }
+
+        let mut cities = vec![];
+        let mut airports = vec![];
+        for (entry, (kind, distance)) in best {
+            match entry {
+                Entry::City(i) => cities.push(SearchHit { item: self.cities[i].clone(), kind, distance }),
+                Entry::Airport(i) => airports.push(SearchHit { item: self.airports[i].clone(), kind, distance }),
+            }
+        }
+        cities.sort_by(|a, b| rank(a, &a.item.name).cmp(&rank(b, &b.item.name)));
+        airports.sort_by(|a, b| rank(a, &a.item.name).cmp(&rank(b, &b.item.name)));
+        cities.truncate(limit);
+        airports.truncate(limit);
+        SearchResults { cities, airports }
+    }
+}
+
+/// Better match first, then shorter name, since it is closer to what was typed so far
+fn rank<'a, T>(hit: &SearchHit<T>, name: &'a str) -> (MatchKind, usize, usize, &'a str) {
+    (hit.kind, hit.distance, name.chars().count(), name)
+}
+
+fn normalize(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+/// Levenshtein distance counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
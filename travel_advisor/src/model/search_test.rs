@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod search_tests {
+    use crate::model::{
+        Airport,
+        City,
+        CityAlias,
+        MatchKind,
+        SearchIndex,
+    };
+
+    fn airport(id: i64, name: &str, iata_code: &str) -> Airport {
+        Airport {
+            id,
+            city_id: 1,
+            name: name.to_string(),
+            location: None,
+            iata_code: Some(iata_code.to_string()),
+            icao_code: None,
+        }
+    }
+
+    fn index() -> SearchIndex {
+        SearchIndex::new(
+            vec![
+                City {
+                    aliases: vec![CityAlias {
+                        id: 1,
+                        city_id: 1,
+                        name: "Beograd".to_string(),
+                        language: "sr-Latn".to_string(),
+                    }],
+                    ..City::new(1, "Belgrade".to_string())
+                },
+                City::new(2, "Berlin".to_string()),
+                City::new(3, "New York".to_string()),
+                City::new(4, "Bern".to_string()),
+            ],
+            vec![
+                airport(10, "Nikola Tesla", "BEG"),
+                airport(11, "Berlin Brandenburg", "BER"),
+                airport(12, "John F. Kennedy", "JFK"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_prefix_ranks_shorter_names_first() {
+        let results = index().search("ber", 10);
+
+        let cities: Vec<i64> = results.cities.iter().map(|h| h.item.id).collect();
+        assert_eq!(vec![4, 2], cities[..2].to_vec());
+        assert!(results.cities.iter().take(2).all(|h| h.kind == MatchKind::Prefix));
+        // code match beats the name prefix
+        assert_eq!(11, results.airports[0].item.id);
+        assert_eq!(MatchKind::Code, results.airports[0].kind);
+    }
+
+    #[test]
+    fn test_exact_name_and_word_prefix() {
+        let index = index();
+
+        let results = index.search("  bern ", 10);
+        assert_eq!(4, results.cities[0].item.id);
+        assert_eq!(MatchKind::Exact, results.cities[0].kind);
+
+        let results = index.search("york", 10);
+        assert_eq!(vec![3], results.cities.iter().map(|h| h.item.id).collect::<Vec<i64>>());
+        assert_eq!(MatchKind::WordPrefix, results.cities[0].kind);
+
+        let results = index.search("tesla", 10);
+        assert_eq!(10, results.airports[0].item.id);
+
+        let results = index.search("Beograd", 10);
+        assert_eq!(1, results.cities[0].item.id);
+        assert_eq!(MatchKind::Exact, results.cities[0].kind);
+    }
+
+    #[test]
+    fn test_typos_are_tolerated() {
+        let index = index();
+
+        let results = index.search("belgarde", 10);
+        assert_eq!(1, results.cities[0].item.id);
+        assert_eq!(MatchKind::Fuzzy, results.cities[0].kind);
+        assert_eq!(2, results.cities[0].distance);
+
+        // a typo in what was typed so far
+        let results = index.search("bwlg", 10);
+        assert_eq!(vec![1], results.cities.iter().map(|h| h.item.id).collect::<Vec<i64>>());
+
+        // too short to guess
+        assert!(index.search("xe", 10).cities.is_empty());
+    }
+
+    #[test]
+    fn test_limit_and_empty_query() {
+        let index = index();
+
+        assert_eq!(1, index.search("b", 1).cities.len());
+        let results = index.search(" ", 10);
+        assert!(results.cities.is_empty() && results.airports.is_empty());
+    }
+}
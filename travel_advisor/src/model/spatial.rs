@@ -0,0 +1,93 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use super::{
+    Airport,
+    Coordinates,
+};
+
+/// Size of a grid cell in degrees of latitude and longitude
+const CELL_DEGREES: f64 = 1.0;
+/// Shortest distance in meters between two parallels one degree apart
+const METERS_PER_DEGREE: f64 = 111_195.0;
+
+/// Airport found around a point, with its distance from the point in meters
+#[derive(Clone)]
+pub struct NearbyAirport {
+    pub airport: Airport,
+    pub distance: f64,
+}
+
+/// Grid of airports with known coordinates, bucketed by whole degrees.
+///
+/// A lookup only measures distances to airports in cells overlapping the bounding box
+/// of the searched circle. Airports without coordinates are left out.
+pub struct AirportIndex {
+    cells: HashMap<(i32, i32), Vec<(Airport, Coordinates)>>,
+}
+
+impl AirportIndex {
+    pub fn new(airports: Vec<Airport>) -> AirportIndex {
+        let mut cells: HashMap<(i32, i32), Vec<(Airport, Coordinates)>> = HashMap::new();
+        for airport in airports {
+            if let Some(location) = airport.location.filter(|l| l.is_valid()) {
+                cells.entry(cell_of(&location)).or_default().push((airport, location));
+            }
+        }
+        AirportIndex { cells }
+    }
+
+    /// Lists up to `limit` airports within `radius` meters of `center`, closest first.
+    pub fn nearby(&self, center: &Coordinates, radius: f64, limit: usize) -> Vec<NearbyAirport> {
+        let (row, column) = cell_of(center);
+        let span = radius / METERS_PER_DEGREE / CELL_DEGREES;
+        let rows = span.ceil() as i32;
+        let max_column = (360.0 / CELL_DEGREES) as i32;
+        // a degree of longitude shrinks towards the poles, look around the whole parallel near them
+        let widest_latitude = (center.latitude.abs() + span * CELL_DEGREES).min(90.0);
+        let columns = match widest_latitude < 89.0 {
+            true => ((span / widest_latitude.to_radians().cos()).ceil() as i32).min(max_column / 2),
+            false => max_column / 2,
+        };
+
+        let mut found: Vec<NearbyAirport> = vec![];
+        let mut visited = HashSet::new();
+        for row in row - rows..=row + rows {
+            for column in column - columns..=column + columns {
+                let key = (row, normalized_column(column, max_column));
+                if !visited.insert(key) {
+                    continue;
+                }
+                let airports = match self.cells.get(&key) {
+                    Some(airports) => airports,
+                    None => continue,
+                };
+                found.extend(airports.iter()
+                    .map(|(airport, location)| NearbyAirport {
+                        airport: airport.clone(),
+                        distance: center.distance_to(location),
+                    })
+                    .filter(|nearby| nearby.distance <= radius));
+            }
+        }
+        found.sort_by(|a, b| a.distance.total_cmp(&b.distance).then(a.airport.id.cmp(&b.airport.id)));
+        found.truncate(limit);
+        found
+    }
+}
+
+fn cell_of(location: &Coordinates) -> (i32, i32) {
+    let max_column = (360.0 / CELL_DEGREES) as i32;
+    (
+        (location.latitude / CELL_DEGREES).floor() as i32,
+        normalized_column((location.longitude / CELL_DEGREES).floor() as i32, max_column),
+    )
+}
+
+/// Wraps a column index around the antimeridian
+fn normalized_column(column: i32, max_column: i32) -> i32 {
+    let half = max_column / 2;
+    (column + half).rem_euclid(max_column) - half
+}
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod spatial_tests {
+    use crate::model::{
+        Airport,
+        AirportIndex,
+        Coordinates,
+        fixtures::located_airport,
+    };
+
+    fn ids(index: &AirportIndex, latitude: f64, longitude: f64, radius_km: f64, limit: usize) -> Vec<i64> {
+        index.nearby(&Coordinates { latitude, longitude }, radius_km * 1000.0, limit)
+            .iter()
+            .map(|n| n.airport.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_nearby_airports_ordered_by_distance() {
+        let index = AirportIndex::new(vec![
+            located_airport(1, 44.8184, 20.3091),
+            located_airport(2, 45.2461, 19.8517),
+            located_airport(3, 43.3372, 21.8536),
+            located_airport(4, 51.4700, -0.4543),
+            Airport { location: None, ..located_airport(5, 0.0, 0.0) },
+        ]);
+
+        assert_eq!(vec![1, 2, 3], ids(&index, 44.8, 20.4, 300.0, 10));
+        assert_eq!(vec![1, 2], ids(&index, 44.8, 20.4, 300.0, 2));
+        assert_eq!(vec![1], ids(&index, 44.8, 20.4, 20.0, 10));
+        assert!(ids(&index, 0.0, 0.0, 100.0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_nearby_airports_across_antimeridian() {
+        let index = AirportIndex::new(vec![
+            located_airport(1, -17.7553, 177.4433),
+            located_airport(2, -13.8300, -171.9972),
+        ]);
+
+        assert_eq!(vec![2, 1], ids(&index, -14.0, -172.5, 1500.0, 10));
+        assert_eq!(vec![1], ids(&index, -17.0, 179.9, 400.0, 10));
+    }
+}
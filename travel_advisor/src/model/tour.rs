@@ -0,0 +1,122 @@
+/// Largest number of cities to visit for which the order is searched exhaustively
+pub const EXACT_TOUR_LIMIT: usize = 12;
+
+/// Orders stops so that the sum of costs between consecutive stops is low.
+///
+/// `costs[from][to]` is the cost of getting from one stop to another, `None` if impossible.
+/// Stop 0 is the origin and always comes first. When the tour is `closed`, getting back from
+/// the last stop to the origin is included. Up to `EXACT_TOUR_LIMIT` stops besides the origin
+/// the cheapest order is found, beyond that the order is only improved until no reversal of
+/// a part of it makes it cheaper.
+/// Returns `None` if no order visits all stops.
+pub fn visiting_order(costs: &[Vec<Option<i64>>], closed: bool) -> Option<Vec<usize>> {
+    if costs.len() <= 1 {
+        return Some(vec![0]);
+    }
+    match costs.len() - 1 <= EXACT_TOUR_LIMIT {
+        true => exact_order(costs, closed),
+        false => heuristic_order(costs, closed),
+    }
+}
+
+/// Total cost of visiting stops in the given order
+pub fn order_cost(costs: &[Vec<Option<i64>>], order: &[usize], closed: bool) -> Option<i64> {
+    let mut total = 0;
+    for pair in order.windows(2) {
+        total += costs[pair[0]][pair[1]]?;
+    }
+    if closed && order.len() > 1 {
+        total += costs[order[order.len() - 1]][order[0]]?;
+    }
+    Some(total)
+}
+
+/// Held-Karp dynamic programming over subsets of visited stops
+fn exact_order(costs: &[Vec<Option<i64>>], closed: bool) -> Option<Vec<usize>> {
+    let stops = costs.len() - 1;
+    let full = (1usize << stops) - 1;
+    // best[mask][last] = (cost, previous stop) of visiting stops in `mask` ending at `last`,
+    // stop `i` being bit `i - 1`
+    let mut best: Vec<Vec<Option<(i64, usize)>>> = vec![vec![None; stops + 1]; full + 1];
+    for stop in 1..=stops {
+        best[1 << (stop - 1)][stop] = costs[0][stop].map(|cost| (cost, 0));
+    }
+    for mask in 1..=full {
+        for last in 1..=stops {
+            let (cost, _) = match best[mask][last] {
+                Some(entry) => entry,
+                None => continue,
+            };
+            for next in 1..=stops {
+                let bit = 1 << (next - 1);
+                if mask & bit != 0 {
+                    continue;
+                }
+                let next_cost = match costs[last][next] {
+                    Some(leg) => cost + leg,
+                    None => continue,
+                };
+                if best[mask | bit][next].is_none_or(|(c, _)| next_cost < c) {
+                    best[mask | bit][next] = Some((next_cost, last));
+                }
+            }
+        }
+    }
+
+    let mut last = (1..=stops)
+        .filter_map(|last| {
+            let (cost, _) = best[full][last]?;
+            match closed {
+                true => costs[last][0].map(|back| (cost + back, last)),
+                false => Some((cost, last)),
+            }
+        })
+        .min()?
+        .1;
+    let mut order = vec![];
+    let mut mask = full;
+    while last != 0 {
+        order.push(last);
+        let (_, previous) = best[mask][last]?;
+        mask &= !(1 << (last - 1));
+        last = previous;
+    }
+    order.push(0);
+    order.reverse();
+    Some(order)
+}
+
+/// Nearest neighbour order improved by reversing parts of it
+fn heuristic_order(costs: &[Vec<Option<i64>>], closed: bool) -> Option<Vec<usize>> {
+    let mut order = vec![0];
+    let mut remaining: Vec<usize> = (1..costs.len()).collect();
+    while !remaining.is_empty() {
+        let last = order[order.len() - 1];
+        let (index, _) = remaining.iter()
+            .enumerate()
+            .filter_map(|(index, stop)| costs[last][*stop].map(|cost| (index, cost)))
+            .min_by_key(|(_, cost)| *cost)
+            // no direct way to any remaining stop, let improvement sort it out
+            .unwrap_or((0, 0));
+        order.push(remaining.remove(index));
+    }
+
+    let mut current = order_cost(costs, &order, closed);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for from in 1..order.len() - 1 {
+            for to in from + 1..order.len() {
+                let mut candidate = order.clone();
+                candidate[from..=to].reverse();
+                let cost = order_cost(costs, &candidate, closed);
+                if cost.is_some() && current.is_none_or(|c| cost < Some(c)) {
+                    order = candidate;
+                    current = cost;
+                    improved = true;
+                }
+            }
+        }
+    }
+    current.map(|_| order)
+}
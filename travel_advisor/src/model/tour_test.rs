@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tour_tests {
+    use super::super::tour::{
+        EXACT_TOUR_LIMIT,
+        order_cost,
+        visiting_order,
+    };
+
+    /// Stops on a line at given positions, where flying back costs twice as much
+    fn line_costs(positions: &[i64]) -> Vec<Vec<Option<i64>>> {
+        positions.iter()
+            .map(|from| positions.iter()
+                .map(|to| match to >= from {
+                    true => Some(to - from),
+                    false => Some(2 * (from - to)),
+                })
+                .collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_exact_order_open_tour() {
+        let costs = line_costs(&[0, 30, 10, 20]);
+
+        assert_eq!(Some(vec![0, 2, 3, 1]), visiting_order(&costs, false));
+    }
+
+    #[test]
+    fn test_exact_order_closed_tour() {
+        let costs = vec![
+            vec![None, Some(10), Some(15), Some(20)],
+            vec![Some(10), None, Some(35), Some(25)],
+            vec![Some(15), Some(35), None, Some(30)],
+            vec![Some(20), Some(25), Some(30), None],
+        ];
+
+        let order = visiting_order(&costs, true).unwrap();
+
+        assert_eq!(Some(80), order_cost(&costs, &order, true));
+    }
+
+    #[test]
+    fn test_order_skips_impossible_legs() {
+        let mut costs = line_costs(&[0, 10, 20]);
+        costs[1][2] = None;
+
+        assert_eq!(Some(vec![0, 2, 1]), visiting_order(&costs, false));
+
+        costs[2][1] = None;
+        assert_eq!(None, visiting_order(&costs, false));
+    }
+
+    #[test]
+    fn test_heuristic_order_for_many_stops() {
+        let positions: Vec<i64> = (0..=EXACT_TOUR_LIMIT as i64 + 3).map(|i| (i * 7) % 19 * 10).collect();
+        let costs = line_costs(&positions);
+
+        let order = visiting_order(&costs, false).unwrap();
+
+        let mut visited = order.clone();
+        visited.sort();
+        assert_eq!((0..positions.len()).collect::<Vec<usize>>(), visited);
+        assert_eq!(0, order[0]);
+        // going up the line and never back is optimal
+        assert_eq!(Some(positions.iter().max().unwrap() - positions[0]), order_cost(&costs, &order, false));
+    }
+}
@@ -0,0 +1,9 @@
+/// Ground transport from one airport to another airport of the same city
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    pub id: i64,
+    pub from_airport_id: i64,
+    pub to_airport_id: i64,
+    pub price: i64,
+    pub duration_minutes: i32,
+}
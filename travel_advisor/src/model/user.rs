@@ -35,4 +35,8 @@ impl User {
         self.roles.iter().any(|r| r == "admin")
     }
 
+    pub fn is_moderator(&self) -> bool {
+        self.roles.iter().any(|r| r == "moderator")
+    }
+
 }
\ No newline at end of file
@@ -1,10 +1,24 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    airport_transfers (id) {
+        id -> Bigint,
+        from_airport_id -> Bigint,
+        to_airport_id -> Bigint,
+        price -> Bigint,
+        duration_minutes -> Integer,
+    }
+}
+
 diesel::table! {
     airports (id) {
         id -> Bigint,
         city_id -> Bigint,
         name -> Varchar,
+        latitude -> Nullable<Double>,
+        longitude -> Nullable<Double>,
+        iata_code -> Nullable<Char>,
+        icao_code -> Nullable<Char>,
     }
 }
 
@@ -12,6 +26,42 @@ diesel::table! {
     cities (id) {
         id -> Bigint,
         name -> Varchar,
+        country_code -> Nullable<Char>,
+        region_id -> Nullable<Bigint>,
+        time_zone -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    city_aliases (id) {
+        id -> Bigint,
+        city_id -> Bigint,
+        name -> Varchar,
+        language -> Varchar,
+    }
+}
+
+diesel::table! {
+    city_tags (city_id, tag) {
+        city_id -> Bigint,
+        tag -> Varchar,
+    }
+}
+
+diesel::table! {
+    comment_reactions (comment_id, user_id) {
+        comment_id -> Bigint,
+        user_id -> Bigint,
+        kind -> Varchar,
+    }
+}
+
+diesel::table! {
+    comment_revisions (id) {
+        id -> Bigint,
+        comment_id -> Bigint,
+        text -> Varchar,
+        written_at -> Timestamp,
     }
 }
 
@@ -23,6 +73,69 @@ diesel::table! {
         text -> Varchar,
         updated_at -> Timestamp,
         created_at -> Timestamp,
+        parent_id -> Nullable<Bigint>,
+        deleted -> Bool,
+        hidden -> Bool,
+    }
+}
+
+diesel::table! {
+    comment_flags (id) {
+        id -> Bigint,
+        comment_id -> Bigint,
+        user_id -> Bigint,
+        reason -> Varchar,
+        resolved -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    countries (code) {
+        code -> Char,
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    points_of_interest (id) {
+        id -> Bigint,
+        city_id -> Bigint,
+        name -> Varchar,
+        category -> Varchar,
+        description -> Text,
+        latitude -> Nullable<Double>,
+        longitude -> Nullable<Double>,
+    }
+}
+
+diesel::table! {
+    moderation_records (id) {
+        id -> Bigint,
+        comment_id -> Bigint,
+        comment_user_id -> Bigint,
+        comment_text -> Varchar,
+        moderator_id -> Bigint,
+        action -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    regions (id) {
+        id -> Bigint,
+        country_code -> Char,
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    route_schedules (id) {
+        id -> Bigint,
+        route_id -> Bigint,
+        departure_time -> Time,
+        duration_minutes -> Integer,
+        days_of_week -> Unsigned<Tinyint>,
     }
 }
 
@@ -45,13 +158,36 @@ diesel::table! {
 }
 
 diesel::joinable!(airports -> cities (city_id));
+diesel::joinable!(cities -> countries (country_code));
+diesel::joinable!(city_aliases -> cities (city_id));
+diesel::joinable!(cities -> regions (region_id));
+diesel::joinable!(city_tags -> cities (city_id));
+diesel::joinable!(comment_flags -> comments (comment_id));
+diesel::joinable!(comment_flags -> users (user_id));
+diesel::joinable!(comment_reactions -> comments (comment_id));
+diesel::joinable!(comment_reactions -> users (user_id));
+diesel::joinable!(comment_revisions -> comments (comment_id));
 diesel::joinable!(comments -> cities (city_id));
 diesel::joinable!(comments -> users (user_id));
+diesel::joinable!(points_of_interest -> cities (city_id));
+diesel::joinable!(regions -> countries (country_code));
+diesel::joinable!(route_schedules -> routes (route_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    airport_transfers,
     airports,
     cities,
+    city_aliases,
+    city_tags,
+    comment_flags,
+    comment_reactions,
+    comment_revisions,
     comments,
+    countries,
+    moderation_records,
+    points_of_interest,
+    regions,
+    route_schedules,
     routes,
     users,
 );
@@ -1,10 +1,22 @@
 pub mod services {
-    use std::sync::Arc;
+    use std::sync::{
+        Arc,
+        RwLock,
+    };
 
     use log::error;
 
     use crate::{
-        model::Airport,
+        model::{
+            Airport,
+            AirportIndex,
+            AirportRef,
+            Coordinates,
+            NearbyAirport,
+            is_iata_code,
+            is_icao_code,
+            Transfer,
+        },
         util::{
             Error,
             ErrorCode::{
@@ -12,52 +24,59 @@ pub mod services {
                 TextRowParse,
             },
         },
-        services::traits::AirportService,
+        services::traits::{
+            AirportService,
+            SearchService,
+        },
         storage::{
             AirportRepository,
             CityRepository,
+            TransferRepository,
         },
     };
     use super::super::macros::log_if_error;
 
+    /// Finds the ID of an airport given by ID or by IATA or ICAO code
+    pub fn resolve_airport(airport_repo: &(dyn AirportRepository + Sync + Send), reference: &AirportRef) -> Result<i64, Error> {
+        match reference {
+            AirportRef::Id(id) => Ok(*id),
+            AirportRef::Code(code) => match airport_repo.get_by_code(code.to_ascii_uppercase()) {
+                Ok(Some(airport)) => Ok(airport.id),
+                Ok(None) => Err(Error::not_found(format!("airport with code {} not found", code))),
+                Err(err) => {
+                    error!("failed to load airport with code {}: {}", code, err);
+                    Err(err.wrap(format!("failed to load airport with code {}", code)))
+                },
+            },
+        }
+    }
+
     pub fn new_airport_service(
         city_repo: Arc<dyn CityRepository + Sync + Send>,
         airport_repo: Arc<dyn AirportRepository + Sync + Send>,
+        transfer_repo: Arc<dyn TransferRepository + Sync + Send>,
+        search_service: Arc<dyn SearchService + Sync + Send>,
     ) -> Arc<impl AirportService> {
         Arc::new(AirportServiceImpl {
             airport_repo: airport_repo,
             city_repo: city_repo,
+            transfer_repo,
+            search_service,
+            index: RwLock::new(None),
         })
     }
 
     struct AirportServiceImpl {
         city_repo: Arc<dyn CityRepository + Sync + Send>,
         airport_repo: Arc<dyn AirportRepository + Sync + Send>,
+        transfer_repo: Arc<dyn TransferRepository + Sync + Send>,
+        search_service: Arc<dyn SearchService + Sync + Send>,
+        /// Airports by location, built on first lookup and rebuilt whenever airports change
+        index: RwLock<Option<Arc<AirportIndex>>>,
     }
 
-    impl AirportService for AirportServiceImpl {
-
-        fn get_all(&self) -> Result<Vec<Airport>, Error> {
-            log_if_error!(self.airport_repo.get_all())
-        }
-
-        fn get_by_id(&self, id: i64) -> Result<Option<Airport>, Error> {
-            log_if_error!(self.airport_repo.get_by_id(id))
-        }
-
-        fn create(&self, airport: Airport) -> Result<Airport, Error> {
-            log_if_error!(self.airport_repo.new(&airport))
-        }
-
-        fn update(&self, airport: Airport) -> Result<(), Error> {
-            log_if_error!(self.airport_repo.update(airport))
-        }
-
-        fn delete(&self, id: i64) -> Result<(), Error> {
-            log_if_error!(self.airport_repo.delete(id))
-        }
-
-        fn save_airports(&self, sv_text: &[u8]) -> Result<(), Error> {
+    impl AirportServiceImpl {
+        fn import_airports(&self, sv_text: &[u8]) -> Result<(), Error> {
             let mut count: i64 = 0;
             let mut csv_reader = csv::Reader::from_reader(sv_text);
             for record in csv_reader.records() {
@@ -82,10 +101,42 @@ pub mod services {
                         return Err(err.wrap(format!("only pocessed {}: failed to load city {}", count, city_name)));
                     },
                 };
+                let mut coordinates = [None, None];
+                for (index, column) in [2, 3].iter().enumerate() {
+                    coordinates[index] = match record.get(*column).map(|v| v.trim()).filter(|v| !v.is_empty()) {
+                        Some(value) => match value.parse::<f64>() {
+                            Ok(number) => Some(number),
+                            Err(err) => {
+                                error!("only pocessed {}: malformed coordinate {}: {}", count, value, err);
+                                return Err(Error::internal(TextRowParse, format!("only pocessed {}: malformed coordinate {}: {}", count, value, err)));
+                            },
+                        },
+                        None => None,
+                    };
+                }
+                let location = Coordinates::from_parts(coordinates[0], coordinates[1]);
+                if location.is_some_and(|l| !l.is_valid()) || coordinates[0].is_some() != coordinates[1].is_some() {
+                    error!("only pocessed {}: invalid coordinates of airport {}", count, &record[1]);
+                    return Err(Error::internal(TextRowParse, format!("only pocessed {}: invalid coordinates of airport {}", count, &record[1])));
+                }
+                let mut codes = [None, None];
+                for (index, (column, valid)) in [(4, is_iata_code as fn(&str) -> bool), (5, is_icao_code)].iter().enumerate() {
+                    codes[index] = match record.get(*column).map(|v| v.trim().to_ascii_uppercase()).filter(|v| !v.is_empty()) {
+                        Some(code) if !valid(&code) => {
+                            error!("only pocessed {}: malformed airport code {}", count, code);
+                            return Err(Error::internal(TextRowParse, format!("only pocessed {}: malformed airport code {}", count, code)));
+                        },
+                        code => code,
+                    };
+                }
+                let [iata_code, icao_code] = codes;
                 let airport = Airport {
                     id: 0,
                     city_id: city.id,
                     name: record[1].to_string(),
+                    location,
+                    iata_code,
+                    icao_code,
                 };
                 match self.airport_repo.new(&airport) {
                     Ok(_) => count += 1,
@@ -99,6 +150,113 @@ pub mod services {
             Ok(())
         }
 
+        fn loaded_index(&self) -> Result<Arc<AirportIndex>, Error> {
+            if let Some(index) = self.index.read().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref() {
+                return Ok(index.clone());
+            }
+            let airports = match self.airport_repo.get_all() {
+                Ok(airports) => airports,
+                Err(err) => {
+                    error!("failed to load airports for index: {}", err);
+                    return Err(err.wrap_str("failed to load airports for index"));
+                },
+            };
+            let index = Arc::new(AirportIndex::new(airports));
+            *self.index.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(index.clone());
+            Ok(index)
+        }
+    }
+
+    impl AirportService for AirportServiceImpl {
+
+        fn get_all(&self) -> Result<Vec<Airport>, Error> {
+            log_if_error!(self.airport_repo.get_all())
+        }
+
+        fn get_by_id(&self, id: i64) -> Result<Option<Airport>, Error> {
+            log_if_error!(self.airport_repo.get_by_id(id))
+        }
+
+        fn create(&self, airport: Airport) -> Result<Airport, Error> {
+            let created = log_if_error!(self.airport_repo.new(&airport))?;
+            self.refresh_index();
+            Ok(created)
+        }
+
+        fn update(&self, airport: Airport) -> Result<(), Error> {
+            log_if_error!(self.airport_repo.update(airport))?;
+            self.refresh_index();
+            Ok(())
+        }
+
+        fn delete(&self, id: i64) -> Result<(), Error> {
+            log_if_error!(self.airport_repo.delete(id))?;
+            self.refresh_index();
+            Ok(())
+        }
+
+        fn save_airports(&self, sv_text: &[u8]) -> Result<(), Error> {
+            let result = self.import_airports(sv_text);
+            // rows before a failing one are saved as well
+            self.refresh_index();
+            result
+        }
+
+        fn get_transfers(&self, airport_id: i64) -> Result<Vec<Transfer>, Error> {
+            log_if_error!(self.transfer_repo.find_from(airport_id))
+        }
+
+        fn save_transfer(&self, transfer: Transfer) -> Result<Transfer, Error> {
+            if transfer.from_airport_id == transfer.to_airport_id {
+                return Err(Error::bad_request("transfer must connect two different airports".to_string()));
+            }
+            let mut city_ids = vec![];
+            for airport_id in [transfer.from_airport_id, transfer.to_airport_id] {
+                match self.airport_repo.get_by_id(airport_id) {
+                    Ok(Some(airport)) => city_ids.push(airport.city_id),
+                    Ok(None) => return Err(Error::not_found(format!("airport with ID={} not found", airport_id))),
+                    Err(err) => {
+                        error!("failed to load airport {}: {}", airport_id, err);
+                        return Err(err.wrap(format!("failed to load airport {}", airport_id)));
+                    },
+                };
+            }
+            if city_ids[0] != city_ids[1] {
+                return Err(Error::bad_request("transfer must connect airports of the same city".to_string()));
+            }
+            log_if_error!(self.transfer_repo.save(transfer))
+        }
+
+        fn delete_transfer(&self, from_airport_id: i64, to_airport_id: i64) -> Result<(), Error> {
+            log_if_error!(self.transfer_repo.delete(from_airport_id, to_airport_id))
+        }
+
+        fn get_by_code(&self, code: &str) -> Result<Option<Airport>, Error> {
+            log_if_error!(self.airport_repo.get_by_code(code.to_ascii_uppercase()))
+        }
+
+        fn resolve(&self, reference: &AirportRef) -> Result<i64, Error> {
+            resolve_airport(self.airport_repo.as_ref(), reference)
+        }
+
+        fn find_nearby(&self, center: Coordinates, radius: f64, limit: usize) -> Result<Vec<NearbyAirport>, Error> {
+            Ok(self.loaded_index()?.nearby(&center, radius, limit))
+        }
+
+        /// Reloads the spatial index, then the search index. When loading fails the spatial index
+        /// is dropped, so that the next lookup tries again.
+        fn refresh_index(&self) {
+            let index = match self.airport_repo.get_all() {
+                Ok(airports) => Some(Arc::new(AirportIndex::new(airports))),
+                Err(err) => {
+                    error!("failed to rebuild airport index: {}", err);
+                    None
+                },
+            };
+            *self.index.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = index;
+            self.search_service.refresh_index();
+        }
+
     }
 
 }
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod airport_service_tests {
+
+    use std::sync::Arc;
+
+    use mockall::Sequence;
+
+    use crate::{
+        model::{
+            Coordinates,
+            fixtures::located_airport,
+        },
+        storage::{
+            MockAirportRepository,
+            MockCityRepository,
+            MockTransferRepository,
+        },
+    };
+    use super::super::{
+        airport_service::services::new_airport_service,
+        traits::{
+            AirportService,
+            MockSearchService,
+        },
+    };
+
+    #[test]
+    fn airport_added_after_startup_is_found_once_index_is_refreshed() {
+        let belgrade = Coordinates { latitude: 44.82, longitude: 20.29 };
+        let mut sequence = Sequence::new();
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_all()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(vec![located_airport(1, 44.82, 20.29)]));
+        airport_mock.expect_get_all()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|| Ok(vec![located_airport(1, 44.82, 20.29), located_airport(2, 45.25, 19.85)]));
+        let mut search_mock = MockSearchService::default();
+        search_mock.expect_refresh_index()
+            .times(1)
+            .return_const(());
+
+        let service = new_airport_service(
+            Arc::new(MockCityRepository::default()),
+            Arc::new(airport_mock),
+            Arc::new(MockTransferRepository::default()),
+            Arc::new(search_mock),
+        );
+        let nearby_ids = |service: &dyn AirportService| -> Vec<i64> {
+            service.find_nearby(belgrade, 100_000.0, 10).unwrap().iter().map(|n| n.airport.id).collect()
+        };
+
+        assert_eq!(vec![1], nearby_ids(service.as_ref()));
+        // the index built by the first lookup is reused until refreshed
+        assert_eq!(vec![1], nearby_ids(service.as_ref()));
+        service.refresh_index();
+        assert_eq!(vec![1, 2], nearby_ids(service.as_ref()));
+    }
+
+}
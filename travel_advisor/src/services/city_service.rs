@@ -1,36 +1,191 @@
 pub mod services {
-    use std::sync::Arc;
+    use std::{
+        collections::HashMap,
+        sync::Arc,
+    };
 
     use log::error;
     use async_trait::async_trait;
+    use chrono_tz::Tz;
     
     use crate::{
         AirportRepository,
         CityRepository,
-        model::City,
-        services::traits::CityService,
-        util::Error,
+        model::{
+            City,
+            CityAlias,
+            CityDeletePolicy,
+            CityReferences,
+            CityTag,
+            TagMatch,
+            is_country_code,
+        },
+        services::{
+            country_service::services::existing_country,
+            traits::{
+                CityService,
+                SearchService,
+            },
+        },
+        storage::{
+            CommentRepository,
+            CountryRepository,
+            PoiRepository,
+            routes::RouteRepository,
+        },
+        util::{
+            Error,
+            ErrorCode::CityInUse,
+        },
     };
 
     pub struct CityServiceImpl {
         city_repo: Arc<dyn CityRepository + Sync + Send>,
         airport_repo: Arc<dyn AirportRepository + Sync + Send>,
+        country_repo: Arc<dyn CountryRepository + Sync + Send>,
+        route_repo: Arc<dyn RouteRepository + Sync + Send>,
+        comment_repo: Arc<dyn CommentRepository + Sync + Send>,
+        poi_repo: Arc<dyn PoiRepository + Sync + Send>,
+        search_service: Arc<dyn SearchService + Sync + Send>,
     }
 
     pub fn new_city_service(
         city_repo: Arc<dyn CityRepository + Sync + Send>,
         airport_repo: Arc<dyn AirportRepository + Sync + Send>,
+        country_repo: Arc<dyn CountryRepository + Sync + Send>,
+        route_repo: Arc<dyn RouteRepository + Sync + Send>,
+        comment_repo: Arc<dyn CommentRepository + Sync + Send>,
+        poi_repo: Arc<dyn PoiRepository + Sync + Send>,
+        search_service: Arc<dyn SearchService + Sync + Send>,
     ) -> Arc<impl CityService> {
         Arc::new(CityServiceImpl {
             city_repo: city_repo,
             airport_repo: airport_repo,
+            country_repo,
+            route_repo,
+            comment_repo,
+            poi_repo,
+            search_service,
         })
     }
 
+    impl CityServiceImpl {
+        /// Checks that the country exists and that the region belongs to it
+        fn check_country(&self, country_code: &Option<String>, region_id: Option<i64>) -> Result<(), Error> {
+            match country_code {
+                Some(code) if !is_country_code(code) =>
+                    Err(Error::bad_request("country code must be two uppercase letters".to_string())),
+                Some(code) => {
+                    let country = existing_country(self.country_repo.as_ref(), code)?;
+                    if region_id.is_some_and(|id| !country.regions.iter().any(|r| r.id == id)) {
+                        return Err(Error::bad_request(format!("region does not belong to {}", code)));
+                    }
+                    Ok(())
+                },
+                None if region_id.is_some() =>
+                    Err(Error::bad_request("region requires a country".to_string())),
+                None => Ok(()),
+            }
+        }
+
+        fn import_cities(&self, sv_text: &[u8]) -> Result<(), Error> {
+            let mut count: i64 = 0;
+
+            // country code and time zone columns may be left out
+            let mut csv_reader = csv::ReaderBuilder::new().flexible(true).from_reader(sv_text);
+            for record in csv_reader.records() {
+                let record = match record {
+                    Ok(r) => r,
+                    Err(err) => {
+                        error!("only pocessed {}: malformed CSV: {}", count, err.to_string());
+                        return Err(Error::internal(
+                            crate::util::ErrorCode::TextRowParse,
+                            format!("only pocessed {}: malformed CSV: {}", count, err.to_string())
+                        ));
+                    },
+                };
+        
+                let name = record[0].to_string();
+                let country_code = record.get(1)
+                    .map(|c| c.trim().to_ascii_uppercase())
+                    .filter(|c| !c.is_empty());
+                if let Some(code) = &country_code {
+                    if let Err(err) = existing_country(self.country_repo.as_ref(), code) {
+                        return Err(err.wrap(format!("only pocessed {}", count)));
+                    }
+                }
+                let time_zone = match record.get(2).map(|tz| tz.trim()).filter(|tz| !tz.is_empty()) {
+                    Some(tz) => match tz.parse::<Tz>() {
+                        Ok(tz) => Some(tz),
+                        Err(_) => {
+                            error!("only pocessed {}: unknown time zone {} in row {}", count, tz, count + 1);
+                            return Err(Error::bad_request_with_code(
+                                crate::util::ErrorCode::TextRowParse,
+                                format!("only pocessed {}: unknown time zone {} in row {}", count, tz, count + 1)
+                            ));
+                        },
+                    },
+                    None => None,
+                };
+                let city = match self.city_repo.new(name.clone()) {
+                    Err(err) => {
+                        error!("only pocessed {}: failed to save city: {}", count, err.to_string());
+                        return Err(err.wrap(format!("only pocessed {}: failed to save city", count)));
+                    },
+                    Ok(city) => city,
+                };
+                if country_code.is_some() {
+                    if let Err(err) = self.city_repo.set_country(city.id, country_code, None) {
+                        error!("only pocessed {}: failed to set country of city: {}", count, err);
+                        return Err(err.wrap(format!("only pocessed {}: failed to set country of city", count)));
+                    }
+                }
+                if time_zone.is_some() {
+                    if let Err(err) = self.set_time_zone(city.id, time_zone) {
+                        return Err(err.wrap(format!("only pocessed {}", count)));
+                    }
+                }
+
+                count += 1;
+            };
+        
+            Ok(())
+        }
+
+        fn attach_tags(&self, cities: &mut [City]) -> Result<(), Error> {
+            if cities.is_empty() {
+                return Ok(());
+            }
+            let tags = match self.city_repo.get_tags(cities.iter().map(|c| c.id).collect()) {
+                Ok(tags) => tags,
+                Err(err) => {
+                    error!("failed to load city tags: {}", err);
+                    return Err(err.wrap_str("failed to load city tags"));
+                },
+            };
+            for city in cities.iter_mut() {
+                city.tags = tags.iter().filter(|t| t.city_id == city.id).map(|t| t.tag.clone()).collect();
+            }
+            Ok(())
+        }
+
+        fn city_exists(&self, id: i64) -> Result<bool, Error> {
+            match self.city_repo.get_by_id(id) {
+                Ok(city) => Ok(city.is_some()),
+                Err(err) => {
+                    error!("failed to load city {}: {}", id, err);
+                    Err(err.wrap(format!("failed to load city {}", id)))
+                },
+            }
+        }
+    }
+
     #[async_trait]
     impl CityService for CityServiceImpl {
         fn get_all(&self) -> Result<Vec<City>, Error> {
-            self.city_repo.get_all()
+            let mut cities = self.city_repo.get_all()?;
+            self.attach_tags(&mut cities)?;
+            Ok(cities)
         }
 
         fn get_full(&self, id: i64) -> Result<Option<City>, Error> {
@@ -51,43 +206,273 @@ pub mod services {
                     return Err(Error::internal(crate::util::ErrorCode::DbRead, err.to_string()));
                 },
             };
+            if let Some(code) = city.country_code.clone() {
+                let country = existing_country(self.country_repo.as_ref(), &code)?;
+                city.region = country.regions.iter()
+                    .find(|r| Some(r.id) == city.region_id)
+                    .cloned();
+                city.country = Some(country);
+            }
+            city.aliases = self.get_aliases(id)?;
+            self.attach_tags(std::slice::from_mut(&mut city))?;
 
             Ok(Some(city))
         }
 
+        fn find_by_tags(&self, tags: Vec<String>, tag_match: TagMatch) -> Result<Vec<City>, Error> {
+            let tagged = match self.city_repo.find_tagged(tags.clone()) {
+                Ok(tagged) => tagged,
+                Err(err) => {
+                    error!("failed to find tagged cities: {}", err);
+                    return Err(err.wrap_str("failed to find tagged cities"));
+                },
+            };
+            let mut city_ids: Vec<i64> = tagged.iter().map(|t| t.city_id).collect();
+            city_ids.dedup();
+            city_ids.retain(|id| {
+                let city_tags: Vec<String> = tagged.iter()
+                    .filter(|t| t.city_id == *id)
+                    .map(|t| t.tag.clone())
+                    .collect();
+                tag_match.matches(&city_tags, &tags)
+            });
+            if city_ids.is_empty() {
+                return Ok(vec![]);
+            }
+            let mut cities = match self.city_repo.get_by_ids(city_ids) {
+                Ok(cities) => cities,
+                Err(err) => {
+                    error!("failed to load tagged cities: {}", err);
+                    return Err(err.wrap_str("failed to load tagged cities"));
+                },
+            };
+            self.attach_tags(&mut cities)?;
+            Ok(cities)
+        }
+
         fn new(&self, name: String) -> Result<City, Error> {
-            self.city_repo.new(name)
+            let city = self.city_repo.new(name)?;
+            self.search_service.refresh_index();
+            Ok(city)
         }
 
         fn save_cities(&self, sv_text: &[u8]) -> Result<(), Error> {
-            let mut count: i64 = 0;
+            let result = self.import_cities(sv_text);
+            // rows before a failing one are saved as well
+            self.search_service.refresh_index();
+            result
+        }
 
-            let mut csv_reader = csv::Reader::from_reader(sv_text);
-            for record in csv_reader.records() {
-                let record = match record {
-                    Ok(r) => r,
-                    Err(err) => {
-                        error!("only pocessed {}: malformed CSV: {}", count, err.to_string());
-                        return Err(Error::internal(
-                            crate::util::ErrorCode::TextRowParse,
-                            format!("only pocessed {}: malformed CSV: {}", count, err.to_string())
-                        ));
-                    },
-                };
-        
-                let name = record[0].to_string();
-                match self.city_repo.new(name.clone()) {
+        fn set_country(&self, city_id: i64, country_code: Option<String>, region_id: Option<i64>) -> Result<(), Error> {
+            self.check_country(&country_code, region_id)?;
+            match self.city_repo.set_country(city_id, country_code, region_id) {
+                Ok(()) => {
+                    self.search_service.refresh_index();
+                    Ok(())
+                },
+                Err(err) => {
+                    error!("failed to set country of city {}: {}", city_id, err);
+                    Err(err.wrap(format!("failed to set country of city {}", city_id)))
+                },
+            }
+        }
+
+        fn set_time_zone(&self, city_id: i64, time_zone: Option<Tz>) -> Result<(), Error> {
+            match self.city_repo.set_time_zone(city_id, time_zone.map(|tz| tz.name().to_string())) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    error!("failed to set time zone of city {}: {}", city_id, err);
+                    Err(err.wrap(format!("failed to set time zone of city {}", city_id)))
+                },
+            }
+        }
+
+        fn time_zones(&self, mut city_ids: Vec<i64>) -> Result<HashMap<i64, Tz>, Error> {
+            city_ids.sort();
+            city_ids.dedup();
+            match self.city_repo.get_by_ids(city_ids) {
+                Ok(cities) => Ok(cities.iter()
+                    .filter_map(|c| c.time_zone.map(|tz| (c.id, tz)))
+                    .collect()),
+                Err(err) => {
+                    error!("failed to load cities: {}", err);
+                    Err(err.wrap_str("failed to load cities"))
+                },
+            }
+        }
+
+        fn update(&self, city: City) -> Result<(), Error> {
+            self.check_country(&city.country_code, city.region_id)?;
+            match self.city_repo.update(&city) {
+                Ok(()) => {
+                    self.search_service.refresh_index();
+                    Ok(())
+                },
+                Err(err) => {
+                    error!("failed to update city {}: {}", city.id, err);
+                    Err(err.wrap(format!("failed to update city {}", city.id)))
+                },
+            }
+        }
+
+        fn references(&self, id: i64) -> Result<CityReferences, Error> {
+            let airport_ids: Vec<i64> = match self.airport_repo.get_by_city_id(id) {
+                Ok(airports) => airports.iter().map(|a| a.id).collect(),
+                Err(err) => {
+                    error!("failed to load airports of city {}: {}", id, err);
+                    return Err(err.wrap_str("failed to load airports"));
+                },
+            };
+            let route_ids = if airport_ids.is_empty() {
+                vec![]
+            } else {
+                match self.route_repo.find_by_airports(airport_ids.clone()) {
+                    Ok(routes) => routes.iter().map(|r| r.id).collect(),
                     Err(err) => {
-                        error!("only pocessed {}: failed to save city: {}", count, err.to_string());
-                        return Err(err.wrap(format!("only pocessed {}: failed to save city", count)));
+                        error!("failed to load routes of city {}: {}", id, err);
+                        return Err(err.wrap_str("failed to load routes"));
                     },
-                    _ => (),
-                };
+                }
+            };
+            let comment_ids = match self.comment_repo.get_by_city(id) {
+                Ok(comments) => comments.iter().map(|c| c.id).collect(),
+                Err(err) => {
+                    error!("failed to load comments of city {}: {}", id, err);
+                    return Err(err.wrap_str("failed to load comments"));
+                },
+            };
+            let poi_ids = match self.poi_repo.get_by_city(id, vec![]) {
+                Ok(pois) => pois.iter().map(|p| p.id).collect(),
+                Err(err) => {
+                    error!("failed to load points of interest of city {}: {}", id, err);
+                    return Err(err.wrap_str("failed to load points of interest"));
+                },
+            };
+            Ok(CityReferences {
+                airport_ids,
+                route_ids,
+                comment_ids,
+                poi_ids,
+            })
+        }
 
-                count += 1;
+        fn delete(&self, id: i64, policy: CityDeletePolicy) -> Result<(), Error> {
+            if !self.city_exists(id)? {
+                return Err(Error::not_found("city not found".to_string()));
+            }
+            let references = self.references(id)?;
+            match policy {
+                CityDeletePolicy::Restrict if !references.is_empty() => return Err(Error::conflict(
+                    CityInUse,
+                    format!("city {} is still referenced", id),
+                    references.describe(),
+                )),
+                CityDeletePolicy::Reassign(target_id) if target_id == id =>
+                    return Err(Error::bad_request("city cannot be reassigned to itself".to_string())),
+                CityDeletePolicy::Reassign(target_id) if !self.city_exists(target_id)? =>
+                    return Err(Error::not_found(format!("city {} to reassign to not found", target_id))),
+                _ => (),
             };
-        
+            match self.city_repo.delete_with(id, policy) {
+                Ok(()) => {
+                    self.search_service.refresh_index();
+                    Ok(())
+                },
+                Err(err) => {
+                    error!("failed to delete city {}: {}", id, err);
+                    Err(err.wrap(format!("failed to delete city {}", id)))
+                },
+            }
+        }
+
+        fn get_aliases(&self, city_id: i64) -> Result<Vec<CityAlias>, Error> {
+            match self.city_repo.get_aliases(vec![city_id]) {
+                Ok(aliases) => Ok(aliases),
+                Err(err) => {
+                    error!("failed to load aliases of city {}: {}", city_id, err);
+                    Err(err.wrap(format!("failed to load aliases of city {}", city_id)))
+                },
+            }
+        }
+
+        fn add_alias(&self, alias: CityAlias) -> Result<CityAlias, Error> {
+            if !self.city_exists(alias.city_id)? {
+                return Err(Error::not_found("city not found".to_string()));
+            }
+            match self.city_repo.new_alias(&alias) {
+                Ok(alias) => {
+                    self.search_service.refresh_index();
+                    Ok(alias)
+                },
+                Err(err) => {
+                    error!("failed to save alias of city {}: {}", alias.city_id, err);
+                    Err(err.wrap(format!("failed to save alias of city {}", alias.city_id)))
+                },
+            }
+        }
+
+        fn delete_alias(&self, city_id: i64, alias_id: i64) -> Result<(), Error> {
+            match self.city_repo.delete_alias(city_id, alias_id) {
+                Ok(()) => {
+                    self.search_service.refresh_index();
+                    Ok(())
+                },
+                Err(err) => {
+                    error!("failed to delete alias {} of city {}: {}", alias_id, city_id, err);
+                    Err(err.wrap(format!("failed to delete alias {} of city {}", alias_id, city_id)))
+                },
+            }
+        }
+
+        fn localize(&self, cities: &mut [City], languages: &[String]) -> Result<(), Error> {
+            if languages.is_empty() || cities.is_empty() {
+                return Ok(());
+            }
+            let aliases = match self.city_repo.get_aliases(cities.iter().map(|c| c.id).collect()) {
+                Ok(aliases) => aliases,
+                Err(err) => {
+                    error!("failed to load city aliases: {}", err);
+                    return Err(err.wrap_str("failed to load city aliases"));
+                },
+            };
+            for city in cities.iter_mut() {
+                city.aliases = aliases.iter().filter(|a| a.city_id == city.id).cloned().collect();
+                city.name = city.localized_name(languages).to_string();
+            }
             Ok(())
         }
+
+        fn all_tags(&self) -> Result<Vec<String>, Error> {
+            match self.city_repo.get_all_tags() {
+                Ok(tags) => Ok(tags),
+                Err(err) => {
+                    error!("failed to load tags: {}", err);
+                    Err(err.wrap_str("failed to load tags"))
+                },
+            }
+        }
+
+        fn add_tag(&self, city_id: i64, tag: String) -> Result<(), Error> {
+            if !self.city_exists(city_id)? {
+                return Err(Error::not_found("city not found".to_string()));
+            }
+            match self.city_repo.add_tag(&CityTag { city_id, tag }) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    error!("failed to tag city {}: {}", city_id, err);
+                    Err(err.wrap(format!("failed to tag city {}", city_id)))
+                },
+            }
+        }
+
+        fn delete_tag(&self, city_id: i64, tag: String) -> Result<(), Error> {
+            match self.city_repo.delete_tag(&CityTag { city_id, tag }) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    error!("failed to untag city {}: {}", city_id, err);
+                    Err(err.wrap(format!("failed to untag city {}", city_id)))
+                },
+            }
+        }
     }
 }
@@ -0,0 +1,359 @@
+#[cfg(test)]
+mod city_service_tests {
+
+    use std::sync::Arc;
+
+    use mockall::predicate::eq;
+
+    use crate::{
+        model::{
+            Airport,
+            City,
+            CityDeletePolicy,
+            CityTag,
+            Country,
+            PoiCategory,
+            PointOfInterest,
+            Route,
+            TagMatch,
+        },
+        storage::{
+            MockAirportRepository,
+            MockCityRepository,
+            MockCommentRepository,
+            MockCountryRepository,
+            MockPoiRepository,
+            routes::MockRouteRepository,
+        },
+        util::{
+            Error,
+            ErrorCode::DbDelete,
+        },
+    };
+    use super::super::{
+        city_service::services::new_city_service,
+        traits::{
+            CityService,
+            MockSearchService,
+        },
+    };
+
+    fn airport(id: i64, city_id: i64) -> Airport {
+        Airport {
+            id,
+            city_id,
+            name: format!("airport {}", id),
+            location: None,
+            iata_code: None,
+            icao_code: None,
+        }
+    }
+
+    #[derive(Default)]
+    struct Mocks {
+        city: MockCityRepository,
+        airport: MockAirportRepository,
+        country: MockCountryRepository,
+        route: MockRouteRepository,
+        comment: MockCommentRepository,
+        poi: MockPoiRepository,
+        search: MockSearchService,
+    }
+
+    fn service_of(mocks: Mocks) -> Arc<impl CityService> {
+        new_city_service(
+            Arc::new(mocks.city),
+            Arc::new(mocks.airport),
+            Arc::new(mocks.country),
+            Arc::new(mocks.route),
+            Arc::new(mocks.comment),
+            Arc::new(mocks.poi),
+            Arc::new(mocks.search),
+        )
+    }
+
+    /// City 1 has airport 10 served by route 100, point of interest 1000 and no comments
+    fn referenced_city_mocks() -> Mocks {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_id()
+            .returning(|id| Ok(Some(City::new(id, format!("city {}", id)))));
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_by_city_id()
+            .with(eq(1))
+            .return_once(|_| Ok(vec![airport(10, 1)]));
+        let mut route_mock = MockRouteRepository::default();
+        route_mock.expect_find_by_airports()
+            .with(eq(vec![10]))
+            .return_once(|_| Ok(vec![Route { id: 100, start: 10, finish: 20, price: 50 }]));
+        let mut comment_mock = MockCommentRepository::default();
+        comment_mock.expect_get_by_city()
+            .with(eq(1))
+            .return_once(|_| Ok(vec![]));
+        let mut poi_mock = MockPoiRepository::default();
+        poi_mock.expect_get_by_city()
+            .with(eq(1), eq(vec![]))
+            .return_once(|_, _| Ok(vec![PointOfInterest {
+                id: 1000,
+                city_id: 1,
+                name: "Kalemegdan".to_string(),
+                category: PoiCategory::Attraction,
+                description: String::new(),
+                location: None,
+            }]));
+        Mocks {
+            city: city_mock,
+            airport: airport_mock,
+            route: route_mock,
+            comment: comment_mock,
+            poi: poi_mock,
+            ..Mocks::default()
+        }
+    }
+
+    #[test]
+    fn restrict_delete_lists_blocking_references() {
+        let mut mocks = referenced_city_mocks();
+        mocks.city.expect_delete_with().never();
+
+        mocks.search.expect_refresh_index().never();
+
+        let service = service_of(mocks);
+
+        let err = service.delete(1, CityDeletePolicy::Restrict).err().unwrap();
+
+        assert!(matches!(err, Error::Conflict(_)));
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(json.contains("CityInUse"));
+        assert!(json.contains("[\"airport/10\",\"route/100\",\"poi/1000\"]"));
+    }
+
+    #[test]
+    fn cascade_delete_runs_in_one_repository_call() {
+        let mut mocks = referenced_city_mocks();
+        mocks.city.expect_delete_with()
+            .with(eq(1), eq(CityDeletePolicy::Cascade))
+            .times(1)
+            .return_once(|_, _| Ok(()));
+
+        mocks.search.expect_refresh_index()
+            .times(1)
+            .return_const(());
+
+        let service = service_of(mocks);
+
+        assert!(service.delete(1, CityDeletePolicy::Cascade).is_ok());
+    }
+
+    #[test]
+    fn reassign_delete_runs_in_one_repository_call() {
+        let mut mocks = referenced_city_mocks();
+        mocks.city.expect_delete_with()
+            .with(eq(1), eq(CityDeletePolicy::Reassign(2)))
+            .times(1)
+            .return_once(|_, _| Ok(()));
+
+        mocks.search.expect_refresh_index()
+            .times(1)
+            .return_const(());
+
+        let service = service_of(mocks);
+
+        assert!(service.delete(1, CityDeletePolicy::Reassign(2)).is_ok());
+    }
+
+    #[test]
+    fn failed_delete_keeps_search_index() {
+        let mut mocks = referenced_city_mocks();
+        mocks.city.expect_delete_with()
+            .with(eq(1), eq(CityDeletePolicy::Cascade))
+            .times(1)
+            .return_once(|_, _| Err(Error::internal(DbDelete, "deadlock".to_string())));
+
+        mocks.search.expect_refresh_index().never();
+
+        let service = service_of(mocks);
+
+        assert!(service.delete(1, CityDeletePolicy::Cascade).is_err());
+    }
+
+    #[test]
+    fn upload_accepts_rows_without_country_and_time_zone() {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_new()
+            .with(eq("Belgrade".to_string()))
+            .times(1)
+            .return_once(|name| Ok(City::new(1, name)));
+        city_mock.expect_new()
+            .with(eq("Novi Sad".to_string()))
+            .times(1)
+            .return_once(|name| Ok(City::new(2, name)));
+        city_mock.expect_set_country()
+            .with(eq(2), eq(Some("RS".to_string())), eq(None))
+            .times(1)
+            .return_once(|_, _, _| Ok(()));
+        city_mock.expect_set_time_zone().never();
+        let mut country_mock = MockCountryRepository::default();
+        country_mock.expect_get_by_code()
+            .with(eq("RS".to_string()))
+            .times(1)
+            .return_once(|code| Ok(Some(Country {
+                code,
+                name: "Serbia".to_string(),
+                regions: vec![],
+            })));
+
+        let mut search_mock = MockSearchService::default();
+        search_mock.expect_refresh_index()
+            .times(1)
+            .return_const(());
+
+        let service = service_of(Mocks {
+            city: city_mock,
+            country: country_mock,
+            search: search_mock,
+            ..Mocks::default()
+        });
+
+        assert!(service.save_cities("name,country_code,time_zone\nBelgrade\nNovi Sad,rs\n".as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn upload_rejects_unknown_time_zone_with_row() {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_new()
+            .with(eq("Belgrade".to_string()))
+            .times(1)
+            .returning(|name| Ok(City::new(1, name)));
+        city_mock.expect_set_time_zone()
+            .times(1)
+            .return_once(|_, _| Ok(()));
+
+        let mut search_mock = MockSearchService::default();
+        search_mock.expect_refresh_index()
+            .times(1)
+            .return_const(());
+
+        let service = service_of(Mocks {
+            city: city_mock,
+            search: search_mock,
+            ..Mocks::default()
+        });
+
+        let err = service.save_cities("name,country_code,time_zone\nBelgrade,,Europe/Belgrade\nAtlantis,,Atlantis/Capital\n".as_bytes())
+            .err().unwrap();
+
+        assert!(matches!(err, Error::BadRequest(_)));
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(json.contains("TextRowParse"));
+        assert!(json.contains("in row 2"));
+    }
+
+    fn tag(city_id: i64, tag: &str) -> CityTag {
+        CityTag {
+            city_id,
+            tag: tag.to_string(),
+        }
+    }
+
+    /// Beach and mountain tags on city 1, only beach on city 2
+    fn tagged_cities_mock() -> MockCityRepository {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_find_tagged()
+            .with(eq(vec!["beach".to_string(), "mountain".to_string()]))
+            .return_once(|_| Ok(vec![tag(1, "beach"), tag(1, "mountain"), tag(2, "beach")]));
+        city_mock.expect_get_tags()
+            .returning(|ids| Ok(vec![tag(1, "beach"), tag(1, "mountain"), tag(2, "beach")]
+                .into_iter()
+                .filter(|t| ids.contains(&t.city_id))
+                .collect()));
+        city_mock
+    }
+
+    fn service_with(city_mock: MockCityRepository) -> Arc<impl CityService> {
+        service_of(Mocks {
+            city: city_mock,
+            ..Mocks::default()
+        })
+    }
+
+    #[test]
+    fn find_by_tags_matching_all_needs_every_tag() {
+        let mut city_mock = tagged_cities_mock();
+        city_mock.expect_get_by_ids()
+            .with(eq(vec![1]))
+            .times(1)
+            .return_once(|_| Ok(vec![City::new(1, "Split".to_string())]));
+
+        let cities = service_with(city_mock)
+            .find_by_tags(vec!["beach".to_string(), "mountain".to_string()], TagMatch::All)
+            .unwrap();
+
+        assert_eq!(vec![1], cities.iter().map(|c| c.id).collect::<Vec<i64>>());
+        assert_eq!(vec!["beach".to_string(), "mountain".to_string()], cities[0].tags);
+    }
+
+    #[test]
+    fn find_by_tags_matching_any_needs_one_tag() {
+        let mut city_mock = tagged_cities_mock();
+        city_mock.expect_get_by_ids()
+            .with(eq(vec![1, 2]))
+            .times(1)
+            .return_once(|_| Ok(vec![City::new(1, "Split".to_string()), City::new(2, "Budva".to_string())]));
+
+        let cities = service_with(city_mock)
+            .find_by_tags(vec!["beach".to_string(), "mountain".to_string()], TagMatch::Any)
+            .unwrap();
+
+        assert_eq!(vec![1, 2], cities.iter().map(|c| c.id).collect::<Vec<i64>>());
+        assert_eq!(vec!["beach".to_string()], cities[1].tags);
+    }
+
+    #[test]
+    fn find_by_tags_skips_untagged_cities() {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_find_tagged()
+            .with(eq(vec!["beach".to_string()]))
+            .return_once(|_| Ok(vec![]));
+        city_mock.expect_get_by_ids().never();
+
+        let cities = service_with(city_mock)
+            .find_by_tags(vec!["beach".to_string()], TagMatch::All)
+            .unwrap();
+
+        assert!(cities.is_empty());
+    }
+
+    #[test]
+    fn add_tag_to_missing_city_is_not_found() {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_id()
+            .with(eq(7))
+            .return_once(|_| Ok(None));
+        city_mock.expect_add_tag().never();
+
+        let err = service_with(city_mock).add_tag(7, "beach".to_string()).err().unwrap();
+
+        assert!(matches!(err, Error::NotFound(_)));
+    }
+
+    #[test]
+    fn add_and_delete_tag_of_city() {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_id()
+            .with(eq(1))
+            .return_once(|id| Ok(Some(City::new(id, "Split".to_string()))));
+        city_mock.expect_add_tag()
+            .with(eq(tag(1, "beach")))
+            .times(1)
+            .return_once(|_| Ok(()));
+        city_mock.expect_delete_tag()
+            .with(eq(tag(1, "beach")))
+            .times(1)
+            .return_once(|_| Ok(()));
+        let service = service_with(city_mock);
+
+        assert!(service.add_tag(1, "beach".to_string()).is_ok());
+        assert!(service.delete_tag(1, "beach".to_string()).is_ok());
+    }
+
+}
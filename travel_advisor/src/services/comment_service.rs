@@ -1,5 +1,8 @@
 pub mod services {
-    use std::sync::Arc;
+    use std::{
+        collections::HashMap,
+        sync::Arc,
+    };
 
     use log::error;
 
@@ -7,26 +10,122 @@ pub mod services {
         CommentRepository,
         model::{
             Comment,
+            CommentRevision,
+            CommentSort,
+            Reaction,
+            ReactionCounts,
             User,
         },
         util::Error,
     };
-    use super::super::traits::CommentService;
+    use super::super::traits::{
+        CommentService,
+        ContentPolicy,
+    };
 
     struct CommentServiceImpl {
         repo: Arc<dyn CommentRepository + Sync + Send>,
+        policy: Arc<dyn ContentPolicy + Sync + Send>,
     }
 
-    pub fn new_comment_service(repo: Arc<dyn CommentRepository + Sync + Send>) -> Arc<impl CommentService> {
+    pub fn new_comment_service(
+        repo: Arc<dyn CommentRepository + Sync + Send>,
+        policy: Arc<dyn ContentPolicy + Sync + Send>,
+    ) -> Arc<impl CommentService> {
         Arc::new(CommentServiceImpl {
             repo: repo,
+            policy,
         })
     }
 
+    impl CommentServiceImpl {
+        fn load(&self, id: i64) -> Result<Comment, Error> {
+            match self.repo.get_by_id(id) {
+                Ok(Some(comment)) => Ok(comment),
+                Ok(None) => Err(Error::not_found("comment not found".to_string())),
+                Err(err) => {
+                    error!("failed to load comment: {}", err.to_string());
+                    Err(err.wrap_str("failed to load comment"))
+                },
+            }
+        }
+
+        fn replies_of(&self, id: i64) -> Result<Vec<Comment>, Error> {
+            match self.repo.get_replies(id) {
+                Ok(replies) => Ok(replies),
+                Err(err) => {
+                    error!("failed to load replies to comment {}: {}", id, err.to_string());
+                    Err(err.wrap_str("failed to load replies"))
+                },
+            }
+        }
+
+        fn count_reactions(&self, comment_ids: Vec<i64>) -> Result<HashMap<i64, ReactionCounts>, Error> {
+            match self.repo.count_reactions(comment_ids) {
+                Ok(counts) => Ok(counts),
+                Err(err) => {
+                    error!("failed to count reactions: {}", err.to_string());
+                    Err(err.wrap_str("failed to count reactions"))
+                },
+            }
+        }
+
+        fn with_reactions(&self, mut comments: Vec<Comment>) -> Result<Vec<Comment>, Error> {
+            if comments.is_empty() {
+                return Ok(comments);
+            }
+            let mut counts = self.count_reactions(comments.iter().map(|c| c.id).collect())?;
+            for comment in comments.iter_mut() {
+                comment.reactions = counts.remove(&comment.id).unwrap_or_default();
+            }
+            Ok(comments)
+        }
+
+        fn reactions_of(&self, comment_id: i64) -> Result<ReactionCounts, Error> {
+            Ok(self.count_reactions(vec![comment_id])?.remove(&comment_id).unwrap_or_default())
+        }
+
+        /// Removes placeholders up the thread that were left without replies
+        fn prune_placeholders(&self, mut parent_id: Option<i64>) -> Result<(), Error> {
+            while let Some(id) = parent_id {
+                let parent = match self.repo.get_by_id(id) {
+                    Ok(Some(parent)) if parent.deleted => parent,
+                    Ok(_) => return Ok(()),
+                    Err(err) => {
+                        error!("failed to load parent comment: {}", err.to_string());
+                        return Err(err.wrap_str("failed to load parent comment"));
+                    },
+                };
+                if !self.replies_of(id)?.is_empty() {
+                    return Ok(());
+                }
+                if let Err(err) = self.repo.delete(id) {
+                    error!("failed to delete placeholder comment: {}", err.to_string());
+                    return Err(err.wrap_str("failed to delete placeholder comment"));
+                }
+                parent_id = parent.parent_id;
+            }
+            Ok(())
+        }
+    }
+
     impl CommentService for CommentServiceImpl {
 
         fn create(&self, user_id: i64, mut comment: Comment) -> Result<Comment, Error> {
             comment.user_id = user_id;
+            self.policy.check(&comment.content)?;
+            if let Some(parent_id) = comment.parent_id {
+                let parent = match self.load(parent_id) {
+                    Ok(parent) => parent,
+                    Err(err) => return Err(err.wrap_str("failed to load parent comment")),
+                };
+                if parent.city_id != comment.city_id {
+                    return Err(Error::bad_request("reply must be posted for the city of the parent comment".to_string()));
+                }
+                if parent.deleted {
+                    return Err(Error::bad_request("cannot reply to a deleted comment".to_string()));
+                }
+            }
 
             match self.repo.create(comment) {
                 Ok(comment) => Ok(comment),
@@ -41,6 +140,10 @@ pub mod services {
             if user_id.clone() != comment.user_id.clone() {
                 return Err(Error::forbidden_str("only poster can change comment"))
             }
+            if comment.deleted {
+                return Err(Error::bad_request("deleted comment cannot be changed".to_string()));
+            }
+            self.policy.check(&comment.content)?;
             // update comment
             match self.repo.update(comment.id.clone(), comment.content.clone()) {
                 Ok(()) => (),
@@ -52,7 +155,7 @@ pub mod services {
             // reload comment
             match self.repo.get_by_id(comment.id.clone()) {
                 Ok(comment) => match comment {
-                    Some(comment) => Ok(comment),
+                    Some(comment) => Ok(self.with_reactions(vec![comment])?.remove(0)),
                     None => panic!("this should never happen"),
                 },
                 Err(err) => {
@@ -63,7 +166,7 @@ pub mod services {
         }
 
         fn delete(&self, id: i64, user: User) -> Result<(), Error> {
-            let allowed = user.is_admin();
+            let allowed = user.is_admin() || user.is_moderator();
             let comment = match self.repo.get_by_id(id) {
                 Ok(comment) => match comment {
                     Some(comment) => comment,
@@ -76,31 +179,45 @@ pub mod services {
             };
 
             if !allowed && comment.user_id.clone() != user.id.clone() {
-                return Err(Error::forbidden_str("only poster or admin can delete comment"))
+                return Err(Error::forbidden_str("only poster, moderator or admin can delete comment"))
             }
 
+            // a comment with replies stays as a placeholder
+            if !self.replies_of(id)?.is_empty() {
+                return match self.repo.mark_deleted(id) {
+                    Ok(()) => Ok(()),
+                    Err(err) => {
+                        error!("failed to mark comment as deleted: {}", err.to_string());
+                        Err(err.wrap_str("failed to delete comment"))
+                    },
+                };
+            }
             match self.repo.delete(id) {
-                Ok(()) => Ok(()),
+                Ok(()) => (),
                 Err(err) => {
                     error!("failed to delete comment: {}", err.to_string());
-                    Err(err.wrap_str("failed to delete comment"))
+                    return Err(err.wrap_str("failed to delete comment"));
                 },
             }
+            self.prune_placeholders(comment.parent_id)
         }
 
-        fn list_for_city(&self, city_id: i64) -> Result<Vec<Comment>, Error> {
-            match self.repo.get_by_city(city_id) {
-                Ok(result) => Ok(result),
+        fn list_for_city(&self, city_id: i64, sort: CommentSort) -> Result<Vec<Comment>, Error> {
+            let comments = match self.repo.get_by_city(city_id) {
+                Ok(result) => result.into_iter().filter(|c| !c.hidden).collect(),
                 Err(err) => {
                     error!("failed to list comments for city: {}", err.to_string());
-                    Err(err)
+                    return Err(err);
                 },
-            }
+            };
+            let mut comments = self.with_reactions(comments)?;
+            sort.apply(&mut comments);
+            Ok(comments)
         }
 
         fn list_for_user(&self, user_id: i64) -> Result<Vec<Comment>, Error> {
             match self.repo.get_by_user(user_id) {
-                Ok(result) => Ok(result),
+                Ok(result) => self.with_reactions(result),
                 Err(err) => {
                     error!("failed to list comments of the user: {}", err.to_string());
                     Err(err)
@@ -118,6 +235,49 @@ pub mod services {
             }
         }
 
+        fn list_replies(&self, id: i64) -> Result<Vec<Comment>, Error> {
+            self.load(id)?;
+            let replies = self.replies_of(id)?;
+            self.with_reactions(replies.into_iter().filter(|c| !c.hidden).collect())
+        }
+
+        fn history(&self, id: i64, user: User) -> Result<Vec<CommentRevision>, Error> {
+            let comment = self.load(id)?;
+            if !user.is_admin() && comment.user_id != user.id {
+                return Err(Error::forbidden_str("only poster or admin can see comment history"));
+            }
+            match self.repo.get_revisions(id) {
+                Ok(revisions) => Ok(revisions),
+                Err(err) => {
+                    error!("failed to load history of comment {}: {}", id, err.to_string());
+                    Err(err.wrap_str("failed to load comment history"))
+                },
+            }
+        }
+
+        fn react(&self, reaction: Reaction) -> Result<ReactionCounts, Error> {
+            let comment = self.load(reaction.comment_id)?;
+            if comment.deleted || comment.hidden {
+                return Err(Error::bad_request("cannot react to a deleted or hidden comment".to_string()));
+            }
+            if comment.user_id == reaction.user_id {
+                return Err(Error::bad_request("cannot react to own comment".to_string()));
+            }
+            if let Err(err) = self.repo.set_reaction(&reaction) {
+                error!("failed to save reaction: {}", err.to_string());
+                return Err(err.wrap_str("failed to save reaction"));
+            }
+            self.reactions_of(reaction.comment_id)
+        }
+
+        fn remove_reaction(&self, comment_id: i64, user_id: i64) -> Result<ReactionCounts, Error> {
+            if let Err(err) = self.repo.delete_reaction(comment_id, user_id) {
+                error!("failed to delete reaction: {}", err.to_string());
+                return Err(err.wrap_str("failed to delete reaction"));
+            }
+            self.reactions_of(comment_id)
+        }
+
     }
 
 }
\ No newline at end of file
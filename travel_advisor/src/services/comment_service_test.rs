@@ -1,44 +1,41 @@
 #[cfg(test)]
 mod airport_service_test {
 
-    use std::{sync::Arc, time::SystemTime};
+    use std::{collections::HashMap, sync::Arc, time::SystemTime};
 
     use actix_web::HttpMessage;
-    use mockall::{
-        mock,
-        predicate::eq,
-    };
+    use mockall::predicate::eq;
 
     use crate::{
-        model::Comment,
-        util::Error,
+        model::{
+            Comment,
+            CommentRevision,
+            CommentSort,
+            ReactionCounts,
+            User,
+        },
+        util::{
+            Error,
+            ErrorCode,
+            TextSpan,
+        },
     };
 
-    use crate::storage::CommentRepository;
+    use crate::storage::{
+        CommentRepository,
+        MockCommentRepository,
+    };
     use super::super::{
         comment_service::services::new_comment_service,
-        traits::CommentService,
+        traits::{
+            CommentService,
+            MockContentPolicy,
+        },
     };
 
-    mock! {
-
-        pub CommentRepositoryTest {}
-
-        impl CommentRepository for CommentRepositoryTest {
-            fn create(&self, comment: Comment) -> Result<Comment, Error>;
-            fn get_by_city(&self, city_id: i64) -> Result<Vec<Comment>, Error>;
-            fn get_by_user(&self, user_id: i64) -> Result<Vec<Comment>, Error>;
-            fn update(&self, id: i64, text: String) -> Result<(), Error>;
-            fn delete(&self, id: i64) -> Result<(), Error>;
-            fn delete_for_city(&self, city_id: i64) -> Result<(), Error>;
-            fn get_by_id(&self, id: i64) -> Result<Option<Comment>, Error>;
-        }
-
-    }
-
     #[test]
     fn create_comment_get_comment() {
-        let mut mock = MockCommentRepositoryTest::new();
+        let mut mock = MockCommentRepository::new();
 
         let now = SystemTime::now();
 
@@ -50,14 +47,18 @@ mod airport_service_test {
                     id: 1,
                     city_id: 2,
                     user_id: 3,
+                    parent_id: None,
                     content: "content".to_string(),
+                    deleted: false,
+                    hidden: false,
+                    reactions: ReactionCounts::default(),
                     created_at: now.clone(),
                     updated_at: now.clone(),
                 }))
             });
 
         let mock_param: Arc<dyn CommentRepository + Send + Sync> = Arc::new(mock);
-        let service = new_comment_service(mock_param);
+        let service = new_comment_service(mock_param, Arc::new(MockContentPolicy::new()));
 
         let comment = service.get_by_id(1);
 
@@ -75,7 +76,7 @@ mod airport_service_test {
 
     #[actix_rt::test]
     async fn create_comment_get_not_found() {
-        let mut mock = MockCommentRepositoryTest::new();
+        let mut mock = MockCommentRepository::new();
 
         mock.expect_get_by_id()
             .with(eq(1 as i64))
@@ -86,7 +87,7 @@ mod airport_service_test {
         ;
 
         let mock_param: Arc<dyn CommentRepository + Send + Sync> = Arc::new(mock);
-        let service = new_comment_service(mock_param);
+        let service = new_comment_service(mock_param, Arc::new(MockContentPolicy::new()));
 
         let comment = service.get_by_id(1);
 
@@ -95,6 +96,158 @@ mod airport_service_test {
         assert!(matches!(err, Error::NotFound(_)));
     }
 
+    fn comment(id: i64, parent_id: Option<i64>, deleted: bool) -> Comment {
+        Comment {
+            id,
+            city_id: 2,
+            user_id: 3,
+            parent_id,
+            content: match deleted {
+                true => String::new(),
+                false => format!("comment {}", id),
+            },
+            deleted,
+            hidden: false,
+            reactions: ReactionCounts::default(),
+            created_at: SystemTime::now(),
+            updated_at: SystemTime::now(),
+        }
+    }
+
+    fn poster() -> User {
+        User {
+            id: 3,
+            email: "poster@example.com".to_string(),
+            pass: String::new(),
+            roles: vec!["user".to_string()],
+        }
+    }
+
+    #[test]
+    fn delete_comment_with_replies_leaves_placeholder() {
+        let mut mock = MockCommentRepository::new();
+        mock.expect_get_by_id()
+            .with(eq(1))
+            .return_once(|_| Ok(Some(comment(1, None, false))));
+        mock.expect_get_replies()
+            .with(eq(1))
+            .return_once(|_| Ok(vec![comment(2, Some(1), false)]));
+        mock.expect_mark_deleted()
+            .with(eq(1))
+            .times(1)
+            .return_once(|_| Ok(()));
+        mock.expect_delete().never();
+
+        let service = new_comment_service(Arc::new(mock), Arc::new(MockContentPolicy::new()));
+
+        assert!(service.delete(1, poster()).is_ok());
+    }
+
+    #[test]
+    fn delete_last_reply_removes_placeholder() {
+        let mut mock = MockCommentRepository::new();
+        mock.expect_get_by_id()
+            .with(eq(2))
+            .return_once(|_| Ok(Some(comment(2, Some(1), false))));
+        mock.expect_get_by_id()
+            .with(eq(1))
+            .return_once(|_| Ok(Some(comment(1, None, true))));
+        mock.expect_get_replies()
+            .returning(|_| Ok(vec![]));
+        mock.expect_delete()
+            .with(eq(2))
+            .times(1)
+            .return_once(|_| Ok(()));
+        mock.expect_delete()
+            .with(eq(1))
+            .times(1)
+            .return_once(|_| Ok(()));
+
+        let service = new_comment_service(Arc::new(mock), Arc::new(MockContentPolicy::new()));
+
+        assert!(service.delete(2, poster()).is_ok());
+    }
+
+    #[test]
+    fn create_rejected_by_policy_is_not_saved() {
+        let mut mock = MockCommentRepository::new();
+        mock.expect_create().never();
+        let mut policy = MockContentPolicy::new();
+        policy.expect_check()
+            .withf(|text| text == "comment 1")
+            .times(1)
+            .returning(|_| Err(Error::bad_request_with_spans(
+                ErrorCode::BannedWord,
+                "text contains banned words".to_string(),
+                vec![TextSpan { start: 0, end: 7 }],
+            )));
+
+        let service = new_comment_service(Arc::new(mock), Arc::new(policy));
+        let err = service.create(3, comment(1, None, false)).err().unwrap();
+
+        match err {
+            Error::BadRequest(p) => {
+                assert!(matches!(p.code(), ErrorCode::BannedWord));
+                assert_eq!(&[TextSpan { start: 0, end: 7 }], p.spans());
+            },
+            _ => panic!("expected bad request"),
+        }
+    }
+
+    #[test]
+    fn history_only_for_poster_and_admin() {
+        let mut mock = MockCommentRepository::new();
+        mock.expect_get_by_id()
+            .with(eq(1))
+            .returning(|_| Ok(Some(comment(1, None, false))));
+        mock.expect_get_revisions()
+            .with(eq(1))
+            .times(2)
+            .returning(|_| Ok(vec![CommentRevision {
+                id: 5,
+                comment_id: 1,
+                content: "first version".to_string(),
+                written_at: SystemTime::now(),
+            }]));
+
+        let service = new_comment_service(Arc::new(mock), Arc::new(MockContentPolicy::new()));
+        let other = User {
+            id: 4,
+            ..poster()
+        };
+        let admin = User {
+            id: 6,
+            roles: vec!["admin".to_string()],
+            ..poster()
+        };
+
+        assert_eq!(1, service.history(1, poster()).unwrap().len());
+        assert_eq!(1, service.history(1, admin).unwrap().len());
+        assert!(matches!(service.history(1, other).unwrap_err(), Error::Forbidden(_)));
+    }
+
+    #[test]
+    fn list_for_city_sorted_by_helpful_leaves_out_hidden() {
+        let mut mock = MockCommentRepository::new();
+        mock.expect_get_by_city()
+            .with(eq(2))
+            .return_once(|_| Ok(vec![
+                comment(1, None, false),
+                comment(2, None, false),
+                Comment { hidden: true, ..comment(3, None, false) },
+            ]));
+        mock.expect_count_reactions()
+            .with(eq(vec![1, 2]))
+            .return_once(|_| Ok(HashMap::from([(2, ReactionCounts { up: 4, down: 1 })])));
+
+        let service = new_comment_service(Arc::new(mock), Arc::new(MockContentPolicy::new()));
+        let comments = service.list_for_city(2, CommentSort::Helpful).unwrap();
+
+        assert_eq!(vec![2, 1], comments.iter().map(|c| c.id).collect::<Vec<i64>>());
+        assert_eq!(ReactionCounts { up: 4, down: 1 }, comments[0].reactions);
+        assert_eq!(ReactionCounts::default(), comments[1].reactions);
+    }
+
     type Meters = u32;
     type Feet = u32;
 
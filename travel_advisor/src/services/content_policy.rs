@@ -0,0 +1,134 @@
+pub mod services {
+    use std::sync::Arc;
+
+    use crate::{
+        services::traits::ContentPolicy,
+        util::{
+            Error,
+            ErrorCode,
+            TextSpan,
+        },
+    };
+
+    /// Size of the comment text column
+    const MAX_LENGTH: usize = 250;
+    /// Longest run of a single character that still passes as ordinary text
+    const MAX_REPEATED: usize = 6;
+
+    struct DefaultContentPolicy {
+        banned_words: Vec<String>,
+        max_links: usize,
+    }
+
+    /// Banned words are matched as whole words, ignoring case
+    pub fn new_default_content_policy(banned_words: Vec<String>, max_links: usize) -> Arc<impl ContentPolicy> {
+        Arc::new(DefaultContentPolicy {
+            banned_words: banned_words.iter()
+                .map(|w| w.trim().to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect(),
+            max_links,
+        })
+    }
+
+    /// Spans of the longest runs of characters matching the predicate
+    fn runs(chars: &[char], matches: impl Fn(char) -> bool) -> Vec<TextSpan> {
+        let mut spans: Vec<TextSpan> = vec![];
+        let mut start: Option<usize> = None;
+        for (i, c) in chars.iter().enumerate() {
+            match (matches(*c), start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    spans.push(TextSpan { start: s, end: i });
+                    start = None;
+                },
+                _ => (),
+            }
+        }
+        if let Some(s) = start {
+            spans.push(TextSpan { start: s, end: chars.len() });
+        }
+        spans
+    }
+
+    fn text_of(chars: &[char], span: &TextSpan) -> String {
+        chars[span.start..span.end].iter().collect()
+    }
+
+    fn is_link(token: &str) -> bool {
+        let token = token.to_lowercase();
+        token.starts_with("http://") || token.starts_with("https://") || token.starts_with("www.")
+    }
+
+    /// Whitespace and digits are left out, long numbers are not spam
+    fn repeated(chars: &[char]) -> Vec<TextSpan> {
+        let mut spans: Vec<TextSpan> = vec![];
+        let mut start = 0;
+        for i in 1..=chars.len() {
+            if i < chars.len() && chars[i] == chars[start] {
+                continue;
+            }
+            let c = chars[start];
+            if i - start > MAX_REPEATED && !c.is_whitespace() && !c.is_ascii_digit() {
+                spans.push(TextSpan { start, end: i });
+            }
+            start = i;
+        }
+        spans
+    }
+
+    impl DefaultContentPolicy {
+        fn banned(&self, chars: &[char]) -> Vec<TextSpan> {
+            runs(chars, char::is_alphanumeric).into_iter()
+                .filter(|s| self.banned_words.contains(&text_of(chars, s).to_lowercase()))
+                .collect()
+        }
+
+        fn links(&self, chars: &[char]) -> Vec<TextSpan> {
+            runs(chars, |c| !c.is_whitespace()).into_iter()
+                .filter(|s| is_link(&text_of(chars, s)))
+                .collect()
+        }
+    }
+
+    impl ContentPolicy for DefaultContentPolicy {
+
+        fn check(&self, text: &str) -> Result<(), Error> {
+            let chars: Vec<char> = text.chars().collect();
+            if chars.len() > MAX_LENGTH {
+                return Err(Error::bad_request_with_spans(
+                    ErrorCode::TextTooLong,
+                    format!("text has {} characters, at most {} are allowed", chars.len(), MAX_LENGTH),
+                    vec![TextSpan { start: MAX_LENGTH, end: chars.len() }],
+                ));
+            }
+            let banned = self.banned(&chars);
+            if !banned.is_empty() {
+                return Err(Error::bad_request_with_spans(
+                    ErrorCode::BannedWord,
+                    "text contains banned words".to_string(),
+                    banned,
+                ));
+            }
+            let links = self.links(&chars);
+            if links.len() > self.max_links {
+                return Err(Error::bad_request_with_spans(
+                    ErrorCode::TooManyLinks,
+                    format!("text has {} links, at most {} are allowed", links.len(), self.max_links),
+                    links[self.max_links..].to_vec(),
+                ));
+            }
+            let repeated = repeated(&chars);
+            if !repeated.is_empty() {
+                return Err(Error::bad_request_with_spans(
+                    ErrorCode::RepeatedCharacters,
+                    format!("text repeats a character more than {} times in a row", MAX_REPEATED),
+                    repeated,
+                ));
+            }
+            Ok(())
+        }
+
+    }
+
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod content_policy_tests {
+
+    use crate::util::{
+        Error,
+        ErrorCode,
+        TextSpan,
+    };
+    use super::super::{
+        content_policy::services::new_default_content_policy,
+        traits::ContentPolicy,
+    };
+
+    fn violation(result: Result<(), Error>) -> (ErrorCode, Vec<TextSpan>) {
+        match result {
+            Err(Error::BadRequest(p)) => (p.code().clone(), p.spans().to_vec()),
+            Err(err) => panic!("expected bad request, got {}", err),
+            Ok(()) => panic!("expected violation"),
+        }
+    }
+
+    #[test]
+    fn ordinary_text_passes() {
+        let policy = new_default_content_policy(vec!["scam".to_string()], 1);
+
+        assert!(policy.check("Sooo nice, 1000000 stars! See https://example.com").is_ok());
+    }
+
+    #[test]
+    fn too_long_text_points_past_the_limit() {
+        let policy = new_default_content_policy(vec![], 1);
+
+        let (code, spans) = violation(policy.check(&"ž".repeat(260)));
+
+        assert!(matches!(code, ErrorCode::TextTooLong));
+        assert_eq!(vec![TextSpan { start: 250, end: 260 }], spans);
+    }
+
+    #[test]
+    fn banned_words_match_whole_words_ignoring_case() {
+        let policy = new_default_content_policy(vec![" Scam ".to_string()], 1);
+
+        let (code, spans) = violation(policy.check("scammer says: SCAM, total scam"));
+
+        assert!(matches!(code, ErrorCode::BannedWord));
+        assert_eq!(vec![TextSpan { start: 14, end: 18 }, TextSpan { start: 26, end: 30 }], spans);
+    }
+
+    #[test]
+    fn links_over_limit_are_pointed_at() {
+        let policy = new_default_content_policy(vec![], 1);
+
+        let (code, spans) = violation(policy.check("see http://a.com and WWW.b.com"));
+
+        assert!(matches!(code, ErrorCode::TooManyLinks));
+        assert_eq!(vec![TextSpan { start: 21, end: 30 }], spans);
+    }
+
+    #[test]
+    fn repeated_characters_are_spam() {
+        let policy = new_default_content_policy(vec![], 1);
+
+        let (code, spans) = violation(policy.check("cheap!!!!!! flights       aaaaaaa"));
+
+        assert!(matches!(code, ErrorCode::RepeatedCharacters));
+        assert_eq!(vec![TextSpan { start: 26, end: 33 }], spans);
+    }
+
+}
@@ -0,0 +1,85 @@
+pub mod services {
+    use std::sync::Arc;
+
+    use log::error;
+
+    use crate::{
+        model::{
+            City,
+            Country,
+            Region,
+        },
+        services::traits::CountryService,
+        storage::{
+            CityRepository,
+            CountryRepository,
+        },
+        util::Error,
+    };
+    use super::super::macros::log_if_error;
+
+    /// Loads the country, failing when there is none with the code
+    pub fn existing_country(country_repo: &(dyn CountryRepository + Sync + Send), code: &str) -> Result<Country, Error> {
+        match country_repo.get_by_code(code.to_string()) {
+            Ok(Some(country)) => Ok(country),
+            Ok(None) => Err(Error::not_found(format!("country {} not found", code))),
+            Err(err) => {
+                error!("failed to load country {}: {}", code, err);
+                Err(err.wrap(format!("failed to load country {}", code)))
+            },
+        }
+    }
+
+    pub fn new_country_service(
+        country_repo: Arc<dyn CountryRepository + Sync + Send>,
+        city_repo: Arc<dyn CityRepository + Sync + Send>,
+    ) -> Arc<impl CountryService> {
+        Arc::new(CountryServiceImpl {
+            country_repo,
+            city_repo,
+        })
+    }
+
+    struct CountryServiceImpl {
+        country_repo: Arc<dyn CountryRepository + Sync + Send>,
+        city_repo: Arc<dyn CityRepository + Sync + Send>,
+    }
+
+    impl CountryService for CountryServiceImpl {
+        fn get_all(&self) -> Result<Vec<Country>, Error> {
+            log_if_error!(self.country_repo.get_all())
+        }
+
+        fn get_by_code(&self, code: &str) -> Result<Option<Country>, Error> {
+            log_if_error!(self.country_repo.get_by_code(code.to_string()))
+        }
+
+        fn create(&self, country: Country) -> Result<Country, Error> {
+            match self.country_repo.get_by_code(country.code.clone()) {
+                Ok(None) => (),
+                Ok(Some(_)) => return Err(Error::bad_request(format!("country {} already exists", country.code))),
+                Err(err) => {
+                    error!("failed to load country {}: {}", country.code, err);
+                    return Err(err.wrap(format!("failed to load country {}", country.code)));
+                },
+            };
+            log_if_error!(self.country_repo.new(&country))
+        }
+
+        fn create_region(&self, region: Region) -> Result<Region, Error> {
+            let country = existing_country(self.country_repo.as_ref(), &region.country_code)?;
+            if country.regions.iter().any(|r| r.name == region.name) {
+                return Err(Error::bad_request(format!("region {} already exists in {}", region.name, country.code)));
+            }
+            log_if_error!(self.country_repo.new_region(region))
+        }
+
+        fn get_cities(&self, code: &str, region_id: Option<i64>) -> Result<Vec<City>, Error> {
+            let country = existing_country(self.country_repo.as_ref(), code)?;
+            if region_id.is_some_and(|id| !country.regions.iter().any(|r| r.id == id)) {
+                return Err(Error::not_found(format!("region not found in {}", code)));
+            }
+            log_if_error!(self.city_repo.get_by_country(country.code, region_id))
+        }
+    }
+}
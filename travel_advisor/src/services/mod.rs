@@ -2,7 +2,12 @@ mod auth;
 mod airport_service;
 mod city_service;
 mod comment_service;
+mod content_policy;
+mod country_service;
+mod moderation_service;
+mod poi_service;
 mod route_service;
+mod search_service;
 pub mod traits;
 mod macros;
 
@@ -12,6 +17,17 @@ pub use airport_service::services::new_airport_service as new_airport_service;
 pub use auth::services::new_auth_service as new_auth_service;
 pub use city_service::services::new_city_service as new_city_service;
 pub use comment_service::services::new_comment_service as new_comment_service;
+pub use content_policy::services::new_default_content_policy as new_default_content_policy;
+pub use country_service::services::new_country_service as new_country_service;
+pub use moderation_service::services::new_moderation_service as new_moderation_service;
+pub use poi_service::services::new_poi_service as new_poi_service;
 pub(super) use route_service::services::new_route_service as new_route_service;
+pub use search_service::services::new_search_service as new_search_service;
 
+mod airport_service_test;
+mod city_service_test;
 mod comment_service_test;
+mod content_policy_test;
+mod moderation_service_test;
+mod poi_service_test;
+mod route_service_test;
@@ -0,0 +1,138 @@
+pub mod services {
+    use std::{
+        sync::Arc,
+        time::SystemTime,
+    };
+
+    use log::error;
+
+    use crate::{
+        model::{
+            Comment,
+            CommentFlag,
+            FlaggedComment,
+            ModerationAction,
+            ModerationRecord,
+            User,
+        },
+        services::traits::ModerationService,
+        storage::{
+            CommentRepository,
+            ModerationRepository,
+        },
+        util::Error,
+    };
+
+    struct ModerationServiceImpl {
+        comment_repo: Arc<dyn CommentRepository + Sync + Send>,
+        moderation_repo: Arc<dyn ModerationRepository + Sync + Send>,
+    }
+
+    pub fn new_moderation_service(
+        comment_repo: Arc<dyn CommentRepository + Sync + Send>,
+        moderation_repo: Arc<dyn ModerationRepository + Sync + Send>,
+    ) -> Arc<impl ModerationService> {
+        Arc::new(ModerationServiceImpl {
+            comment_repo,
+            moderation_repo,
+        })
+    }
+
+    impl ModerationServiceImpl {
+        fn load_comment(&self, id: i64) -> Result<Option<Comment>, Error> {
+            match self.comment_repo.get_by_id(id) {
+                Ok(comment) => Ok(comment),
+                Err(err) => {
+                    error!("failed to load comment {}: {}", id, err);
+                    Err(err.wrap(format!("failed to load comment {}", id)))
+                },
+            }
+        }
+    }
+
+    impl ModerationService for ModerationServiceImpl {
+
+        fn flag(&self, flag: CommentFlag) -> Result<CommentFlag, Error> {
+            let comment = match self.load_comment(flag.comment_id)? {
+                Some(comment) => comment,
+                None => return Err(Error::not_found("comment not found".to_string())),
+            };
+            if comment.deleted {
+                return Err(Error::bad_request("deleted comment cannot be flagged".to_string()));
+            }
+            if comment.user_id == flag.user_id {
+                return Err(Error::bad_request("own comment cannot be flagged".to_string()));
+            }
+            match self.moderation_repo.add_flag(&flag) {
+                Ok(flag) => Ok(flag),
+                Err(err) => {
+                    error!("failed to flag comment {}: {}", flag.comment_id, err);
+                    Err(err.wrap(format!("failed to flag comment {}", flag.comment_id)))
+                },
+            }
+        }
+
+        fn queue(&self) -> Result<Vec<FlaggedComment>, Error> {
+            let flags = match self.moderation_repo.get_open_flags() {
+                Ok(flags) => flags,
+                Err(err) => {
+                    error!("failed to load open flags: {}", err);
+                    return Err(err.wrap_str("failed to load open flags"));
+                },
+            };
+            let mut queue: Vec<FlaggedComment> = vec![];
+            for flag in flags {
+                if let Some(flagged) = queue.iter_mut().find(|f| f.comment.id == flag.comment_id) {
+                    flagged.flags.push(flag);
+                    continue;
+                }
+                // flags of deleted comments go away with them, so the comment is normally there
+                if let Some(comment) = self.load_comment(flag.comment_id)? {
+                    queue.push(FlaggedComment {
+                        comment,
+                        flags: vec![flag],
+                    });
+                }
+            }
+            Ok(queue)
+        }
+
+        fn moderate(&self, comment_id: i64, action: ModerationAction, moderator: User) -> Result<(), Error> {
+            if !moderator.is_moderator() && !moderator.is_admin() {
+                return Err(Error::forbidden_str("only moderator or admin can moderate comments"));
+            }
+            let comment = match self.load_comment(comment_id)? {
+                Some(comment) => comment,
+                None => return Err(Error::not_found("comment not found".to_string())),
+            };
+            let record = ModerationRecord {
+                id: 0,
+                comment_id,
+                comment_user_id: comment.user_id,
+                comment_text: comment.content.clone(),
+                moderator_id: moderator.id,
+                action,
+                created_at: SystemTime::now(),
+            };
+            match self.moderation_repo.moderate(&record) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    error!("failed to {} comment {}: {}", action.as_str(), comment_id, err);
+                    Err(err.wrap(format!("failed to {} comment {}", action.as_str(), comment_id)))
+                },
+            }
+        }
+
+        fn records(&self, comment_id: Option<i64>) -> Result<Vec<ModerationRecord>, Error> {
+            match self.moderation_repo.get_records(comment_id) {
+                Ok(records) => Ok(records),
+                Err(err) => {
+                    error!("failed to load moderation records: {}", err);
+                    Err(err.wrap_str("failed to load moderation records"))
+                },
+            }
+        }
+
+    }
+
+}
@@ -0,0 +1,174 @@
+#[cfg(test)]
+mod moderation_service_tests {
+
+    use std::{sync::Arc, time::SystemTime};
+
+    use mockall::predicate::eq;
+
+    use crate::{
+        model::{
+            Comment,
+            CommentFlag,
+            ModerationAction,
+            ReactionCounts,
+            User,
+        },
+        util::Error,
+    };
+
+    use crate::storage::{
+        MockCommentRepository,
+        MockModerationRepository,
+    };
+    use super::super::{
+        moderation_service::services::new_moderation_service,
+        traits::ModerationService,
+    };
+
+    fn comment() -> Comment {
+        Comment {
+            id: 1,
+            city_id: 2,
+            user_id: 3,
+            parent_id: None,
+            content: "buy cheap tickets here".to_string(),
+            deleted: false,
+            hidden: false,
+            reactions: ReactionCounts::default(),
+            created_at: SystemTime::now(),
+            updated_at: SystemTime::now(),
+        }
+    }
+
+    fn flag(comment_id: i64, user_id: i64) -> CommentFlag {
+        CommentFlag {
+            id: 0,
+            comment_id,
+            user_id,
+            reason: "spam".to_string(),
+            created_at: SystemTime::now(),
+        }
+    }
+
+    fn moderator() -> User {
+        User {
+            id: 5,
+            email: "moderator@example.com".to_string(),
+            pass: String::new(),
+            roles: vec!["moderator".to_string()],
+        }
+    }
+
+    #[test]
+    fn hide_records_decision_with_comment_snapshot() {
+        let mut comment_repo = MockCommentRepository::new();
+        comment_repo.expect_get_by_id()
+            .with(eq(1))
+            .returning(|_| Ok(Some(comment())));
+        let mut moderation_repo = MockModerationRepository::new();
+        moderation_repo.expect_moderate()
+            .withf(|r| r.comment_id == 1
+                && r.comment_user_id == 3
+                && r.comment_text == "buy cheap tickets here"
+                && r.moderator_id == 5
+                && r.action == ModerationAction::Hide)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = new_moderation_service(
+            Arc::new(comment_repo),
+            Arc::new(moderation_repo),
+        );
+        assert!(service.moderate(1, ModerationAction::Hide, moderator()).is_ok());
+    }
+
+    #[test]
+    fn delete_keeps_text_of_removed_comment_in_record() {
+        let mut comment_repo = MockCommentRepository::new();
+        comment_repo.expect_get_by_id()
+            .with(eq(1))
+            .returning(|_| Ok(Some(comment())));
+        comment_repo.expect_delete().never();
+        comment_repo.expect_mark_deleted().never();
+        let mut moderation_repo = MockModerationRepository::new();
+        moderation_repo.expect_moderate()
+            .withf(|r| r.comment_id == 1
+                && r.comment_text == "buy cheap tickets here"
+                && r.action == ModerationAction::Delete)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = new_moderation_service(
+            Arc::new(comment_repo),
+            Arc::new(moderation_repo),
+        );
+        assert!(service.moderate(1, ModerationAction::Delete, moderator()).is_ok());
+    }
+
+    #[test]
+    fn regular_user_cannot_moderate() {
+        let mut moderation_repo = MockModerationRepository::new();
+        moderation_repo.expect_moderate().never();
+
+        let service = new_moderation_service(
+            Arc::new(MockCommentRepository::new()),
+            Arc::new(moderation_repo),
+        );
+        let user = User {
+            roles: vec!["user".to_string()],
+            ..moderator()
+        };
+        let err = service.moderate(1, ModerationAction::Delete, user).unwrap_err();
+        assert!(matches!(err, Error::Forbidden(_)));
+    }
+
+    #[test]
+    fn queue_groups_flags_by_comment() {
+        let mut comment_repo = MockCommentRepository::new();
+        comment_repo.expect_get_by_id()
+            .with(eq(1))
+            .times(1)
+            .returning(|_| Ok(Some(comment())));
+        comment_repo.expect_get_by_id()
+            .with(eq(2))
+            .times(1)
+            .returning(|_| Ok(Some(Comment {
+                id: 2,
+                ..comment()
+            })));
+        let mut moderation_repo = MockModerationRepository::new();
+        moderation_repo.expect_get_open_flags()
+            .times(1)
+            .returning(|| Ok(vec![flag(1, 7), flag(2, 8), flag(1, 9)]));
+
+        let service = new_moderation_service(
+            Arc::new(comment_repo),
+            Arc::new(moderation_repo),
+        );
+        let queue = service.queue().unwrap();
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].comment.id, 1);
+        assert_eq!(queue[0].flags.iter().map(|f| f.user_id).collect::<Vec<i64>>(), vec![7, 9]);
+        assert_eq!(queue[1].comment.id, 2);
+        assert_eq!(queue[1].flags.iter().map(|f| f.user_id).collect::<Vec<i64>>(), vec![8]);
+    }
+
+    #[test]
+    fn own_comment_cannot_be_flagged() {
+        let mut comment_repo = MockCommentRepository::new();
+        comment_repo.expect_get_by_id()
+            .with(eq(1))
+            .returning(|_| Ok(Some(comment())));
+        let mut moderation_repo = MockModerationRepository::new();
+        moderation_repo.expect_add_flag().never();
+
+        let service = new_moderation_service(
+            Arc::new(comment_repo),
+            Arc::new(moderation_repo),
+        );
+        let err = service.flag(flag(1, 3)).unwrap_err();
+        assert!(matches!(err, Error::BadRequest(_)));
+    }
+
+}
@@ -0,0 +1,101 @@
+pub mod services {
+    use std::sync::Arc;
+
+    use log::error;
+
+    use crate::{
+        CityRepository,
+        model::{
+            PoiCategory,
+            PointOfInterest,
+        },
+        services::traits::PoiService,
+        storage::PoiRepository,
+        util::Error,
+    };
+
+    struct PoiServiceImpl {
+        city_repo: Arc<dyn CityRepository + Sync + Send>,
+        poi_repo: Arc<dyn PoiRepository + Sync + Send>,
+    }
+
+    pub fn new_poi_service(
+        city_repo: Arc<dyn CityRepository + Sync + Send>,
+        poi_repo: Arc<dyn PoiRepository + Sync + Send>,
+    ) -> Arc<impl PoiService> {
+        Arc::new(PoiServiceImpl {
+            city_repo,
+            poi_repo,
+        })
+    }
+
+    impl PoiServiceImpl {
+        fn check_city(&self, city_id: i64) -> Result<(), Error> {
+            match self.city_repo.get_by_id(city_id) {
+                Ok(Some(_)) => Ok(()),
+                Ok(None) => Err(Error::not_found("city not found".to_string())),
+                Err(err) => {
+                    error!("failed to load city {}: {}", city_id, err);
+                    Err(err.wrap(format!("failed to load city {}", city_id)))
+                },
+            }
+        }
+    }
+
+    impl PoiService for PoiServiceImpl {
+
+        fn list_for_city(&self, city_id: i64, categories: Vec<PoiCategory>) -> Result<Vec<PointOfInterest>, Error> {
+            self.check_city(city_id)?;
+            match self.poi_repo.get_by_city(city_id, categories) {
+                Ok(pois) => Ok(pois),
+                Err(err) => {
+                    error!("failed to list points of interest of city {}: {}", city_id, err);
+                    Err(err.wrap_str("failed to list points of interest"))
+                },
+            }
+        }
+
+        fn get_by_id(&self, city_id: i64, id: i64) -> Result<Option<PointOfInterest>, Error> {
+            match self.poi_repo.get_by_id(id) {
+                Ok(poi) => Ok(poi.filter(|p| p.city_id == city_id)),
+                Err(err) => {
+                    error!("failed to load point of interest {}: {}", id, err);
+                    Err(err.wrap_str("failed to load point of interest"))
+                },
+            }
+        }
+
+        fn create(&self, poi: PointOfInterest) -> Result<PointOfInterest, Error> {
+            self.check_city(poi.city_id)?;
+            match self.poi_repo.create(&poi) {
+                Ok(poi) => Ok(poi),
+                Err(err) => {
+                    error!("failed to save point of interest: {}", err);
+                    Err(err.wrap_str("failed to save point of interest"))
+                },
+            }
+        }
+
+        fn update(&self, poi: PointOfInterest) -> Result<(), Error> {
+            match self.poi_repo.update(&poi) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    error!("failed to update point of interest {}: {}", poi.id, err);
+                    Err(err.wrap(format!("failed to update point of interest {}", poi.id)))
+                },
+            }
+        }
+
+        fn delete(&self, city_id: i64, id: i64) -> Result<(), Error> {
+            match self.poi_repo.delete(city_id, id) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    error!("failed to delete point of interest {}: {}", id, err);
+                    Err(err.wrap(format!("failed to delete point of interest {}", id)))
+                },
+            }
+        }
+
+    }
+
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod poi_service_tests {
+
+    use std::sync::Arc;
+
+    use mockall::predicate::eq;
+
+    use crate::{
+        model::{
+            City,
+            PoiCategory,
+            PointOfInterest,
+        },
+        storage::{
+            MockCityRepository,
+            MockPoiRepository,
+        },
+        util::Error,
+    };
+    use super::super::{
+        poi_service::services::new_poi_service,
+        traits::PoiService,
+    };
+
+    fn hotel(id: i64, city_id: i64) -> PointOfInterest {
+        PointOfInterest {
+            id,
+            city_id,
+            name: format!("hotel {}", id),
+            category: PoiCategory::Hotel,
+            description: String::new(),
+            location: None,
+        }
+    }
+
+    #[test]
+    fn list_for_missing_city_is_not_found() {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_id()
+            .with(eq(1))
+            .return_once(|_| Ok(None));
+        let mut poi_mock = MockPoiRepository::default();
+        poi_mock.expect_get_by_city().never();
+
+        let service = new_poi_service(Arc::new(city_mock), Arc::new(poi_mock));
+
+        let err = service.list_for_city(1, vec![PoiCategory::Hotel]).err().unwrap();
+        assert!(matches!(err, Error::NotFound(_)));
+    }
+
+    #[test]
+    fn list_for_city_passes_categories_on() {
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_id()
+            .with(eq(1))
+            .return_once(|id| Ok(Some(City::new(id, "Belgrade".to_string()))));
+        let mut poi_mock = MockPoiRepository::default();
+        poi_mock.expect_get_by_city()
+            .with(eq(1), eq(vec![PoiCategory::Hotel, PoiCategory::Restaurant]))
+            .times(1)
+            .return_once(|_, _| Ok(vec![hotel(5, 1)]));
+
+        let service = new_poi_service(Arc::new(city_mock), Arc::new(poi_mock));
+
+        let pois = service.list_for_city(1, vec![PoiCategory::Hotel, PoiCategory::Restaurant]).unwrap();
+        assert_eq!(vec![hotel(5, 1)], pois);
+    }
+
+    #[test]
+    fn poi_of_another_city_is_not_found() {
+        let mut poi_mock = MockPoiRepository::default();
+        poi_mock.expect_get_by_id()
+            .with(eq(5))
+            .return_once(|id| Ok(Some(hotel(id, 2))));
+
+        let service = new_poi_service(Arc::new(MockCityRepository::default()), Arc::new(poi_mock));
+
+        assert_eq!(None, service.get_by_id(1, 5).unwrap());
+    }
+
+}
@@ -1,38 +1,59 @@
 pub mod services {
-    use std::sync::Arc;
+    use std::sync::{Arc, RwLock};
 
+    use chrono_tz::Tz;
     use log::error;
 
     use crate::{
         model::{
             common::FromStringRecord,
+            Place,
             Route,
-            best_route::BestRoute, Airport, City,
+            RouteRecord,
+            Schedule,
+            best_route::RouteGraph, Airport, City, Itinerary, Objective, ParetoItinerary, ReachableCity, RouteConstraints, RoundTrip, Tour,
         },
         storage::{
             AirportRepository,
             CityRepository,
+            ScheduleRepository,
+            TransferRepository,
             routes::RouteRepository,
         },
         util::{
             Error,
-            ErrorCode::TextRowParse,
+            ErrorCode::{
+                CityHasNoAirports,
+                CountryHasNoAirports,
+                TagHasNoAirports,
+                TextRowParse,
+                UnsatisfiableRouteConstraints,
+            },
         },
     };
     use super::super::{
+        airport_service::services::resolve_airport,
         macros::log_if_error,
-        traits::RouteService,
+        traits::{
+            RouteSearchResult,
+            RouteService,
+        },
     };
 
     pub fn new_route_service(
         route_repo: Arc<dyn RouteRepository + Sync + Send>,
         airport_repo: Arc<dyn AirportRepository + Sync + Send>,
         city_repo: Arc<dyn CityRepository + Sync + Send>,
+        schedule_repo: Arc<dyn ScheduleRepository + Sync + Send>,
+        transfer_repo: Arc<dyn TransferRepository + Sync + Send>,
     ) -> Arc<impl RouteService> {
         Arc::new(RouteServiceImpl {
             route_repo: route_repo,
             airport_repo: airport_repo,
             city_repo: city_repo,
+            schedule_repo,
+            transfer_repo,
+            graph: RwLock::new(None),
         })
     }
 
@@ -40,6 +61,185 @@ pub mod services {
         route_repo: Arc<dyn RouteRepository + Sync + Send>,
         airport_repo: Arc<dyn AirportRepository + Sync + Send>,
         city_repo: Arc<dyn CityRepository + Sync + Send>,
+        schedule_repo: Arc<dyn ScheduleRepository + Sync + Send>,
+        transfer_repo: Arc<dyn TransferRepository + Sync + Send>,
+        /// Routes with their airports, transfers and schedules, built on first search
+        /// and dropped whenever any of them change
+        graph: RwLock<Option<Arc<RouteGraph>>>,
+    }
+
+    impl RouteServiceImpl {
+        fn load_route_graph(&self) -> Result<Arc<RouteGraph>, Error> {
+            if let Some(graph) = self.graph.read().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref() {
+                return Ok(graph.clone());
+            }
+            let routes = match self.route_repo.find_all() {
+                Ok(routes) => routes,
+                Err(err) => {
+                    error!("failed to load routes: {}", err);
+                    return Err(err.wrap_str("failed to load routes"));
+                },
+            };
+            let airports = match self.airport_repo.get_all() {
+                Ok(airports) => airports,
+                Err(err) => {
+                    error!("failed to load airports: {}", err);
+                    return Err(err.wrap_str("failed to load airports"));
+                },
+            };
+            let transfers = match self.transfer_repo.find_all() {
+                Ok(transfers) => transfers,
+                Err(err) => {
+                    error!("failed to load transfers: {}", err);
+                    return Err(err.wrap_str("failed to load transfers"));
+                },
+            };
+            let schedules = match self.schedule_repo.find_all() {
+                Ok(schedules) => schedules,
+                Err(err) => {
+                    error!("failed to load schedules: {}", err);
+                    return Err(err.wrap_str("failed to load schedules"));
+                },
+            };
+            let graph = Arc::new(RouteGraph::new(routes, airports)
+                .with_transfers(transfers)
+                .with_schedules(schedules));
+            *self.graph.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(graph.clone());
+            Ok(graph)
+        }
+
+        /// Checks the constraints and returns IDs of airports at starting and destination place.
+        fn endpoints(&self, start: &Place, finish: &Place, constraints: &RouteConstraints) -> Result<(Vec<i64>, Vec<i64>), Error> {
+            if let (Place::City(start), Place::City(finish)) = (start, finish) {
+                if let Some(reason) = constraints.contradiction(*start, *finish) {
+                    return Err(Error::bad_request_with_code(UnsatisfiableRouteConstraints, reason));
+                }
+            }
+            let sources = self.airport_ids_at(start, "starting")?;
+            let destinations = self.airport_ids_at(finish, "destination")?;
+            Ok((sources, destinations))
+        }
+
+        /// Returns IDs of all airports at the place, failing if there are none.
+        fn airport_ids_at(&self, place: &Place, role: &str) -> Result<Vec<i64>, Error> {
+            let code = match place {
+                Place::City(city_id) => return self.airport_ids_of(*city_id, role),
+                Place::Country(code) => code,
+                Place::Tagged(tag) => return self.airport_ids_tagged(tag, role),
+            };
+            let airports = match self.airport_repo.get_by_country(code.clone()) {
+                Ok(airports) => airports,
+                Err(err) => {
+                    error!("failed to load airports in {} country: {}", role, err);
+                    return Err(err.wrap(format!("failed to load airports in {} country", role)));
+                },
+            };
+            if airports.is_empty() {
+                return Err(Error::not_found_with_code(
+                    CountryHasNoAirports,
+                    format!("{} country {} has no airports", role, code),
+                ));
+            }
+            Ok(airports.iter().map(|a| a.id).collect())
+        }
+
+        /// Returns IDs of all airports in cities with the tag, failing if there are none.
+        fn airport_ids_tagged(&self, tag: &str, role: &str) -> Result<Vec<i64>, Error> {
+            let city_ids = self.tagged_city_ids(tag)?;
+            let airports = match city_ids.is_empty() {
+                true => vec![],
+                false => match self.airport_repo.get_by_city_ids(city_ids) {
+                    Ok(airports) => airports,
+                    Err(err) => {
+                        error!("failed to load airports in {} cities: {}", role, err);
+                        return Err(err.wrap(format!("failed to load airports in {} cities", role)));
+                    },
+                },
+            };
+            let airport_ids: Vec<i64> = airports.iter().map(|a| a.id).collect();
+            if airport_ids.is_empty() {
+                return Err(Error::not_found_with_code(
+                    TagHasNoAirports,
+                    format!("no {} city tagged {} has airports", role, tag),
+                ));
+            }
+            Ok(airport_ids)
+        }
+
+        fn tagged_city_ids(&self, tag: &str) -> Result<Vec<i64>, Error> {
+            match self.city_repo.find_tagged(vec![tag.to_string()]) {
+                Ok(tagged) => Ok(tagged.iter().map(|t| t.city_id).collect()),
+                Err(err) => {
+                    error!("failed to find cities tagged {}: {}", tag, err);
+                    Err(err.wrap(format!("failed to find cities tagged {}", tag)))
+                },
+            }
+        }
+
+        /// Returns IDs of all airports in the city, failing if there are none.
+        fn airport_ids_of(&self, city_id: i64, role: &str) -> Result<Vec<i64>, Error> {
+            let airports = match self.airport_repo.get_by_city_id(city_id) {
+                Ok(airports) => airports,
+                Err(err) => {
+                    error!("failed to load airports at {} city: {}", role, err);
+                    return Err(err.wrap(format!("failed to load airports at {} city", role)));
+                },
+            };
+            if airports.is_empty() {
+                return Err(Error::not_found_with_code(
+                    CityHasNoAirports,
+                    format!("{} city {} has no airports", role, city_id),
+                ));
+            }
+            Ok(airports.iter().map(|a| a.id).collect())
+        }
+
+        /// Tells apart a search that failed because of constraints from one with no route at all.
+        fn no_route_error(
+            graph: &RouteGraph,
+            sources: &[i64],
+            destinations: &[i64],
+            constraints: &RouteConstraints,
+            objective: &Objective,
+        ) -> Error {
+            if !constraints.is_empty()
+                && !graph.best_paths(sources, destinations, 1, &RouteConstraints::default(), objective).is_empty() {
+                    return Error::bad_request_with_code(
+                        UnsatisfiableRouteConstraints,
+                        "no route satisfies given constraints".to_string(),
+                    );
+                }
+            Error::not_found("no route found".to_string())
+        }
+
+        /// Collects airports and cities the itineraries pass through.
+        fn places_of<'a>(
+            &self,
+            graph: &RouteGraph,
+            itineraries: impl Iterator<Item = &'a Itinerary>,
+        ) -> Result<(Vec<Airport>, Vec<City>), Error> {
+            let mut airport_ids = itineraries
+                .flat_map(|i| i.routes.iter())
+                .flat_map(|r| vec![r.start, r.finish])
+                .collect::<Vec<i64>>();
+            airport_ids.sort();
+            airport_ids.dedup();
+            let airports: Vec<Airport> = airport_ids.iter()
+                .filter_map(|id| graph.airport(*id).cloned())
+                .collect();
+            let mut city_ids = airports.iter()
+                .map(|a| a.city_id)
+                .collect::<Vec<i64>>();
+            city_ids.sort();
+            city_ids.dedup();
+            let cities = match self.city_repo.get_by_ids(city_ids.clone()) {
+                Ok(cities) => cities,
+                Err(err) => return Err(err.wrap(format!(
+                    "failed to get cities for IDs ({:?})", city_ids
+                ))),
+            };
+            Ok((airports, cities))
+        }
     }
 
     impl RouteService for RouteServiceImpl {
@@ -52,11 +252,15 @@ pub mod services {
         }
 
         fn update(&self, route: Route) -> Result<(), Error> {
-            log_if_error!(self.route_repo.update(route))
+            log_if_error!(self.route_repo.update(route))?;
+            self.refresh_graph();
+            Ok(())
         }
 
         fn delete(&self, id: i64) -> Result<(), Error> {
-            log_if_error!(self.route_repo.delete(id))
+            log_if_error!(self.route_repo.delete(id))?;
+            self.refresh_graph();
+            Ok(())
         }
 
         fn save_routes(&self, sv_text: &[u8]) -> Result<(), Error> {
@@ -70,15 +274,31 @@ pub mod services {
                         return Err(Error::internal(TextRowParse, format!("only pocessed {}: malformed CSV: {}", count, err.to_string())));
                     },
                 };
-                let route = match Route::from_string_record(record) {
+                let record = match RouteRecord::from_string_record(record) {
                     Ok(r) => r,
                     Err(err) => {
                         error!("failed to parse row. only pocessed {}: malformed CSV: {}", count, err.to_string());
                         return Err(Error::internal(TextRowParse, format!("only pocessed {}: malformed CSV: {}", count, err.to_string())));
                     },
                 };
+                let mut airport_ids = vec![];
+                for reference in [&record.start, &record.finish] {
+                    match resolve_airport(self.airport_repo.as_ref(), reference) {
+                        Ok(id) => airport_ids.push(id),
+                        Err(err) => {
+                            error!("failed to resolve airport. only pocessed {}: {}", count, err);
+                            return Err(err.wrap(format!("only pocessed {}", count)));
+                        },
+                    };
+                }
+                let route = Route {
+                    id: 0,
+                    start: airport_ids[0],
+                    finish: airport_ids[1],
+                    price: record.price,
+                };
                 match self.route_repo.new(route) {
-                    Ok(_) => (),
+                    Ok(_) => self.refresh_graph(),
                     Err(err) => {
                         error!("failed to save route. only pocessed {}: malformed CSV: {}", count, err.to_string());
                         return Err(Error::internal(TextRowParse, format!("only pocessed {}: malformed CSV: {}", count, err.to_string())));
@@ -89,64 +309,204 @@ pub mod services {
             Ok(())
         }
 
-        fn find_cheapest_route(&self, start: i64, finish: i64) -> Result<(Vec<Route>, Vec<Airport>, Vec<City>), Error> {
-            let start_airports = match self.airport_repo.get_by_city_id(start) {
-                Ok(airports) => airports,
+        fn get_schedules(&self, route_id: i64) -> Result<Vec<Schedule>, Error> {
+            log_if_error!(self.schedule_repo.find_by_route(route_id))
+        }
+
+        fn add_schedule(&self, schedule: Schedule) -> Result<Schedule, Error> {
+            match self.route_repo.find_by_id(schedule.route_id) {
+                Ok(Some(_)) => (),
+                Ok(None) => return Err(Error::not_found("route not found".to_string())),
+                Err(err) => {
+                    error!("failed to load route: {}", err);
+                    return Err(err.wrap_str("failed to load route"));
+                },
+            };
+            let created = log_if_error!(self.schedule_repo.create(schedule))?;
+            self.refresh_graph();
+            Ok(created)
+        }
+
+        fn delete_schedule(&self, route_id: i64, id: i64) -> Result<(), Error> {
+            log_if_error!(self.schedule_repo.delete(route_id, id))?;
+            self.refresh_graph();
+            Ok(())
+        }
+
+        fn time_zones_of(&self, route_id: i64) -> Result<(Option<Tz>, Option<Tz>), Error> {
+            let route = match self.route_repo.find_by_id(route_id) {
+                Ok(Some(route)) => route,
+                Ok(None) => return Err(Error::not_found("route not found".to_string())),
                 Err(err) => {
-                    error!("failed to load airports at starting city: {}", err.to_string());
-                    return Err(err.wrap_str("failed to load airports at starting city"));
+                    error!("failed to load route: {}", err);
+                    return Err(err.wrap_str("failed to load route"));
                 },
             };
-            let finish_airports = match self.airport_repo.get_by_city_id(finish) {
+            let airports = match self.airport_repo.get_by_ids(vec![route.start, route.finish]) {
                 Ok(airports) => airports,
                 Err(err) => {
-                    error!("failed to load airports at destination city: {}", err.to_string());
-                    return Err(err.wrap_str("failed to load airports at destination city"));
+                    error!("failed to load airports of route {}: {}", route_id, err);
+                    return Err(err.wrap_str("failed to load airports"));
                 },
             };
-            let (mut best_route_finder, mut start) = BestRoute::new(
-                start_airports[0].id.clone(),
-                finish_airports.iter().map(|a| a.id.clone()).collect(),
-                self.route_repo.clone(),
-                self.airport_repo.clone(),
-            );
-            let route_ids = match best_route_finder.search_for_best_path(&mut start) {
-                Ok(opt) => match opt {
-                    Some(ids) => ids,
-                    None => return Err(Error::not_found("no route found".to_string())),
+            let city_ids: Vec<i64> = airports.iter().map(|a| a.city_id).collect();
+            let cities = match self.city_repo.get_by_ids(city_ids) {
+                Ok(cities) => cities,
+                Err(err) => {
+                    error!("failed to load cities of route {}: {}", route_id, err);
+                    return Err(err.wrap_str("failed to load cities"));
                 },
-                Err(err) => return Err(err.wrap_str("failed to calculate cheapest route")),
             };
-            let routes = match self.route_repo.find_by_ids(route_ids.clone()) {
-                Ok(routes) => routes,
-                Err(err) => return Err(err.wrap(
-                    format!("failed to get routes for IDs ({:?})", route_ids),
-                )),
+            let time_zone_of = |airport_id: i64| airports.iter()
+                .find(|a| a.id == airport_id)
+                .and_then(|a| cities.iter().find(|c| c.id == a.city_id))
+                .and_then(|c| c.time_zone);
+            Ok((time_zone_of(route.start), time_zone_of(route.finish)))
+        }
+
+        fn find_best_routes(
+            &self,
+            start: Place,
+            finish: Place,
+            count: usize,
+            constraints: RouteConstraints,
+            objective: Objective,
+        ) -> RouteSearchResult<Itinerary> {
+            let (sources, destinations) = self.endpoints(&start, &finish, &constraints)?;
+            let graph = match self.load_route_graph() {
+                Ok(graph) => graph,
+                Err(err) => return Err(err.wrap_str("failed to calculate best route")),
             };
-            let mut airport_ids = routes.iter()
-                .flat_map(|r| vec![r.start.clone(), r.finish.clone()])
-                .collect::<Vec<i64>>();
-            airport_ids.sort();
-            airport_ids.dedup();
-            let airports = match self.airport_repo.get_by_ids(airport_ids.clone()) {
-                Ok(airports) => airports,
-                Err(err) => return Err(err.wrap(format!(
-                    "failed to get airports for IDs ({:?})", airport_ids
-                ))),
+            let itineraries = graph.best_paths(&sources, &destinations, count, &constraints, &objective);
+            if itineraries.is_empty() {
+                return Err(Self::no_route_error(&graph, &sources, &destinations, &constraints, &objective));
+            }
+            let (airports, cities) = self.places_of(&graph, itineraries.iter())?;
+
+            Ok((itineraries, airports, cities))
+        }
+
+        fn find_pareto_routes(
+            &self,
+            start: Place,
+            finish: Place,
+            constraints: RouteConstraints,
+            objective: Objective,
+        ) -> RouteSearchResult<ParetoItinerary> {
+            let (sources, destinations) = self.endpoints(&start, &finish, &constraints)?;
+            let graph = match self.load_route_graph() {
+                Ok(graph) => graph,
+                Err(err) => return Err(err.wrap_str("failed to calculate pareto routes")),
             };
-            let mut city_ids = airports.iter()
-                .map(|a| a.city_id.clone())
-                .collect::<Vec<i64>>();
-            city_ids.sort();
-            city_ids.dedup();
-            let cities = match self.city_repo.get_by_ids(city_ids.clone()) {
+            let front = graph.pareto_paths(&sources, &destinations, &constraints, &objective);
+            if front.is_empty() {
+                return Err(Self::no_route_error(&graph, &sources, &destinations, &constraints, &objective));
+            }
+            let (airports, cities) = self.places_of(&graph, front.iter().map(|p| &p.itinerary))?;
+
+            Ok((front, airports, cities))
+        }
+
+        fn find_round_trips(
+            &self,
+            outbound: (i64, i64),
+            inbound: (i64, i64),
+            count: usize,
+            constraints: RouteConstraints,
+        ) -> RouteSearchResult<RoundTrip> {
+            let outbound_airports = self.endpoints(&Place::City(outbound.0), &Place::City(outbound.1), &constraints)
+                .map_err(|err| err.wrap_str("outbound"))?;
+            let inbound_airports = self.endpoints(&Place::City(inbound.0), &Place::City(inbound.1), &constraints)
+                .map_err(|err| err.wrap_str("return"))?;
+            let graph = match self.load_route_graph() {
+                Ok(graph) => graph,
+                Err(err) => return Err(err.wrap_str("failed to calculate round trips")),
+            };
+            let trips = graph.round_trips(
+                (&outbound_airports.0, &outbound_airports.1),
+                (&inbound_airports.0, &inbound_airports.1),
+                count,
+                &constraints,
+            );
+            if trips.is_empty() {
+                let single_constraints = RouteConstraints {
+                    max_price: None,
+                    ..constraints.clone()
+                };
+                for (name, (sources, destinations)) in [("outbound", &outbound_airports), ("return", &inbound_airports)] {
+                    if graph.best_paths(sources, destinations, 1, &single_constraints, &Objective::Cheapest).is_empty() {
+                        return Err(Self::no_route_error(&graph, sources, destinations, &single_constraints, &Objective::Cheapest)
+                            .wrap_str(name));
+                    }
+                }
+                return Err(Error::bad_request_with_code(
+                    UnsatisfiableRouteConstraints,
+                    "no round trip within maximum price".to_string(),
+                ));
+            }
+            let (airports, cities) = self.places_of(
+                &graph,
+                trips.iter().flat_map(|t| [&t.outbound, &t.inbound]),
+            )?;
+
+            Ok((trips, airports, cities))
+        }
+
+        fn plan_tour(
+            &self,
+            origin: i64,
+            city_ids: Vec<i64>,
+            return_to_origin: bool,
+        ) -> Result<(Tour, Vec<Airport>, Vec<City>), Error> {
+            let mut stops = vec![self.airport_ids_of(origin, "starting")?];
+            for city_id in city_ids.iter() {
+                stops.push(self.airport_ids_of(*city_id, "visited")?);
+            }
+            let graph = match self.load_route_graph() {
+                Ok(graph) => graph,
+                Err(err) => return Err(err.wrap_str("failed to plan tour")),
+            };
+            let tour = match graph.plan_tour(&stops, return_to_origin) {
+                Some(tour) => tour,
+                None => return Err(Error::not_found("no tour visits all cities".to_string())),
+            };
+            let (airports, cities) = self.places_of(&graph, tour.legs.iter())?;
+
+            Ok((tour, airports, cities))
+        }
+
+        fn find_reachable_cities(&self, start: i64, budget: i64, tag: Option<String>) -> Result<(Vec<ReachableCity>, Vec<City>), Error> {
+            let sources = self.airport_ids_of(start, "starting")?;
+            let tagged_city_ids = match &tag {
+                Some(tag) => Some(self.tagged_city_ids(tag)?),
+                None => None,
+            };
+            let graph = match self.load_route_graph() {
+                Ok(graph) => graph,
+                Err(err) => return Err(err.wrap_str("failed to find reachable cities")),
+            };
+            let mut reachable = graph.reachable_cities(&sources, budget);
+            if let Some(city_ids) = tagged_city_ids {
+                reachable.retain(|r| city_ids.contains(&r.city_id));
+            }
+            let city_ids: Vec<i64> = reachable.iter().map(|r| r.city_id).collect();
+            if city_ids.is_empty() {
+                return Ok((reachable, vec![]));
+            }
+            let cities = match self.city_repo.get_by_ids(city_ids) {
                 Ok(cities) => cities,
-                Err(err) => return Err(err.wrap(format!(
-                    "failed to get cities for IDs ({:?})", city_ids
-                ))),
+                Err(err) => {
+                    error!("failed to load reachable cities: {}", err);
+                    return Err(err.wrap_str("failed to load reachable cities"));
+                },
             };
 
-            Ok((routes, airports, cities))
+            Ok((reachable, cities))
+        }
+
+        /// Drops the cached graph, so that the next search loads it again.
+        fn refresh_graph(&self) {
+            *self.graph.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,319 @@
+#[cfg(test)]
+mod route_service_tests {
+
+    use std::sync::Arc;
+
+    use mockall::predicate::eq;
+
+    use crate::{
+        model::{
+            Airport,
+            City,
+            CityTag,
+            Objective,
+            Place,
+            Route,
+            RouteConstraints,
+        },
+        storage::{
+            MockAirportRepository,
+            MockCityRepository,
+            MockScheduleRepository,
+            MockTransferRepository,
+            routes::MockRouteRepository,
+        },
+        util::Error,
+    };
+    use super::super::{
+        route_service::services::new_route_service,
+        traits::RouteService,
+    };
+
+    fn airport(id: i64, city_id: i64) -> Airport {
+        Airport {
+            id,
+            city_id,
+            name: format!("airport {}", id),
+            location: None,
+            iata_code: None,
+            icao_code: None,
+        }
+    }
+
+    #[test]
+    fn cheapest_routes_start_city_without_airports() {
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_by_city_id()
+            .with(eq(1))
+            .times(1)
+            .return_once(|_| Ok(vec![]));
+
+        let service = new_route_service(
+            Arc::new(MockRouteRepository::default()),
+            Arc::new(airport_mock),
+            Arc::new(MockCityRepository::default()),
+            Arc::new(MockScheduleRepository::default()),
+            Arc::new(MockTransferRepository::default()),
+        );
+
+        let result = service.find_best_routes(Place::City(1), Place::City(2), 1, RouteConstraints::default(), Objective::Cheapest);
+
+        let err = result.err().unwrap();
+        assert!(matches!(err, Error::NotFound(_)));
+        assert!(serde_json::to_string(&err).unwrap().contains("CityHasNoAirports"));
+    }
+
+    #[test]
+    fn cheapest_routes_depart_from_any_starting_airport() {
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_by_city_id()
+            .with(eq(1))
+            .return_once(|_| Ok(vec![airport(1, 1), airport(2, 1)]));
+        airport_mock.expect_get_by_city_id()
+            .with(eq(2))
+            .return_once(|_| Ok(vec![airport(3, 2)]));
+        airport_mock.expect_get_all()
+            .return_once(|| Ok(vec![airport(1, 1), airport(2, 1), airport(3, 2)]));
+        let mut route_mock = MockRouteRepository::default();
+        route_mock.expect_find_all()
+            .return_once(|| Ok(vec![Route { id: 7, start: 2, finish: 3, price: 100 }]));
+        let mut transfer_mock = MockTransferRepository::default();
+        transfer_mock.expect_find_all()
+            .return_once(|| Ok(vec![]));
+        let mut schedule_mock = MockScheduleRepository::default();
+        schedule_mock.expect_find_all()
+            .return_once(|| Ok(vec![]));
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_ids()
+            .return_once(|_| Ok(vec![City::new(1, "one".to_string()), City::new(2, "two".to_string())]));
+
+        let service = new_route_service(
+            Arc::new(route_mock),
+            Arc::new(airport_mock),
+            Arc::new(city_mock),
+            Arc::new(schedule_mock),
+            Arc::new(transfer_mock),
+        );
+
+        let constraints = RouteConstraints {
+            excluded_airport_ids: vec![1],
+            ..RouteConstraints::default()
+        };
+        let (itineraries, airports, cities) = service.find_best_routes(Place::City(1), Place::City(2), 1, constraints, Objective::Cheapest).unwrap();
+
+        assert_eq!(1, itineraries.len());
+        assert_eq!(vec![7], itineraries[0].routes.iter().map(|r| r.id).collect::<Vec<i64>>());
+        assert_eq!(2, airports.len());
+        assert_eq!(2, cities.len());
+    }
+
+    #[test]
+    fn route_graph_is_loaded_again_after_route_changes() {
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_by_city_id()
+            .with(eq(1))
+            .returning(|_| Ok(vec![airport(1, 1)]));
+        airport_mock.expect_get_by_city_id()
+            .with(eq(2))
+            .returning(|_| Ok(vec![airport(2, 2)]));
+        airport_mock.expect_get_all()
+            .times(2)
+            .returning(|| Ok(vec![airport(1, 1), airport(2, 2)]));
+        let mut route_mock = MockRouteRepository::default();
+        route_mock.expect_find_all()
+            .times(2)
+            .returning(|| Ok(vec![Route { id: 7, start: 1, finish: 2, price: 100 }]));
+        route_mock.expect_update()
+            .times(1)
+            .returning(|_| Ok(()));
+        let mut transfer_mock = MockTransferRepository::default();
+        transfer_mock.expect_find_all()
+            .times(2)
+            .returning(|| Ok(vec![]));
+        let mut schedule_mock = MockScheduleRepository::default();
+        schedule_mock.expect_find_all()
+            .times(2)
+            .returning(|| Ok(vec![]));
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_ids()
+            .returning(|_| Ok(vec![City::new(1, "one".to_string()), City::new(2, "two".to_string())]));
+
+        let service = new_route_service(
+            Arc::new(route_mock),
+            Arc::new(airport_mock),
+            Arc::new(city_mock),
+            Arc::new(schedule_mock),
+            Arc::new(transfer_mock),
+        );
+
+        // the second search is served from the loaded graph, the one after the update loads it again
+        for _ in 0..2 {
+            service.find_best_routes(Place::City(1), Place::City(2), 1, RouteConstraints::default(), Objective::Cheapest).unwrap();
+        }
+        service.update(Route { id: 7, start: 1, finish: 2, price: 80 }).unwrap();
+        let (itineraries, _, _) = service.find_best_routes(Place::City(1), Place::City(2), 1, RouteConstraints::default(), Objective::Cheapest).unwrap();
+
+        assert_eq!(1, itineraries.len());
+    }
+
+    #[test]
+    fn route_upload_resolves_airport_codes() {
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_by_code()
+            .with(eq("BEG".to_string()))
+            .return_once(|_| Ok(Some(Airport { iata_code: Some("BEG".to_string()), ..airport(4, 1) })));
+        airport_mock.expect_get_by_code()
+            .with(eq("LHR".to_string()))
+            .return_once(|_| Ok(None));
+        let mut route_mock = MockRouteRepository::default();
+        route_mock.expect_new()
+            .with(eq(Route { id: 0, start: 4, finish: 9, price: 120 }))
+            .times(1)
+            .returning(Ok);
+
+        let service = new_route_service(
+            Arc::new(route_mock),
+            Arc::new(airport_mock),
+            Arc::new(MockCityRepository::default()),
+            Arc::new(MockScheduleRepository::default()),
+            Arc::new(MockTransferRepository::default()),
+        );
+
+        let result = service.save_routes(b"start,finish,price\nbeg,9,120\nLHR,4,80\n");
+
+        assert!(matches!(result.err().unwrap(), Error::NotFound(_)));
+    }
+
+    #[test]
+    fn cheapest_routes_to_any_airport_of_country() {
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_by_city_id()
+            .with(eq(1))
+            .return_once(|_| Ok(vec![airport(1, 1)]));
+        airport_mock.expect_get_by_country()
+            .with(eq("RS".to_string()))
+            .return_once(|_| Ok(vec![airport(2, 2), airport(3, 3)]));
+        airport_mock.expect_get_all()
+            .return_once(|| Ok(vec![airport(1, 1), airport(2, 2), airport(3, 3)]));
+        let mut route_mock = MockRouteRepository::default();
+        route_mock.expect_find_all()
+            .return_once(|| Ok(vec![
+                Route { id: 1, start: 1, finish: 2, price: 300 },
+                Route { id: 2, start: 1, finish: 3, price: 100 },
+            ]));
+        let mut transfer_mock = MockTransferRepository::default();
+        transfer_mock.expect_find_all()
+            .return_once(|| Ok(vec![]));
+        let mut schedule_mock = MockScheduleRepository::default();
+        schedule_mock.expect_find_all()
+            .return_once(|| Ok(vec![]));
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_ids()
+            .return_once(|_| Ok(vec![City::new(1, "one".to_string()), City::new(3, "three".to_string())]));
+
+        let service = new_route_service(
+            Arc::new(route_mock),
+            Arc::new(airport_mock),
+            Arc::new(city_mock),
+            Arc::new(schedule_mock),
+            Arc::new(transfer_mock),
+        );
+
+        let (itineraries, _, _) = service.find_best_routes(
+            Place::City(1),
+            Place::Country("RS".to_string()),
+            2,
+            RouteConstraints::default(),
+            Objective::Cheapest,
+        ).unwrap();
+
+        assert_eq!(
+            vec![vec![2], vec![1]],
+            itineraries.iter().map(|i| i.routes.iter().map(|r| r.id).collect()).collect::<Vec<Vec<i64>>>(),
+        );
+    }
+
+    #[test]
+    fn cheapest_routes_to_any_tagged_city() {
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_by_city_id()
+            .with(eq(1))
+            .return_once(|_| Ok(vec![airport(1, 1)]));
+        airport_mock.expect_get_by_city_ids()
+            .with(eq(vec![3]))
+            .times(1)
+            .return_once(|_| Ok(vec![airport(3, 3)]));
+        airport_mock.expect_get_all()
+            .returning(|| Ok(vec![airport(1, 1), airport(2, 2), airport(3, 3)]));
+        let mut route_mock = MockRouteRepository::default();
+        route_mock.expect_find_all()
+            .return_once(|| Ok(vec![
+                Route { id: 1, start: 1, finish: 2, price: 100 },
+                Route { id: 2, start: 1, finish: 3, price: 300 },
+            ]));
+        let mut transfer_mock = MockTransferRepository::default();
+        transfer_mock.expect_find_all()
+            .return_once(|| Ok(vec![]));
+        let mut schedule_mock = MockScheduleRepository::default();
+        schedule_mock.expect_find_all()
+            .return_once(|| Ok(vec![]));
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_find_tagged()
+            .with(eq(vec!["beach".to_string()]))
+            .return_once(|_| Ok(vec![CityTag { city_id: 3, tag: "beach".to_string() }]));
+        city_mock.expect_get_by_ids()
+            .return_once(|_| Ok(vec![City::new(1, "one".to_string()), City::new(3, "three".to_string())]));
+
+        let service = new_route_service(
+            Arc::new(route_mock),
+            Arc::new(airport_mock),
+            Arc::new(city_mock),
+            Arc::new(schedule_mock),
+            Arc::new(transfer_mock),
+        );
+
+        let (itineraries, _, _) = service.find_best_routes(
+            Place::City(1),
+            Place::Tagged("beach".to_string()),
+            2,
+            RouteConstraints::default(),
+            Objective::Cheapest,
+        ).unwrap();
+
+        // the cheaper city 2 is not a beach
+        assert_eq!(
+            vec![vec![2]],
+            itineraries.iter().map(|i| i.routes.iter().map(|r| r.id).collect()).collect::<Vec<Vec<i64>>>(),
+        );
+    }
+
+    #[test]
+    fn route_time_zones_come_from_airport_cities() {
+        let mut route_mock = MockRouteRepository::default();
+        route_mock.expect_find_by_id()
+            .with(eq(5))
+            .return_once(|_| Ok(Some(Route { id: 5, start: 1, finish: 2, price: 100 })));
+        let mut airport_mock = MockAirportRepository::default();
+        airport_mock.expect_get_by_ids()
+            .return_once(|_| Ok(vec![airport(1, 10), airport(2, 20)]));
+        let mut city_mock = MockCityRepository::default();
+        city_mock.expect_get_by_ids()
+            .return_once(|_| Ok(vec![
+                City { time_zone: Some(chrono_tz::Europe::Belgrade), ..City::new(10, "Belgrade".to_string()) },
+                City::new(20, "Nowhere".to_string()),
+            ]));
+
+        let service = new_route_service(
+            Arc::new(route_mock),
+            Arc::new(airport_mock),
+            Arc::new(city_mock),
+            Arc::new(MockScheduleRepository::default()),
+            Arc::new(MockTransferRepository::default()),
+        );
+
+        let time_zones = service.time_zones_of(5).unwrap();
+
+        assert_eq!((Some(chrono_tz::Europe::Belgrade), None), time_zones);
+    }
+
+}
@@ -0,0 +1,92 @@
+pub mod services {
+    use std::sync::{
+        Arc,
+        RwLock,
+    };
+
+    use log::error;
+
+    use crate::{
+        model::{
+            SearchIndex,
+            SearchResults,
+        },
+        services::traits::SearchService,
+        storage::{
+            AirportRepository,
+            CityRepository,
+        },
+        util::Error,
+    };
+
+    pub fn new_search_service(
+        city_repo: Arc<dyn CityRepository + Sync + Send>,
+        airport_repo: Arc<dyn AirportRepository + Sync + Send>,
+    ) -> Arc<impl SearchService> {
+        Arc::new(SearchServiceImpl {
+            city_repo,
+            airport_repo,
+            index: RwLock::new(None),
+        })
+    }
+
+    struct SearchServiceImpl {
+        city_repo: Arc<dyn CityRepository + Sync + Send>,
+        airport_repo: Arc<dyn AirportRepository + Sync + Send>,
+        /// Names and codes of cities and airports, built on first search and rebuilt whenever they change
+        index: RwLock<Option<Arc<SearchIndex>>>,
+    }
+
+    impl SearchServiceImpl {
+        fn build_index(&self) -> Result<SearchIndex, Error> {
+            let mut cities = match self.city_repo.get_all() {
+                Ok(cities) => cities,
+                Err(err) => {
+                    error!("failed to load cities for search index: {}", err);
+                    return Err(err.wrap_str("failed to load cities for search index"));
+                },
+            };
+            let aliases = match self.city_repo.get_all_aliases() {
+                Ok(aliases) => aliases,
+                Err(err) => {
+                    error!("failed to load city aliases for search index: {}", err);
+                    return Err(err.wrap_str("failed to load city aliases for search index"));
+                },
+            };
+            for alias in aliases {
+                if let Some(city) = cities.iter_mut().find(|c| c.id == alias.city_id) {
+                    city.aliases.push(alias);
+                }
+            }
+            let airports = match self.airport_repo.get_all() {
+                Ok(airports) => airports,
+                Err(err) => {
+                    error!("failed to load airports for search index: {}", err);
+                    return Err(err.wrap_str("failed to load airports for search index"));
+                },
+            };
+            Ok(SearchIndex::new(cities, airports))
+        }
+
+        fn loaded_index(&self) -> Result<Arc<SearchIndex>, Error> {
+            if let Some(index) = self.index.read().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref() {
+                return Ok(index.clone());
+            }
+            let index = Arc::new(self.build_index()?);
+            *self.index.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(index.clone());
+            Ok(index)
+        }
+    }
+
+    impl SearchService for SearchServiceImpl {
+        fn search(&self, query: &str, limit: usize) -> Result<SearchResults, Error> {
+            Ok(self.loaded_index()?.search(query, limit))
+        }
+
+        /// When loading fails the index is dropped, so that the next search tries again.
+        fn refresh_index(&self) {
+            let index = self.build_index().ok().map(Arc::new);
+            *self.index.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = index;
+        }
+    }
+}
@@ -1,12 +1,45 @@
+use std::collections::HashMap;
+
 use actix_web::http::header::ToStrError;
+use chrono_tz::Tz;
 
 use crate::{
     util::Error,
     model::{
         Airport,
+        AirportRef,
         City,
+        CityAlias,
+        CityDeletePolicy,
+        CityReferences,
         Comment,
+        CommentFlag,
+        CommentRevision,
+        CommentSort,
+        Coordinates,
+        Country,
+        FlaggedComment,
+        Itinerary,
+        NearbyAirport,
+        ModerationAction,
+        ModerationRecord,
+        Objective,
+        ParetoItinerary,
+        Place,
+        PoiCategory,
+        PointOfInterest,
+        ReachableCity,
+        Reaction,
+        ReactionCounts,
+        Region,
+        RoundTrip,
         Route,
+        RouteConstraints,
+        Schedule,
+        SearchResults,
+        TagMatch,
+        Tour,
+        Transfer,
         User,
     },
 };
@@ -16,8 +49,38 @@ use super::UserData;
 pub trait CityService {
     fn get_all(&self) -> Result<Vec<City>, Error>;
     fn get_full(&self, id: i64) -> Result<Option<City>, Error>;
+    /// Cities having all or any of the tags, depending on how they are to match
+    fn find_by_tags(&self, tags: Vec<String>, tag_match: TagMatch) -> Result<Vec<City>, Error>;
     fn new(&self, name: String) -> Result<City, Error>;
     fn save_cities(&self, sv_text: &[u8]) -> Result<(), Error>;
+    /// Moves the city to the country and optionally to one of its regions, or out of any country
+    fn set_country(&self, city_id: i64, country_code: Option<String>, region_id: Option<i64>) -> Result<(), Error>;
+    fn set_time_zone(&self, city_id: i64, time_zone: Option<Tz>) -> Result<(), Error>;
+    /// Time zones of those given cities that have one
+    fn time_zones(&self, city_ids: Vec<i64>) -> Result<HashMap<i64, Tz>, Error>;
+    /// Replaces name, country, region and time zone of the city
+    fn update(&self, city: City) -> Result<(), Error>;
+    /// Lists airports, routes, comments and points of interest referring to the city
+    fn references(&self, id: i64) -> Result<CityReferences, Error>;
+    /// Deletes the city, dealing with whatever refers to it according to the policy
+    fn delete(&self, id: i64, policy: CityDeletePolicy) -> Result<(), Error>;
+    fn get_aliases(&self, city_id: i64) -> Result<Vec<CityAlias>, Error>;
+    fn add_alias(&self, alias: CityAlias) -> Result<CityAlias, Error>;
+    fn delete_alias(&self, city_id: i64, alias_id: i64) -> Result<(), Error>;
+    /// Replaces names of the cities with their aliases in the first of the languages they have one in
+    fn localize(&self, cities: &mut [City], languages: &[String]) -> Result<(), Error>;
+    /// Every tag in use, sorted
+    fn all_tags(&self) -> Result<Vec<String>, Error>;
+    fn add_tag(&self, city_id: i64, tag: String) -> Result<(), Error>;
+    fn delete_tag(&self, city_id: i64, tag: String) -> Result<(), Error>;
+}
+
+pub trait CountryService {
+    fn get_all(&self) -> Result<Vec<Country>, Error>;
+    fn get_by_code(&self, code: &str) -> Result<Option<Country>, Error>;
+    fn create(&self, country: Country) -> Result<Country, Error>;
+    fn create_region(&self, region: Region) -> Result<Region, Error>;
+    fn get_cities(&self, code: &str, region_id: Option<i64>) -> Result<Vec<City>, Error>;
 }
 
 pub trait AirportService {
@@ -27,15 +90,65 @@ pub trait AirportService {
     fn update(&self, airport: Airport) -> Result<(), Error>;
     fn delete(&self, id: i64) -> Result<(), Error>;
     fn save_airports(&self, sv_text: &[u8]) -> Result<(), Error>;
+    fn get_transfers(&self, airport_id: i64) -> Result<Vec<Transfer>, Error>;
+    fn save_transfer(&self, transfer: Transfer) -> Result<Transfer, Error>;
+    fn delete_transfer(&self, from_airport_id: i64, to_airport_id: i64) -> Result<(), Error>;
+    fn get_by_code(&self, code: &str) -> Result<Option<Airport>, Error>;
+    /// Finds the ID of an airport given by ID or by IATA or ICAO code
+    fn resolve(&self, reference: &AirportRef) -> Result<i64, Error>;
+    fn find_nearby(&self, center: Coordinates, radius: f64, limit: usize) -> Result<Vec<NearbyAirport>, Error>;
+    /// Reloads the spatial and search indexes, needed after airports were changed by another service
+    fn refresh_index(&self);
+}
+
+#[cfg_attr(test, mockall::automock)]
+pub trait SearchService {
+    /// Finds cities and airports by name prefix, by name with typos and airports by code
+    fn search(&self, query: &str, limit: usize) -> Result<SearchResults, Error>;
+    /// Reloads the index, needed whenever names or codes of cities or airports change
+    fn refresh_index(&self);
+}
+
+/// Rules comment text has to follow before it is saved
+#[cfg_attr(test, mockall::automock)]
+pub trait ContentPolicy {
+    /// Fails with a bad request pointing at the offending parts of the text
+    fn check(&self, text: &str) -> Result<(), Error>;
 }
 
 pub trait CommentService {
     fn create(&self, user_id: i64, comment: Comment) -> Result<Comment, Error>;
     fn update(&self, user_id: i64, comment: Comment) -> Result<Comment, Error>;
     fn delete(&self, id: i64, user: User) -> Result<(), Error>;
-    fn list_for_city(&self, city_id: i64) -> Result<Vec<Comment>, Error>;
+    fn list_for_city(&self, city_id: i64, sort: CommentSort) -> Result<Vec<Comment>, Error>;
     fn list_for_user(&self, user_id: i64) -> Result<Vec<Comment>, Error>;
     fn get_by_id(&self, id: i64) -> Result<Option<Comment>, Error>;
+    /// Direct replies to the comment, oldest first
+    fn list_replies(&self, id: i64) -> Result<Vec<Comment>, Error>;
+    /// Earlier versions of the comment, oldest first. Only the poster and admins can see them.
+    fn history(&self, id: i64, user: User) -> Result<Vec<CommentRevision>, Error>;
+    /// Sets the reaction of the user to the comment and returns the new counts
+    fn react(&self, reaction: Reaction) -> Result<ReactionCounts, Error>;
+    fn remove_reaction(&self, comment_id: i64, user_id: i64) -> Result<ReactionCounts, Error>;
+}
+
+pub trait ModerationService {
+    /// Reports the comment to moderators
+    fn flag(&self, flag: CommentFlag) -> Result<CommentFlag, Error>;
+    /// Flagged comments waiting for a decision, the longest waiting first
+    fn queue(&self) -> Result<Vec<FlaggedComment>, Error>;
+    /// Approves, hides or deletes the comment, dismissing its flags and recording the decision
+    fn moderate(&self, comment_id: i64, action: ModerationAction, moderator: User) -> Result<(), Error>;
+    fn records(&self, comment_id: Option<i64>) -> Result<Vec<ModerationRecord>, Error>;
+}
+
+pub trait PoiService {
+    /// Points of interest of the city in any of the categories, or in all of them when none are given
+    fn list_for_city(&self, city_id: i64, categories: Vec<PoiCategory>) -> Result<Vec<PointOfInterest>, Error>;
+    fn get_by_id(&self, city_id: i64, id: i64) -> Result<Option<PointOfInterest>, Error>;
+    fn create(&self, poi: PointOfInterest) -> Result<PointOfInterest, Error>;
+    fn update(&self, poi: PointOfInterest) -> Result<(), Error>;
+    fn delete(&self, city_id: i64, id: i64) -> Result<(), Error>;
 }
 
 pub trait AuthService {
@@ -44,11 +157,50 @@ pub trait AuthService {
     fn get_user_if_has_role(&self, header: Option<Result<&str, ToStrError>>, roles: Vec<&str>) -> Result<Option<User>, Error>;
 }
 
+/// Found paths together with all airports and cities they pass through
+pub type RouteSearchResult<T> = Result<(Vec<T>, Vec<Airport>, Vec<City>), Error>;
+
 pub trait RouteService {
     fn get_all(&self, offset: i64, limit: i64) -> Result<Vec<Route>, Error>;
     fn find_by_id(&self, id: i64) -> Result<Option<Route>, Error>;
     fn save_routes(&self, sv_text: &[u8]) -> Result<(), Error>;
     fn update(&self, route: Route) -> Result<(), Error>;
     fn delete(&self, id: i64) -> Result<(), Error>;
-    fn find_cheapest_route(&self, start: i64, finish: i64) -> Result<(Vec<Route>, Vec<Airport>, Vec<City>), Error>;
-}
\ No newline at end of file
+    fn get_schedules(&self, route_id: i64) -> Result<Vec<Schedule>, Error>;
+    fn add_schedule(&self, schedule: Schedule) -> Result<Schedule, Error>;
+    fn delete_schedule(&self, route_id: i64, id: i64) -> Result<(), Error>;
+    /// Time zones of the cities the route departs from and arrives to
+    fn time_zones_of(&self, route_id: i64) -> Result<(Option<Tz>, Option<Tz>), Error>;
+    fn find_best_routes(
+        &self,
+        start: Place,
+        finish: Place,
+        count: usize,
+        constraints: RouteConstraints,
+        objective: Objective,
+    ) -> RouteSearchResult<Itinerary>;
+    fn find_pareto_routes(
+        &self,
+        start: Place,
+        finish: Place,
+        constraints: RouteConstraints,
+        objective: Objective,
+    ) -> RouteSearchResult<ParetoItinerary>;
+    fn find_round_trips(
+        &self,
+        outbound: (i64, i64),
+        inbound: (i64, i64),
+        count: usize,
+        constraints: RouteConstraints,
+    ) -> RouteSearchResult<RoundTrip>;
+    fn plan_tour(
+        &self,
+        origin: i64,
+        city_ids: Vec<i64>,
+        return_to_origin: bool,
+    ) -> Result<(Tour, Vec<Airport>, Vec<City>), Error>;
+    /// Cities reachable for at most the budget, optionally only those with the tag
+    fn find_reachable_cities(&self, start: i64, budget: i64, tag: Option<String>) -> Result<(Vec<ReachableCity>, Vec<City>), Error>;
+    /// Drops the cached route graph, needed after airports or transfers were changed by another service
+    fn refresh_graph(&self);
+}
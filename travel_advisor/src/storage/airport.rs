@@ -1,7 +1,10 @@
 pub mod airports {
     use std::sync::Arc;
 
-    use diesel::prelude::*;
+    use diesel::{
+        prelude::*,
+        result::DatabaseErrorKind,
+    };
 
     use crate::{
         model::Airport,
@@ -11,6 +14,7 @@ pub mod airports {
                 DbRead,
                 DbSave,
                 DbDelete,
+                DuplicateAirportCode,
             },
         },
         schema::airports as air_sch,
@@ -24,6 +28,7 @@ pub mod airports {
         },
     };
 
+    #[cfg_attr(test, mockall::automock)]
     pub trait AirportRepository {
         fn get_all(&self) -> Result<Vec<Airport>, Error>;
         fn get_by_id(&self, id: i64) -> Result<Option<Airport>, Error>;
@@ -32,7 +37,9 @@ pub mod airports {
         fn update(&self, airport: Airport) -> Result<(), Error>;
         fn delete(&self, id: i64) -> Result<(), Error>;
         fn get_by_city_id(&self, city_id: i64) -> Result<Vec<Airport>, Error>;
-        fn get_ids_by_city_ids(&self, city_ids: Vec<i64>) -> Result<Vec<i64>, Error>;
+        fn get_by_city_ids(&self, city_ids: Vec<i64>) -> Result<Vec<Airport>, Error>;
+        fn get_by_code(&self, code: String) -> Result<Option<Airport>, Error>;
+        fn get_by_country(&self, country_code: String) -> Result<Vec<Airport>, Error>;
     }
 
     struct AirportRepositoryImpl {
@@ -47,6 +54,15 @@ pub mod airports {
 
     diesel::sql_function! { fn last_insert_id() -> BigInt; }
 
+    /// Codes are unique, so saving a taken one is the client's mistake
+    fn save_error(err: diesel::result::Error) -> Error {
+        match err {
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, info) =>
+                Error::bad_request_with_code(DuplicateAirportCode, format!("airport code already taken: {}", info.message())),
+            err => Error::internal(DbSave, err.to_string()),
+        }
+    }
+
     impl AirportRepository for AirportRepositoryImpl {
 
         fn get_all(&self) -> Result<Vec<Airport>, Error> {
@@ -82,6 +98,10 @@ pub mod airports {
                 let entity = InsertAirportDB {
                     name: airport.name.clone(),
                     city_id: airport.city_id.clone(),
+                    latitude: airport.location.map(|l| l.latitude),
+                    longitude: airport.location.map(|l| l.longitude),
+                    iata_code: airport.iata_code.clone(),
+                    icao_code: airport.icao_code.clone(),
                 };
                 match diesel::insert_into(air_sch::dsl::airports)
                     .values(&entity)
@@ -101,8 +121,11 @@ pub mod airports {
                     id: res,
                     city_id: airport.city_id.clone(),
                     name: airport.name.clone(),
+                    location: airport.location,
+                    iata_code: airport.iata_code.clone(),
+                    icao_code: airport.icao_code.clone(),
                 }),
-                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                Err(err) => Err(save_error(err)),
             }
         }
 
@@ -113,12 +136,16 @@ pub mod airports {
                 .set((
                     air_sch::dsl::city_id.eq(airport.city_id.clone()),
                     air_sch::dsl::name.eq(airport.name.clone()),
+                    air_sch::dsl::latitude.eq(airport.location.map(|l| l.latitude)),
+                    air_sch::dsl::longitude.eq(airport.location.map(|l| l.longitude)),
+                    air_sch::dsl::iata_code.eq(airport.iata_code.clone()),
+                    air_sch::dsl::icao_code.eq(airport.icao_code.clone()),
                 )).execute(conn) {
                     Ok(rows) => match rows {
                         0 => Err(Error::not_found("airport not found".to_string())),
                         _ => Ok(()),
                     },
-                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                    Err(err) => Err(save_error(err)),
                 }
         }
 
@@ -144,22 +171,13 @@ pub mod airports {
                 }
         }
 
-        fn get_ids_by_city_ids(&self, route_ids: Vec<i64>) -> Result<Vec<i64>, Error> {
+        fn get_by_city_ids(&self, city_ids: Vec<i64>) -> Result<Vec<Airport>, Error> {
             let conn = &mut get_connection_v2!(self.db);
-            let city_ids: Vec<i64> = match air_sch::dsl::airports
-                .filter(air_sch::dsl::id.eq_any(route_ids))
-                .select(air_sch::columns::city_id)
-                .distinct()
-                .load(conn) {
-                    Ok(result) => result,
-                    Err(err) => return Err(Error::internal(DbRead, err.to_string())),
-                };
             match air_sch::dsl::airports
                 .filter(air_sch::dsl::city_id.eq_any(city_ids))
-                .select(air_sch::columns::id)
-                .distinct()
+                .select(AirportDB::as_select())
                 .load(conn) {
-                    Ok(result) => Ok(result),
+                    Ok(result) => Ok(result.iter().map(|a| a.to_model()).collect()),
                     Err(err) => Err(Error::internal(DbRead, err.to_string())),
                 }
         }
@@ -174,6 +192,30 @@ pub mod airports {
                     Err(err) => return Err(Error::internal(DbRead, err.to_string())),
                 }
         }
+
+        fn get_by_country(&self, country_code: String) -> Result<Vec<Airport>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match air_sch::dsl::airports
+                .inner_join(crate::schema::cities::table)
+                .filter(crate::schema::cities::dsl::country_code.eq(country_code))
+                .select(AirportDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|a| a.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn get_by_code(&self, code: String) -> Result<Option<Airport>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match air_sch::dsl::airports
+                .filter(air_sch::dsl::iata_code.eq(&code).or(air_sch::dsl::icao_code.eq(&code)))
+                .select(AirportDB::as_select())
+                .first(conn)
+                .optional() {
+                    Ok(result) => Ok(result.map(|a| a.to_model())),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
     }
 
 }
\ No newline at end of file
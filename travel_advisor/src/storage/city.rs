@@ -5,31 +5,73 @@ pub mod cities {
         insert_into,
         sql_function,
         prelude::*,
+        result::DatabaseErrorKind,
     };
 
     use crate::{
-        model::City,
-        schema::cities::dsl::*,
+        model::{
+            City,
+            CityAlias,
+            CityDeletePolicy,
+            CityTag,
+        },
+        schema::airports::dsl as air_dsl,
+        schema::cities::dsl as city_dsl,
+        schema::comments::dsl as comm_dsl,
+        schema::points_of_interest::dsl as poi_dsl,
+        schema::routes::dsl as route_dsl,
+        schema::city_aliases::dsl as alias_dsl,
+        schema::city_tags::dsl as tag_dsl,
         util::{
             Error,
             ErrorCode::{
                 DbRead,
                 DbSave,
+                DbDelete,
+                DuplicateCityAlias,
             },
         },
     };
     use super::super::{
         Database,
-        entities::CityDB,
+        entities::{
+            CityAliasDB,
+            CityDB,
+            CityTagDB,
+            InsertCityAliasDB,
+        },
         db_context::db_macros::get_connection_v2,
     };
 
+    #[cfg_attr(test, mockall::automock)]
     pub trait CityRepository {
         fn get_all(&self) -> Result<Vec<City>, Error>;
         fn get_by_ids(&self, ids: Vec<i64>) -> Result<Vec<City>, Error>;
         fn get_by_id(&self, id: i64) -> Result<Option<City>, Error>;
         fn new(&self, name: String) -> Result<City, Error>;
+        /// Finds the city by its name or, failing that, by any of its aliases
         fn get_by_name(&self, name: String) -> Result<Option<City>, Error>;
+        /// Lists cities of the country, only those of the region when one is given
+        fn get_by_country(&self, country_code: String, region_id: Option<i64>) -> Result<Vec<City>, Error>;
+        fn set_country(&self, city_id: i64, country_code: Option<String>, region_id: Option<i64>) -> Result<(), Error>;
+        fn set_time_zone(&self, city_id: i64, time_zone: Option<String>) -> Result<(), Error>;
+        /// Saves name, country, region and time zone of the city
+        fn update(&self, city: &City) -> Result<(), Error>;
+        /// Deletes the city in one transaction with whatever the policy does to its airports, routes,
+        /// comments and points of interest, so a failure leaves all of them as they were
+        fn delete_with(&self, id: i64, policy: CityDeletePolicy) -> Result<(), Error>;
+        fn get_aliases(&self, city_ids: Vec<i64>) -> Result<Vec<CityAlias>, Error>;
+        fn get_all_aliases(&self) -> Result<Vec<CityAlias>, Error>;
+        fn new_alias(&self, alias: &CityAlias) -> Result<CityAlias, Error>;
+        fn delete_alias(&self, city_id: i64, alias_id: i64) -> Result<(), Error>;
+        fn get_tags(&self, city_ids: Vec<i64>) -> Result<Vec<CityTag>, Error>;
+        /// Every tag on at least one city, sorted
+        fn get_all_tags(&self) -> Result<Vec<String>, Error>;
+        /// Tags out of the given ones together with the cities carrying them
+        fn find_tagged(&self, tags: Vec<String>) -> Result<Vec<CityTag>, Error>;
+        /// Tags the city, doing nothing if it already has the tag
+        fn add_tag(&self, tag: &CityTag) -> Result<(), Error>;
+        fn delete_tag(&self, tag: &CityTag) -> Result<(), Error>;
     }
 
     sql_function! { fn last_insert_id() -> BigInt; }
@@ -48,7 +90,7 @@ pub mod cities {
 
         fn get_all(&self) ->  Result<Vec<City> ,Error> {
             let conn = &mut get_connection_v2!(self.db);
-            match cities.select(CityDB::as_select()).load(conn) {
+            match city_dsl::cities.select(CityDB::as_select()).load(conn) {
                 Ok(result) => Ok(result.iter().map(|c| c.to_city()).collect()),
                 Err(err) => Err(Error::internal(DbRead, err.to_string())),
             }
@@ -56,7 +98,7 @@ pub mod cities {
 
         fn get_by_id(&self, city_id: i64) -> Result<Option<City>, Error> {
             let conn = &mut get_connection_v2!(self.db);
-            match cities
+            match city_dsl::cities
                 .find(city_id)
                 .select(CityDB::as_select())
                 .first(conn)
@@ -71,56 +113,282 @@ pub mod cities {
 
         fn new(&self, city_name: String) -> Result<City, Error> {
             let conn = &mut get_connection_v2!(self.db);
-            let result = insert_into(cities)
-                .values(name.eq(city_name.clone()))
+            let result = insert_into(city_dsl::cities)
+                .values(city_dsl::name.eq(city_name.clone()))
                 .execute(conn);
             match result {
                 Err(err) => return Err(Error::internal(DbSave, err.to_string())),
                 Ok(size) if size > 0 => (),
                 _ => return Err(Error::internal(DbSave, "nothing was inserted".to_string())),
             };
-            match cities.select(last_insert_id()).load::<i64>(conn) {
+            match city_dsl::cities.select(last_insert_id()).load::<i64>(conn) {
                 Err(err) => Err(Error::internal(DbRead, err.to_string())),
-                Ok(result) if result.len() > 0 => Ok(City {
-                    id: result[0],
-                    name: city_name,
-                    airports: vec![],
-                    comments: vec![],
-                }),
-                _ => Ok(City {
-                    id: -1,
-                    name: city_name,
-                    airports: vec![],
-                    comments: vec![],
-                }),
+                Ok(result) if result.len() > 0 => Ok(City::new(result[0], city_name)),
+                _ => Ok(City::new(-1, city_name)),
             }
         }
 
         fn get_by_name(&self, city_name: String) -> Result<Option<City>, Error> {
             let conn = &mut get_connection_v2!(self.db);
-            match cities
-                .filter(name.eq(city_name.clone()))
+            match city_dsl::cities
+                .filter(city_dsl::name.eq(city_name.clone()))
                 .select(CityDB::as_select())
                 .first(conn)
                 .optional() {
-                    Ok(result) => match result {
-                        Some(result) => Ok(Some(result.to_city())),
-                        None => Ok(None),
-                    },
+                    Ok(Some(result)) => return Ok(Some(result.to_city())),
+                    Ok(None) => (),
+                    Err(err) => return Err(Error::internal(DbRead, err.to_string())),
+                };
+            match alias_dsl::city_aliases
+                .inner_join(city_dsl::cities)
+                .filter(alias_dsl::name.eq(city_name))
+                .order(alias_dsl::id.asc())
+                .select(CityDB::as_select())
+                .first(conn)
+                .optional() {
+                    Ok(result) => Ok(result.map(|c| c.to_city())),
                     Err(err) => Err(Error::internal(DbRead, err.to_string())),
                 }
         }
 
         fn get_by_ids(&self, ids: Vec<i64>) -> Result<Vec<City>, Error> {
             let conn = &mut get_connection_v2!(self.db);
-            match cities
-                .filter(id.eq_any(ids))
+            match city_dsl::cities
+                .filter(city_dsl::id.eq_any(ids))
                 .select(CityDB::as_select())
                 .load(conn) {
                     Ok(result) => Ok(result.iter().map(|c| c.to_city()).collect()),
                     Err(err) => Err(Error::internal(DbRead, err.to_string())),
                 }
         }
+
+        fn get_by_country(&self, city_country_code: String, city_region_id: Option<i64>) -> Result<Vec<City>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let mut query = city_dsl::cities
+                .filter(city_dsl::country_code.eq(city_country_code))
+                .into_boxed();
+            if let Some(city_region_id) = city_region_id {
+                query = query.filter(city_dsl::region_id.eq(city_region_id));
+            }
+            match query
+                .order(city_dsl::name.asc())
+                .select(CityDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|c| c.to_city()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn set_country(&self, city_id: i64, city_country_code: Option<String>, city_region_id: Option<i64>) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match diesel::update(city_dsl::cities.find(city_id))
+                .set((
+                    city_dsl::country_code.eq(city_country_code),
+                    city_dsl::region_id.eq(city_region_id),
+                ))
+                .execute(conn) {
+                    Ok(0) => Err(Error::not_found("city not found".to_string())),
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                }
+        }
+
+        fn set_time_zone(&self, city_id: i64, city_time_zone: Option<String>) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match diesel::update(city_dsl::cities.find(city_id))
+                .set(city_dsl::time_zone.eq(city_time_zone))
+                .execute(conn) {
+                    Ok(0) => Err(Error::not_found("city not found".to_string())),
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                }
+        }
+
+        fn update(&self, city: &City) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match diesel::update(city_dsl::cities.find(city.id))
+                .set((
+                    city_dsl::name.eq(city.name.clone()),
+                    city_dsl::country_code.eq(city.country_code.clone()),
+                    city_dsl::region_id.eq(city.region_id),
+                    city_dsl::time_zone.eq(city.time_zone.map(|tz| tz.name().to_string())),
+                ))
+                .execute(conn) {
+                    Ok(0) => Err(Error::not_found("city not found".to_string())),
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                }
+        }
+
+        fn delete_with(&self, city_id: i64, policy: CityDeletePolicy) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let trx_result = conn.transaction::<(), diesel::result::Error, _>(|conn| {
+                match policy {
+                    CityDeletePolicy::Restrict => (),
+                    CityDeletePolicy::Cascade => {
+                        let airport_ids = air_dsl::airports
+                            .filter(air_dsl::city_id.eq(city_id))
+                            .select(air_dsl::id)
+                            .load::<i64>(conn)?;
+                        if !airport_ids.is_empty() {
+                            diesel::delete(route_dsl::routes
+                                .filter(route_dsl::start.eq_any(airport_ids.clone()).or(route_dsl::finish.eq_any(airport_ids))))
+                                .execute(conn)?;
+                        }
+                        diesel::delete(air_dsl::airports.filter(air_dsl::city_id.eq(city_id))).execute(conn)?;
+                        diesel::delete(comm_dsl::comments.filter(comm_dsl::city_id.eq(city_id))).execute(conn)?;
+                        // points of interest, aliases and tags go with the city through their foreign keys
+                    },
+                    CityDeletePolicy::Reassign(target_id) => {
+                        diesel::update(air_dsl::airports.filter(air_dsl::city_id.eq(city_id)))
+                            .set(air_dsl::city_id.eq(target_id))
+                            .execute(conn)?;
+                        diesel::update(comm_dsl::comments.filter(comm_dsl::city_id.eq(city_id)))
+                            .set(comm_dsl::city_id.eq(target_id))
+                            .execute(conn)?;
+                        diesel::update(poi_dsl::points_of_interest.filter(poi_dsl::city_id.eq(city_id)))
+                            .set(poi_dsl::city_id.eq(target_id))
+                            .execute(conn)?;
+                    },
+                };
+                match diesel::delete(city_dsl::cities.find(city_id)).execute(conn)? {
+                    0 => Err(diesel::result::Error::NotFound),
+                    _ => Ok(()),
+                }
+            });
+            match trx_result {
+                Ok(()) => Ok(()),
+                Err(diesel::result::Error::NotFound) => Err(Error::not_found("city not found".to_string())),
+                Err(err) => Err(Error::internal(DbDelete, err.to_string())),
+            }
+        }
+
+        fn get_aliases(&self, city_ids: Vec<i64>) -> Result<Vec<CityAlias>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match alias_dsl::city_aliases
+                .filter(alias_dsl::city_id.eq_any(city_ids))
+                .order(alias_dsl::id.asc())
+                .select(CityAliasDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|a| a.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn get_all_aliases(&self) -> Result<Vec<CityAlias>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match alias_dsl::city_aliases
+                .order(alias_dsl::id.asc())
+                .select(CityAliasDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|a| a.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn new_alias(&self, alias: &CityAlias) -> Result<CityAlias, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let trx_result = conn.transaction::<i64, diesel::result::Error, _>(|tx_conn| {
+                insert_into(alias_dsl::city_aliases)
+                    .values(&InsertCityAliasDB {
+                        city_id: alias.city_id,
+                        name: alias.name.clone(),
+                        language: alias.language.clone(),
+                    })
+                    .execute(tx_conn)?;
+                match alias_dsl::city_aliases
+                    .select(last_insert_id())
+                    .load::<i64>(tx_conn) {
+                        Err(err) => Err(err),
+                        Ok(ids) if !ids.is_empty() => Ok(ids[0]),
+                        _ => Ok(-1),
+                    }
+            });
+            match trx_result {
+                Ok(alias_id) => Ok(CityAlias {
+                    id: alias_id,
+                    ..alias.clone()
+                }),
+                Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) =>
+                    Err(Error::bad_request_with_code(DuplicateCityAlias, format!("city already has alias {} in {}", alias.name, alias.language))),
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+            }
+        }
+
+        fn delete_alias(&self, alias_city_id: i64, alias_id: i64) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match diesel::delete(
+                alias_dsl::city_aliases
+                    .filter(alias_dsl::id.eq(alias_id))
+                    .filter(alias_dsl::city_id.eq(alias_city_id))
+            ).execute(conn) {
+                Ok(0) => Err(Error::not_found("alias not found".to_string())),
+                Ok(_) => Ok(()),
+                Err(err) => Err(Error::internal(DbDelete, err.to_string())),
+            }
+        }
+
+        fn get_tags(&self, city_ids: Vec<i64>) -> Result<Vec<CityTag>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match tag_dsl::city_tags
+                .filter(tag_dsl::city_id.eq_any(city_ids))
+                .order((tag_dsl::city_id.asc(), tag_dsl::tag.asc()))
+                .select(CityTagDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|t| t.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn get_all_tags(&self) -> Result<Vec<String>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match tag_dsl::city_tags
+                .select(tag_dsl::tag)
+                .distinct()
+                .order(tag_dsl::tag.asc())
+                .load::<String>(conn) {
+                    Ok(result) => Ok(result),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn find_tagged(&self, tags: Vec<String>) -> Result<Vec<CityTag>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match tag_dsl::city_tags
+                .filter(tag_dsl::tag.eq_any(tags))
+                .order((tag_dsl::city_id.asc(), tag_dsl::tag.asc()))
+                .select(CityTagDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|t| t.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn add_tag(&self, city_tag: &CityTag) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match diesel::insert_or_ignore_into(tag_dsl::city_tags)
+                .values(&CityTagDB {
+                    city_id: city_tag.city_id,
+                    tag: city_tag.tag.clone(),
+                })
+                .execute(conn) {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                }
+        }
+
+        fn delete_tag(&self, city_tag: &CityTag) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match diesel::delete(
+                tag_dsl::city_tags
+                    .filter(tag_dsl::city_id.eq(city_tag.city_id))
+                    .filter(tag_dsl::tag.eq(&city_tag.tag))
+            ).execute(conn) {
+                Ok(0) => Err(Error::not_found(format!("city has no tag {}", city_tag.tag))),
+                Ok(_) => Ok(()),
+                Err(err) => Err(Error::internal(DbDelete, err.to_string())),
+            }
+        }
     }
 
 }
@@ -1,20 +1,39 @@
 pub mod comments {
     use std::{
+        collections::HashMap,
         time::SystemTime,
         sync::Arc,
     };
 
     use diesel::{
         prelude::*,
+        mysql::Mysql,
+        query_builder::QueryFragment,
+        query_dsl::methods::ExecuteDsl,
         sql_function,
+        dsl::{
+            count_star,
+            now,
+        },
         insert_into,
+        replace_into,
         update,
         delete,
     };
 
     use crate::{
-        model::Comment,
-        schema::comments::dsl as comm_dsl,
+        model::{
+            Comment,
+            CommentRevision,
+            Reaction,
+            ReactionCounts,
+            ReactionKind,
+        },
+        schema::{
+            comments::dsl as comm_dsl,
+            comment_reactions::dsl as react_dsl,
+            comment_revisions::dsl as rev_dsl,
+        },
         util::{
             Error,
             ErrorCode::{
@@ -26,19 +45,38 @@ pub mod comments {
         storage::{
             Database,
             db_context::db_macros::get_connection_v2,
-            entities::InsertCommentDB,
+            entities::{
+                InsertCommentDB,
+                InsertCommentReactionDB,
+            },
         },
     };
-    use super::super::entities::CommentDB;
+    use super::super::entities::{
+        CommentDB,
+        CommentRevisionDB,
+    };
 
+    #[cfg_attr(test, mockall::automock)]
     pub trait CommentRepository {
         fn create(&self, comment: Comment) -> Result<Comment, Error>;
         fn get_by_city(&self, city_id: i64) -> Result<Vec<Comment>, Error>;
         fn get_by_user(&self, user_id: i64) -> Result<Vec<Comment>, Error>;
+        /// Replaces the text, keeping the previous one as a revision
         fn update(&self, id: i64, text: String) -> Result<(), Error>;
         fn delete(&self, id: i64) -> Result<(), Error>;
-        fn delete_for_city(&self, city_id: i64) -> Result<(), Error>;
         fn get_by_id(&self, id: i64) -> Result<Option<Comment>, Error>;
+        /// Direct replies to the comment, oldest first
+        fn get_replies(&self, parent_id: i64) -> Result<Vec<Comment>, Error>;
+        /// Keeps the text as a revision, then clears it and flags the comment as deleted,
+        /// keeping its replies in place
+        fn mark_deleted(&self, id: i64) -> Result<(), Error>;
+        /// Earlier versions of the comment text, oldest first
+        fn get_revisions(&self, comment_id: i64) -> Result<Vec<CommentRevision>, Error>;
+        /// Saves the reaction, replacing an earlier one of the same user
+        fn set_reaction(&self, reaction: &Reaction) -> Result<(), Error>;
+        fn delete_reaction(&self, comment_id: i64, user_id: i64) -> Result<(), Error>;
+        /// Reaction counts per comment, comments without reactions are left out
+        fn count_reactions(&self, comment_ids: Vec<i64>) -> Result<HashMap<i64, ReactionCounts>, Error>;
     }
 
     struct CommentRepositoryImpl {
@@ -53,6 +91,27 @@ pub mod comments {
 
     sql_function! { fn last_insert_id() -> BigInt; }
 
+    /// Copies the current text of the comment into its revisions
+    pub(super) fn keep_revision(id: i64) -> impl ExecuteDsl<MysqlConnection> + QueryFragment<Mysql> {
+        insert_into(rev_dsl::comment_revisions)
+            .values(comm_dsl::comments
+                .filter(comm_dsl::id.eq(id))
+                .select((comm_dsl::id, comm_dsl::text, comm_dsl::updated_at)))
+            .into_columns((rev_dsl::comment_id, rev_dsl::text, rev_dsl::written_at))
+    }
+
+    /// Keeps the text of the comment as a revision and leaves an empty placeholder in its place
+    pub(crate) fn blank_comment(conn: &mut MysqlConnection, id: i64) -> QueryResult<usize> {
+        ExecuteDsl::execute(keep_revision(id), conn)?;
+        update(comm_dsl::comments.find(id))
+            .set((
+                comm_dsl::deleted.eq(true),
+                comm_dsl::text.eq(""),
+                comm_dsl::updated_at.eq(now),
+            ))
+            .execute(conn)
+    }
+
     impl CommentRepository for CommentRepositoryImpl {
 
         fn create(&self, comment: Comment) -> Result<Comment, Error> {
@@ -62,6 +121,7 @@ pub mod comments {
                     text: comment.content.clone(),
                     city_id: comment.city_id.clone(),
                     user_id: comment.user_id.clone(),
+                    parent_id: comment.parent_id,
                 };
                 match insert_into(comm_dsl::comments)
                     .values(&entity)
@@ -79,7 +139,11 @@ pub mod comments {
                     id: id,
                     city_id: comment.city_id.clone(),
                     user_id: comment.user_id.clone(),
+                    parent_id: comment.parent_id,
                     content: comment.content.clone(),
+                    deleted: false,
+                    hidden: false,
+                    reactions: ReactionCounts::default(),
                     created_at: SystemTime::now(),
                     updated_at: SystemTime::now(),
                 }),
@@ -91,6 +155,7 @@ pub mod comments {
             let conn = &mut get_connection_v2!(self.db);
             match comm_dsl::comments
                 .filter(comm_dsl::city_id.eq(city_id))
+                .order(comm_dsl::id.asc())
                 .select(CommentDB::as_select())
                 .load(conn) {
                     Ok(result) => Ok(result.iter().map(|c| c.to_model()).collect()),
@@ -111,14 +176,21 @@ pub mod comments {
     
         fn update(&self, id: i64, text: String) -> Result<(), Error> {
             let conn = &mut get_connection_v2!(self.db);
-            match update(comm_dsl::comments)
-                .filter(comm_dsl::id.eq(id))
-                .set(comm_dsl::text.eq(text))
-                .execute(conn) {
-                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
-                    Ok(result) if result > 0 => Ok(()),
-                    _ => Err(Error::not_found("comment not found".to_string())),
-                }
+            let trx_result = conn.transaction::<usize, diesel::result::Error, _>(|conn| {
+                ExecuteDsl::execute(keep_revision(id), conn)?;
+                update(comm_dsl::comments)
+                    .filter(comm_dsl::id.eq(id))
+                    .set((
+                        comm_dsl::text.eq(text),
+                        comm_dsl::updated_at.eq(now),
+                    ))
+                    .execute(conn)
+            });
+            match trx_result {
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                Ok(result) if result > 0 => Ok(()),
+                _ => Err(Error::not_found("comment not found".to_string())),
+            }
         }
     
         fn delete(&self, id: i64) -> Result<(), Error> {
@@ -132,16 +204,6 @@ pub mod comments {
                 }
         }
         
-        fn delete_for_city(&self, city_id: i64) -> Result<(), Error> {
-            let conn = &mut get_connection_v2!(self.db);
-            match delete(comm_dsl::comments)
-                .filter(comm_dsl::city_id.eq(city_id))
-                .execute(conn) {
-                    Err(err) => Err(Error::internal(DbDelete, err.to_string())),
-                    Ok(_result) => Ok(()),
-                }
-        }
-    
         fn get_by_id(&self, id: i64) -> Result<Option<Comment>, Error> {
             let conn = &mut get_connection_v2!(self.db);
             match comm_dsl::comments
@@ -156,7 +218,100 @@ pub mod comments {
                     Err(err) => Err(Error::internal(DbRead, err.to_string())),
                 }
         }
-                
+
+        fn get_replies(&self, parent_id: i64) -> Result<Vec<Comment>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match comm_dsl::comments
+                .filter(comm_dsl::parent_id.eq(parent_id))
+                .order(comm_dsl::id.asc())
+                .select(CommentDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|c| c.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn mark_deleted(&self, id: i64) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match conn.transaction::<usize, diesel::result::Error, _>(|conn| blank_comment(conn, id)) {
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                Ok(result) if result > 0 => Ok(()),
+                _ => Err(Error::not_found("comment not found".to_string())),
+            }
+        }
+
+        fn get_revisions(&self, comment_id: i64) -> Result<Vec<CommentRevision>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match rev_dsl::comment_revisions
+                .filter(rev_dsl::comment_id.eq(comment_id))
+                .order(rev_dsl::id.asc())
+                .select(CommentRevisionDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|r| r.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn set_reaction(&self, reaction: &Reaction) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match replace_into(react_dsl::comment_reactions)
+                .values(InsertCommentReactionDB::from_model(reaction))
+                .execute(conn) {
+                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                    Ok(_) => Ok(()),
+                }
+        }
+
+        fn delete_reaction(&self, comment_id: i64, user_id: i64) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match delete(react_dsl::comment_reactions)
+                .filter(react_dsl::comment_id.eq(comment_id))
+                .filter(react_dsl::user_id.eq(user_id))
+                .execute(conn) {
+                    Err(err) => Err(Error::internal(DbDelete, err.to_string())),
+                    Ok(result) if result > 0 => Ok(()),
+                    _ => Err(Error::not_found("reaction not found".to_string())),
+                }
+        }
+
+        fn count_reactions(&self, comment_ids: Vec<i64>) -> Result<HashMap<i64, ReactionCounts>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let rows = match react_dsl::comment_reactions
+                .filter(react_dsl::comment_id.eq_any(comment_ids))
+                .group_by((react_dsl::comment_id, react_dsl::kind))
+                .select((react_dsl::comment_id, react_dsl::kind, count_star()))
+                .load::<(i64, String, i64)>(conn) {
+                    Ok(rows) => rows,
+                    Err(err) => return Err(Error::internal(DbRead, err.to_string())),
+                };
+            let mut counts: HashMap<i64, ReactionCounts> = HashMap::new();
+            for (comment_id, kind, count) in rows {
+                if let Some(kind) = ReactionKind::parse(&kind) {
+                    counts.entry(comment_id).or_default().add(kind, count);
+                }
+            }
+            Ok(counts)
+        }
+
     }
 
 }
+
+#[cfg(test)]
+mod comment_test {
+    use diesel::{
+        debug_query,
+        mysql::Mysql,
+    };
+
+    use super::comments::keep_revision;
+
+    #[test]
+    fn deleted_text_is_kept_as_revision() {
+        let sql = debug_query::<Mysql, _>(&keep_revision(5)).to_string();
+
+        assert!(sql.starts_with("INSERT INTO `comment_revisions` (`comment_id`, `text`, `written_at`) \
+            SELECT `comments`.`id`, `comments`.`text`, `comments`.`updated_at` FROM `comments` WHERE"), "{}", sql);
+        assert!(sql.ends_with("-- binds: [5]"), "{}", sql);
+    }
+}
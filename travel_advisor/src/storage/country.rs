@@ -0,0 +1,131 @@
+pub mod countries {
+    use std::sync::Arc;
+
+    use diesel::{
+        sql_function,
+        prelude::*,
+    };
+
+    use crate::{
+        model::{
+            Country,
+            Region,
+        },
+        schema::{
+            countries::dsl as country_dsl,
+            regions::dsl as region_dsl,
+        },
+        storage::Database,
+        util::{
+            Error,
+            ErrorCode::{
+                DbRead,
+                DbSave,
+            },
+        },
+    };
+    use super::super::{
+        db_context::db_macros::get_connection_v2,
+        entities::{
+            CountryDB,
+            InsertRegionDB,
+            RegionDB,
+        },
+    };
+
+    sql_function! { fn last_insert_id() -> BigInt; }
+
+    #[cfg_attr(test, mockall::automock)]
+    pub trait CountryRepository {
+        fn get_all(&self) -> Result<Vec<Country>, Error>;
+        /// Loads the country together with its regions
+        fn get_by_code(&self, code: String) -> Result<Option<Country>, Error>;
+        fn new(&self, country: &Country) -> Result<Country, Error>;
+        fn new_region(&self, region: Region) -> Result<Region, Error>;
+    }
+
+    struct CountryRepositoryImpl {
+        db: Arc<Database>,
+    }
+
+    pub fn new_country_repository(db: Arc<Database>) -> Arc<impl CountryRepository> {
+        Arc::new(CountryRepositoryImpl {
+            db,
+        })
+    }
+
+    impl CountryRepository for CountryRepositoryImpl {
+        fn get_all(&self) -> Result<Vec<Country>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match country_dsl::countries
+                .order(country_dsl::name.asc())
+                .select(CountryDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|c| c.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn get_by_code(&self, code: String) -> Result<Option<Country>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let mut country = match country_dsl::countries
+                .find(&code)
+                .select(CountryDB::as_select())
+                .first(conn)
+                .optional() {
+                    Ok(Some(country)) => country.to_model(),
+                    Ok(None) => return Ok(None),
+                    Err(err) => return Err(Error::internal(DbRead, err.to_string())),
+                };
+            country.regions = match region_dsl::regions
+                .filter(region_dsl::country_code.eq(&code))
+                .order(region_dsl::name.asc())
+                .select(RegionDB::as_select())
+                .load(conn) {
+                    Ok(result) => result.iter().map(|r| r.to_model()).collect(),
+                    Err(err) => return Err(Error::internal(DbRead, err.to_string())),
+                };
+            Ok(Some(country))
+        }
+
+        fn new(&self, country: &Country) -> Result<Country, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let entity = CountryDB {
+                code: country.code.clone(),
+                name: country.name.clone(),
+            };
+            match diesel::insert_into(country_dsl::countries)
+                .values(&entity)
+                .execute(conn) {
+                    Ok(_) => Ok(entity.to_model()),
+                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                }
+        }
+
+        fn new_region(&self, region: Region) -> Result<Region, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let trx_result = conn.transaction::<i64, diesel::result::Error, _>(|tx_conn| {
+                diesel::insert_into(region_dsl::regions)
+                    .values(&InsertRegionDB {
+                        country_code: region.country_code.clone(),
+                        name: region.name.clone(),
+                    })
+                    .execute(tx_conn)?;
+                match region_dsl::regions
+                    .select(last_insert_id())
+                    .load::<i64>(tx_conn) {
+                        Err(err) => Err(err),
+                        Ok(ids) if !ids.is_empty() => Ok(ids[0]),
+                        _ => Ok(-1),
+                    }
+            });
+            match trx_result {
+                Ok(id) => Ok(Region {
+                    id,
+                    ..region
+                }),
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+            }
+        }
+    }
+}
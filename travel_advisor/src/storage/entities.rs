@@ -7,7 +7,10 @@ use std::{
     ops::Add,
 };
 
-use chrono::NaiveDateTime;
+use chrono::{
+    NaiveDateTime,
+    NaiveTime,
+};
 use diesel::{
     Queryable,
     Selectable,
@@ -19,8 +22,27 @@ use diesel::{
 use crate::model::{
     Airport,
     City,
+    CityAlias,
+    CityTag,
+    Coordinates,
     Comment,
+    CommentFlag,
+    CommentRevision,
+    Country,
+    ModerationAction,
+    ModerationRecord,
+    PoiCategory,
+    PointOfInterest,
+    Reaction,
+    ReactionCounts,
+    Region,
     Route,
+    Schedule,
+    Transfer,
+};
+use crate::util::{
+    Error,
+    ErrorCode::DbRead,
 };
 
 #[derive(Queryable, Selectable, Identifiable, Insertable, PartialEq)]
@@ -28,15 +50,105 @@ use crate::model::{
 pub struct CityDB {
     pub id: i64,
     pub name: String,
+    pub country_code: Option<String>,
+    pub region_id: Option<i64>,
+    pub time_zone: Option<String>,
 }
 
 impl CityDB {
     pub fn to_city(&self) -> City {
         City {
+            country_code: self.country_code.clone(),
+            region_id: self.region_id,
+            // names unknown to this build are treated as a missing zone rather than failing the load
+            time_zone: self.time_zone.as_ref().and_then(|tz| tz.parse().ok()),
+            ..City::new(self.id, self.name.clone())
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::countries)]
+pub struct CountryDB {
+    pub code: String,
+    pub name: String,
+}
+
+impl CountryDB {
+    pub fn to_model(&self) -> Country {
+        Country {
+            code: self.code.clone(),
+            name: self.name.clone(),
+            regions: vec![],
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Identifiable)]
+#[diesel(table_name = crate::schema::city_aliases)]
+pub struct CityAliasDB {
+    pub id: i64,
+    pub city_id: i64,
+    pub name: String,
+    pub language: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::city_aliases)]
+pub struct InsertCityAliasDB {
+    pub city_id: i64,
+    pub name: String,
+    pub language: String,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::city_tags)]
+pub struct CityTagDB {
+    pub city_id: i64,
+    pub tag: String,
+}
+
+impl CityTagDB {
+    pub fn to_model(&self) -> CityTag {
+        CityTag {
+            city_id: self.city_id,
+            tag: self.tag.clone(),
+        }
+    }
+}
+
+impl CityAliasDB {
+    pub fn to_model(&self) -> CityAlias {
+        CityAlias {
             id: self.id,
+            city_id: self.city_id,
+            name: self.name.clone(),
+            language: self.language.clone(),
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Identifiable)]
+#[diesel(table_name = crate::schema::regions)]
+pub struct RegionDB {
+    pub id: i64,
+    pub country_code: String,
+    pub name: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::regions)]
+pub struct InsertRegionDB {
+    pub country_code: String,
+    pub name: String,
+}
+
+impl RegionDB {
+    pub fn to_model(&self) -> Region {
+        Region {
+            id: self.id,
+            country_code: self.country_code.clone(),
             name: self.name.clone(),
-            airports: vec![],
-            comments: vec![],
         }
     }
 }
@@ -55,14 +167,22 @@ pub struct UserDB {
 pub struct AirportDB {
     pub id: i64,
     pub city_id: i64,
-    pub name: String
+    pub name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub iata_code: Option<String>,
+    pub icao_code: Option<String>,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = crate::schema::airports)]
 pub struct InsertAirportDB {
     pub city_id: i64,
-    pub name: String
+    pub name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub iata_code: Option<String>,
+    pub icao_code: Option<String>,
 }
 
 impl AirportDB {
@@ -71,10 +191,53 @@ impl AirportDB {
             id: self.id,
             name: self.name.clone(),
             city_id: self.city_id,
+            location: Coordinates::from_parts(self.latitude, self.longitude),
+            iata_code: self.iata_code.clone(),
+            icao_code: self.icao_code.clone(),
         }
     }
 }
 
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::points_of_interest)]
+pub struct PointOfInterestDB {
+    pub id: i64,
+    pub city_id: i64,
+    pub name: String,
+    pub category: String,
+    pub description: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::points_of_interest)]
+pub struct InsertPointOfInterestDB {
+    pub city_id: i64,
+    pub name: String,
+    pub category: String,
+    pub description: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+impl PointOfInterestDB {
+    pub fn to_model(&self) -> Result<PointOfInterest, Error> {
+        let category = match PoiCategory::parse(&self.category) {
+            Some(category) => category,
+            None => return Err(Error::internal(DbRead, format!("point of interest {} has unknown category {}", self.id, self.category))),
+        };
+        Ok(PointOfInterest {
+            id: self.id,
+            city_id: self.city_id,
+            name: self.name.clone(),
+            category,
+            description: self.description.clone(),
+            location: Coordinates::from_parts(self.latitude, self.longitude),
+        })
+    }
+}
+
 #[derive(Selectable, Queryable, Identifiable, AsChangeset, Clone)]
 #[diesel(table_name = crate::schema::comments)]
 pub struct CommentDB {
@@ -84,6 +247,9 @@ pub struct CommentDB {
     pub text: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub parent_id: Option<i64>,
+    pub deleted: bool,
+    pub hidden: bool,
 }
 
 pub fn naive_to_system(value: NaiveDateTime) -> SystemTime {
@@ -96,7 +262,11 @@ impl CommentDB {
             id: self.id.clone(),
             user_id: self.user_id.clone(),
             city_id: self.city_id.clone(),
+            parent_id: self.parent_id,
             content: self.text.clone(),
+            deleted: self.deleted,
+            hidden: self.hidden,
+            reactions: ReactionCounts::default(),
             created_at: naive_to_system(self.created_at.clone()),
             updated_at: naive_to_system(self.updated_at.clone()),
         }
@@ -108,7 +278,116 @@ impl CommentDB {
 pub struct InsertCommentDB {
     pub user_id: i64,
     pub city_id: i64,
+    pub parent_id: Option<i64>,
+    pub text: String,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::comment_revisions)]
+pub struct CommentRevisionDB {
+    pub id: i64,
+    pub comment_id: i64,
     pub text: String,
+    pub written_at: NaiveDateTime,
+}
+
+impl CommentRevisionDB {
+    pub fn to_model(&self) -> CommentRevision {
+        CommentRevision {
+            id: self.id,
+            comment_id: self.comment_id,
+            content: self.text.clone(),
+            written_at: naive_to_system(self.written_at),
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::comment_reactions)]
+pub struct InsertCommentReactionDB {
+    pub comment_id: i64,
+    pub user_id: i64,
+    pub kind: String,
+}
+
+impl InsertCommentReactionDB {
+    pub fn from_model(reaction: &Reaction) -> Self {
+        InsertCommentReactionDB {
+            comment_id: reaction.comment_id,
+            user_id: reaction.user_id,
+            kind: reaction.kind.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::comment_flags)]
+pub struct CommentFlagDB {
+    pub id: i64,
+    pub comment_id: i64,
+    pub user_id: i64,
+    pub reason: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl CommentFlagDB {
+    pub fn to_model(&self) -> CommentFlag {
+        CommentFlag {
+            id: self.id,
+            comment_id: self.comment_id,
+            user_id: self.user_id,
+            reason: self.reason.clone(),
+            created_at: naive_to_system(self.created_at),
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::comment_flags)]
+pub struct InsertCommentFlagDB {
+    pub comment_id: i64,
+    pub user_id: i64,
+    pub reason: String,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::moderation_records)]
+pub struct ModerationRecordDB {
+    pub id: i64,
+    pub comment_id: i64,
+    pub comment_user_id: i64,
+    pub comment_text: String,
+    pub moderator_id: i64,
+    pub action: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl ModerationRecordDB {
+    pub fn to_model(&self) -> Result<ModerationRecord, Error> {
+        let action = match ModerationAction::parse(&self.action) {
+            Some(action) => action,
+            None => return Err(Error::internal(DbRead, format!("moderation record {} has unknown action {}", self.id, self.action))),
+        };
+        Ok(ModerationRecord {
+            id: self.id,
+            comment_id: self.comment_id,
+            comment_user_id: self.comment_user_id,
+            comment_text: self.comment_text.clone(),
+            moderator_id: self.moderator_id,
+            action,
+            created_at: naive_to_system(self.created_at),
+        })
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::moderation_records)]
+pub struct InsertModerationRecordDB {
+    pub comment_id: i64,
+    pub comment_user_id: i64,
+    pub comment_text: String,
+    pub moderator_id: i64,
+    pub action: String,
 }
 
 #[derive(Queryable, Selectable)]
@@ -138,3 +417,65 @@ pub struct InsertRouteDB {
     pub finish: i64,
     pub price: i64,
 }
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::route_schedules)]
+pub struct ScheduleDB {
+    pub id: i64,
+    pub route_id: i64,
+    pub departure_time: NaiveTime,
+    pub duration_minutes: i32,
+    pub days_of_week: u8,
+}
+
+impl ScheduleDB {
+    pub fn to_model(&self) -> Schedule {
+        Schedule {
+            id: self.id,
+            route_id: self.route_id,
+            departure_time: self.departure_time,
+            duration_minutes: self.duration_minutes,
+            days_of_week: self.days_of_week,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::route_schedules)]
+pub struct InsertScheduleDB {
+    pub route_id: i64,
+    pub departure_time: NaiveTime,
+    pub duration_minutes: i32,
+    pub days_of_week: u8,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::airport_transfers)]
+pub struct TransferDB {
+    pub id: i64,
+    pub from_airport_id: i64,
+    pub to_airport_id: i64,
+    pub price: i64,
+    pub duration_minutes: i32,
+}
+
+impl TransferDB {
+    pub fn to_model(&self) -> Transfer {
+        Transfer {
+            id: self.id,
+            from_airport_id: self.from_airport_id,
+            to_airport_id: self.to_airport_id,
+            price: self.price,
+            duration_minutes: self.duration_minutes,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::airport_transfers)]
+pub struct InsertTransferDB {
+    pub from_airport_id: i64,
+    pub to_airport_id: i64,
+    pub price: i64,
+    pub duration_minutes: i32,
+}
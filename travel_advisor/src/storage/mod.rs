@@ -3,21 +3,57 @@ mod airport;
 mod city;
 mod user;
 mod route;
+mod schedule;
+mod transfer;
 mod comment;
+mod country;
+mod moderation;
+mod poi;
 mod entities;
 
 pub type Database = db_context::Database;
 
 pub use route::routes as routes;
 
+pub use schedule::schedules::new_schedule_repository as new_schedule_repository;
+pub use schedule::schedules::ScheduleRepository as ScheduleRepository;
+#[cfg(test)]
+pub use schedule::schedules::MockScheduleRepository as MockScheduleRepository;
+
+pub use transfer::transfers::new_transfer_repository as new_transfer_repository;
+pub use transfer::transfers::TransferRepository as TransferRepository;
+#[cfg(test)]
+pub use transfer::transfers::MockTransferRepository as MockTransferRepository;
+
 pub use city::cities::new_city_repository as new_city_repository;
 pub use city::cities::CityRepository as CityRepository;
+#[cfg(test)]
+pub use city::cities::MockCityRepository as MockCityRepository;
+
+pub use country::countries::new_country_repository as new_country_repository;
+pub use country::countries::CountryRepository as CountryRepository;
+#[cfg(test)]
+pub use country::countries::MockCountryRepository as MockCountryRepository;
 
 pub use airport::airports::new_airport_repository as new_airport_repository;
 pub use airport::airports::AirportRepository as AirportRepository;
+#[cfg(test)]
+pub use airport::airports::MockAirportRepository as MockAirportRepository;
 
 pub use comment::comments::new_comment_repository as new_comment_repository;
 pub use comment::comments::CommentRepository as CommentRepository;
+#[cfg(test)]
+pub use comment::comments::MockCommentRepository as MockCommentRepository;
+
+pub use moderation::moderations::new_moderation_repository as new_moderation_repository;
+pub use moderation::moderations::ModerationRepository as ModerationRepository;
+#[cfg(test)]
+pub use moderation::moderations::MockModerationRepository as MockModerationRepository;
+
+pub use poi::pois::new_poi_repository as new_poi_repository;
+pub use poi::pois::PoiRepository as PoiRepository;
+#[cfg(test)]
+pub use poi::pois::MockPoiRepository as MockPoiRepository;
 
 pub use user::users::new_user_repository as new_user_repository;
 pub use user::users::UserRepository as UserRepository;
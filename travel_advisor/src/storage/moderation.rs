@@ -0,0 +1,201 @@
+pub mod moderations {
+    use std::sync::Arc;
+
+    use diesel::{
+        prelude::*,
+        delete,
+        insert_into,
+        update,
+        result::DatabaseErrorKind,
+    };
+
+    use crate::{
+        model::{
+            CommentFlag,
+            ModerationAction,
+            ModerationRecord,
+        },
+        schema::{
+            comments::dsl as comm_dsl,
+            comment_flags::dsl as flag_dsl,
+            moderation_records::dsl as record_dsl,
+        },
+        util::{
+            Error,
+            ErrorCode::{
+                DbRead,
+                DbSave,
+                DuplicateCommentFlag,
+            },
+        },
+        storage::Database,
+    };
+    use super::super::{
+        comment::comments::blank_comment,
+        db_context::db_macros::get_connection_v2,
+        entities::{
+            CommentFlagDB,
+            InsertCommentFlagDB,
+            InsertModerationRecordDB,
+            ModerationRecordDB,
+        },
+    };
+
+    #[cfg_attr(test, mockall::automock)]
+    pub trait ModerationRepository {
+        fn add_flag(&self, flag: &CommentFlag) -> Result<CommentFlag, Error>;
+        /// Flags no moderator has acted on yet, oldest first
+        fn get_open_flags(&self) -> Result<Vec<CommentFlag>, Error>;
+        /// Resolves the flags of the comment, applies the action to it and saves the record,
+        /// all in one transaction
+        fn moderate(&self, record: &ModerationRecord) -> Result<(), Error>;
+        /// Records about the comment, or about every comment when none is given, newest first
+        fn get_records(&self, comment_id: Option<i64>) -> Result<Vec<ModerationRecord>, Error>;
+    }
+
+    struct ModerationRepositoryImpl {
+        db: Arc<Database>,
+    }
+
+    pub fn new_moderation_repository(db: Arc<Database>) -> Arc<impl ModerationRepository> {
+        Arc::new(ModerationRepositoryImpl {
+            db,
+        })
+    }
+
+    diesel::sql_function! { fn last_insert_id() -> BigInt; }
+
+    impl ModerationRepository for ModerationRepositoryImpl {
+
+        fn add_flag(&self, flag: &CommentFlag) -> Result<CommentFlag, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let trx_result = conn.transaction::<i64, diesel::result::Error, _>(|tx_conn| {
+                insert_into(flag_dsl::comment_flags)
+                    .values(&InsertCommentFlagDB {
+                        comment_id: flag.comment_id,
+                        user_id: flag.user_id,
+                        reason: flag.reason.clone(),
+                    })
+                    .execute(tx_conn)?;
+                match flag_dsl::comment_flags
+                    .select(last_insert_id())
+                    .load::<i64>(tx_conn) {
+                        Err(err) => Err(err),
+                        Ok(ids) if !ids.is_empty() => Ok(ids[0]),
+                        _ => Ok(-1),
+                    }
+            });
+            match trx_result {
+                Ok(id) => Ok(CommentFlag {
+                    id,
+                    ..flag.clone()
+                }),
+                Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) =>
+                    Err(Error::bad_request_with_code(DuplicateCommentFlag, "comment already flagged by the user".to_string())),
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+            }
+        }
+
+        fn get_open_flags(&self) -> Result<Vec<CommentFlag>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match flag_dsl::comment_flags
+                .filter(flag_dsl::resolved.eq(false))
+                .order(flag_dsl::id.asc())
+                .select(CommentFlagDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|f| f.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn moderate(&self, record: &ModerationRecord) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let trx_result = conn.transaction::<(), diesel::result::Error, _>(|conn| {
+                update(flag_dsl::comment_flags)
+                    .filter(flag_dsl::comment_id.eq(record.comment_id))
+                    .set(flag_dsl::resolved.eq(true))
+                    .execute(conn)?;
+                let changed = match record.action {
+                    ModerationAction::Approve => update(comm_dsl::comments.find(record.comment_id))
+                        .set(comm_dsl::hidden.eq(false))
+                        .execute(conn)?,
+                    ModerationAction::Hide => update(comm_dsl::comments.find(record.comment_id))
+                        .set(comm_dsl::hidden.eq(true))
+                        .execute(conn)?,
+                    ModerationAction::Delete => remove_comment(conn, record.comment_id)?,
+                };
+                if changed == 0 {
+                    return Err(diesel::result::Error::NotFound);
+                }
+                insert_into(record_dsl::moderation_records)
+                    .values(&InsertModerationRecordDB {
+                        comment_id: record.comment_id,
+                        comment_user_id: record.comment_user_id,
+                        comment_text: record.comment_text.clone(),
+                        moderator_id: record.moderator_id,
+                        action: record.action.as_str().to_string(),
+                    })
+                    .execute(conn)?;
+                Ok(())
+            });
+            match trx_result {
+                Ok(()) => Ok(()),
+                Err(diesel::result::Error::NotFound) => Err(Error::not_found("comment not found".to_string())),
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+            }
+        }
+
+        fn get_records(&self, comment_id: Option<i64>) -> Result<Vec<ModerationRecord>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let mut query = record_dsl::moderation_records.into_boxed();
+            if let Some(comment_id) = comment_id {
+                query = query.filter(record_dsl::comment_id.eq(comment_id));
+            }
+            match query
+                .order(record_dsl::id.desc())
+                .select(ModerationRecordDB::as_select())
+                .load(conn) {
+                    Ok(result) => result.iter().map(|r| r.to_model()).collect(),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+    }
+
+    fn has_replies(conn: &mut MysqlConnection, id: i64) -> QueryResult<bool> {
+        comm_dsl::comments
+            .filter(comm_dsl::parent_id.eq(id))
+            .count()
+            .get_result::<i64>(conn)
+            .map(|count| count > 0)
+    }
+
+    /// Deletes the comment the same way its poster would: with replies it stays as a placeholder,
+    /// otherwise it goes together with the placeholders above it that are left without replies
+    fn remove_comment(conn: &mut MysqlConnection, id: i64) -> QueryResult<usize> {
+        if has_replies(conn, id)? {
+            return blank_comment(conn, id);
+        }
+        let mut parent_id = comm_dsl::comments
+            .find(id)
+            .select(comm_dsl::parent_id)
+            .first::<Option<i64>>(conn)
+            .optional()?
+            .flatten();
+        let removed = delete(comm_dsl::comments.find(id)).execute(conn)?;
+        while let Some(id) = parent_id {
+            match comm_dsl::comments
+                .find(id)
+                .select((comm_dsl::deleted, comm_dsl::parent_id))
+                .first::<(bool, Option<i64>)>(conn)
+                .optional()? {
+                    Some((true, grandparent_id)) if !has_replies(conn, id)? => {
+                        delete(comm_dsl::comments.find(id)).execute(conn)?;
+                        parent_id = grandparent_id;
+                    },
+                    _ => break,
+                }
+        }
+        Ok(removed)
+    }
+
+}
@@ -0,0 +1,152 @@
+pub mod pois {
+    use std::sync::Arc;
+
+    use diesel::{
+        prelude::*,
+        insert_into,
+        update,
+        delete,
+    };
+
+    use crate::{
+        model::{
+            PoiCategory,
+            PointOfInterest,
+        },
+        schema::points_of_interest::dsl as poi_dsl,
+        util::{
+            Error,
+            ErrorCode::{
+                DbRead,
+                DbSave,
+                DbDelete,
+            },
+        },
+        storage::Database,
+    };
+    use super::super::{
+        db_context::db_macros::get_connection_v2,
+        entities::{
+            InsertPointOfInterestDB,
+            PointOfInterestDB,
+        },
+    };
+
+    #[cfg_attr(test, mockall::automock)]
+    pub trait PoiRepository {
+        /// Points of interest of the city in any of the categories, or in all of them when none are given
+        fn get_by_city(&self, city_id: i64, categories: Vec<PoiCategory>) -> Result<Vec<PointOfInterest>, Error>;
+        fn get_by_id(&self, id: i64) -> Result<Option<PointOfInterest>, Error>;
+        fn create(&self, poi: &PointOfInterest) -> Result<PointOfInterest, Error>;
+        fn update(&self, poi: &PointOfInterest) -> Result<(), Error>;
+        fn delete(&self, city_id: i64, id: i64) -> Result<(), Error>;
+    }
+
+    struct PoiRepositoryImpl {
+        db: Arc<Database>,
+    }
+
+    pub fn new_poi_repository(db: Arc<Database>) -> Arc<impl PoiRepository> {
+        Arc::new(PoiRepositoryImpl {
+            db,
+        })
+    }
+
+    diesel::sql_function! { fn last_insert_id() -> BigInt; }
+
+    impl PoiRepository for PoiRepositoryImpl {
+
+        fn get_by_city(&self, city_id: i64, categories: Vec<PoiCategory>) -> Result<Vec<PointOfInterest>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let mut query = poi_dsl::points_of_interest
+                .filter(poi_dsl::city_id.eq(city_id))
+                .into_boxed();
+            if !categories.is_empty() {
+                query = query.filter(poi_dsl::category.eq_any(categories.iter().map(|c| c.as_str())));
+            }
+            match query
+                .order(poi_dsl::name.asc())
+                .select(PointOfInterestDB::as_select())
+                .load(conn) {
+                    Ok(result) => result.iter().map(|p| p.to_model()).collect(),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn get_by_id(&self, id: i64) -> Result<Option<PointOfInterest>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match poi_dsl::points_of_interest
+                .find(id)
+                .select(PointOfInterestDB::as_select())
+                .first(conn)
+                .optional() {
+                    Ok(result) => result.map(|p| p.to_model()).transpose(),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn create(&self, poi: &PointOfInterest) -> Result<PointOfInterest, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let trx_result = conn.transaction::<i64, diesel::result::Error, _>(|tx_conn| {
+                insert_into(poi_dsl::points_of_interest)
+                    .values(&InsertPointOfInterestDB {
+                        city_id: poi.city_id,
+                        name: poi.name.clone(),
+                        category: poi.category.as_str().to_string(),
+                        description: poi.description.clone(),
+                        latitude: poi.location.map(|l| l.latitude),
+                        longitude: poi.location.map(|l| l.longitude),
+                    })
+                    .execute(tx_conn)?;
+                match poi_dsl::points_of_interest
+                    .select(last_insert_id())
+                    .load::<i64>(tx_conn) {
+                        Err(err) => Err(err),
+                        Ok(ids) if !ids.is_empty() => Ok(ids[0]),
+                        _ => Ok(-1),
+                    }
+            });
+            match trx_result {
+                Ok(id) => Ok(PointOfInterest {
+                    id,
+                    ..poi.clone()
+                }),
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+            }
+        }
+
+        fn update(&self, poi: &PointOfInterest) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match update(poi_dsl::points_of_interest)
+                .filter(poi_dsl::id.eq(poi.id))
+                .filter(poi_dsl::city_id.eq(poi.city_id))
+                .set((
+                    poi_dsl::name.eq(poi.name.clone()),
+                    poi_dsl::category.eq(poi.category.as_str()),
+                    poi_dsl::description.eq(poi.description.clone()),
+                    poi_dsl::latitude.eq(poi.location.map(|l| l.latitude)),
+                    poi_dsl::longitude.eq(poi.location.map(|l| l.longitude)),
+                ))
+                .execute(conn) {
+                    Ok(0) => Err(Error::not_found("point of interest not found".to_string())),
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(Error::internal(DbSave, err.to_string())),
+                }
+        }
+
+        fn delete(&self, poi_city_id: i64, id: i64) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match delete(
+                poi_dsl::points_of_interest
+                    .filter(poi_dsl::id.eq(id))
+                    .filter(poi_dsl::city_id.eq(poi_city_id))
+            ).execute(conn) {
+                Ok(0) => Err(Error::not_found("point of interest not found".to_string())),
+                Ok(_) => Ok(()),
+                Err(err) => Err(Error::internal(DbDelete, err.to_string())),
+            }
+        }
+
+    }
+
+}
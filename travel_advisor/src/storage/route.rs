@@ -28,14 +28,16 @@ pub mod routes {
 
     sql_function! { fn last_insert_id() -> BigInt; }
 
+    #[cfg_attr(test, mockall::automock)]
     pub trait RouteRepository {
         fn get_all(&self, offset: i64, limit: i64) -> Result<Vec<Route>, Error>;
+        fn find_all(&self) -> Result<Vec<Route>, Error>;
         fn find_by_id(&self, id: i64) -> Result<Option<Route>, Error>;
-        fn find_by_ids(&self, ids: Vec<i64>) -> Result<Vec<Route>, Error>;
         fn new(&self, route: Route) -> Result<Route, Error>;
         fn update(&self, route: Route) -> Result<(), Error>;
         fn delete(&self, id: i64) -> Result<(), Error>;
-        fn find_by_start(&self, start: Vec<i64>, exclude_finishes: Option<Vec<i64>>) -> Result<Vec<Route>, Error>;
+        /// Lists routes departing from or arriving to any of the airports
+        fn find_by_airports(&self, airport_ids: Vec<i64>) -> Result<Vec<Route>, Error>;
     }
 
     struct RouteRepositoryImpl {
@@ -61,6 +63,16 @@ pub mod routes {
                 }
         }
 
+        fn find_all(&self) -> Result<Vec<Route>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match route_dsl::routes
+                .select(RouteDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|r| r.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
         fn find_by_id(&self, id: i64) -> Result<Option<Route>, Error> {
             let conn = &mut get_connection_v2!(self.db);
             match route_dsl::routes
@@ -138,28 +150,16 @@ pub mod routes {
             }
         }
 
-        fn find_by_start(&self, start: Vec<i64>, exclude_finishes: Option<Vec<i64>>) -> Result<Vec<Route>, Error> {
-            let conn = &mut get_connection_v2!(self.db);
-            let mut builder = route_dsl::routes.into_boxed();
-            builder = builder.filter(route_dsl::start.eq_any(start.clone()));
-            if exclude_finishes.is_some() {
-                builder = builder.filter(route_dsl::finish.ne_all(exclude_finishes.unwrap()));
-            }
-            match builder.select(RouteDB::as_select()).load(conn) {
-                Ok(result) => Ok(result.iter().map(|r| r.to_model()).collect()),
-                Err(err) => Err(Error::internal(DbRead, err.to_string())),
-            }
-        }
-
-        fn find_by_ids(&self, ids: Vec<i64>) -> Result<Vec<Route>, Error> {
+        fn find_by_airports(&self, airport_ids: Vec<i64>) -> Result<Vec<Route>, Error> {
             let conn = &mut get_connection_v2!(self.db);
             match route_dsl::routes
-                .filter(route_dsl::id.eq_any(ids))
+                .filter(route_dsl::start.eq_any(airport_ids.clone()).or(route_dsl::finish.eq_any(airport_ids)))
                 .select(RouteDB::as_select())
                 .load(conn) {
                     Ok(result) => Ok(result.iter().map(|r| r.to_model()).collect()),
                     Err(err) => Err(Error::internal(DbRead, err.to_string())),
                 }
         }
+
     }
 }
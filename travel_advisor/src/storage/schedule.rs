@@ -0,0 +1,115 @@
+pub mod schedules {
+    use std::sync::Arc;
+
+    use diesel::{
+        sql_function,
+        prelude::*,
+    };
+
+    use crate::{
+        model::Schedule,
+        schema::route_schedules::dsl as schedule_dsl,
+        storage::Database,
+        util::{
+            Error,
+            ErrorCode::{
+                DbDelete,
+                DbRead,
+                DbSave,
+            },
+        },
+    };
+    use super::super::{
+        db_context::db_macros::get_connection_v2,
+        entities::{
+            ScheduleDB,
+            InsertScheduleDB,
+        },
+    };
+
+    sql_function! { fn last_insert_id() -> BigInt; }
+
+    #[cfg_attr(test, mockall::automock)]
+    pub trait ScheduleRepository {
+        fn find_all(&self) -> Result<Vec<Schedule>, Error>;
+        fn find_by_route(&self, route_id: i64) -> Result<Vec<Schedule>, Error>;
+        fn create(&self, schedule: Schedule) -> Result<Schedule, Error>;
+        fn delete(&self, route_id: i64, id: i64) -> Result<(), Error>;
+    }
+
+    struct ScheduleRepositoryImpl {
+        db: Arc<Database>,
+    }
+
+    pub fn new_schedule_repository(db: Arc<Database>) -> Arc<impl ScheduleRepository> {
+        Arc::new(ScheduleRepositoryImpl {
+            db,
+        })
+    }
+
+    impl ScheduleRepository for ScheduleRepositoryImpl {
+        fn find_all(&self) -> Result<Vec<Schedule>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match schedule_dsl::route_schedules
+                .select(ScheduleDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|s| s.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn find_by_route(&self, route_id: i64) -> Result<Vec<Schedule>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match schedule_dsl::route_schedules
+                .filter(schedule_dsl::route_id.eq(route_id))
+                .order(schedule_dsl::departure_time.asc())
+                .select(ScheduleDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|s| s.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn create(&self, schedule: Schedule) -> Result<Schedule, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let trx_result = conn.transaction::<i64, diesel::result::Error, _>(|tx_conn| {
+                let entity = InsertScheduleDB {
+                    route_id: schedule.route_id,
+                    departure_time: schedule.departure_time,
+                    duration_minutes: schedule.duration_minutes,
+                    days_of_week: schedule.days_of_week,
+                };
+                diesel::insert_into(schedule_dsl::route_schedules)
+                    .values(&entity)
+                    .execute(tx_conn)?;
+                match schedule_dsl::route_schedules
+                    .select(last_insert_id())
+                    .load::<i64>(tx_conn) {
+                        Err(err) => Err(err),
+                        Ok(ids) if !ids.is_empty() => Ok(ids[0]),
+                        _ => Ok(-1),
+                    }
+            });
+            match trx_result {
+                Ok(id) => Ok(Schedule {
+                    id,
+                    ..schedule
+                }),
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+            }
+        }
+
+        fn delete(&self, route_id: i64, id: i64) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match diesel::delete(
+                schedule_dsl::route_schedules
+                    .filter(schedule_dsl::id.eq(id))
+                    .filter(schedule_dsl::route_id.eq(route_id))
+            ).execute(conn) {
+                Err(err) => Err(Error::internal(DbDelete, err.to_string())),
+                Ok(result) if result > 0 => Ok(()),
+                _ => Err(Error::not_found("schedule not found".to_string())),
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+pub mod transfers {
+    use std::sync::Arc;
+
+    use diesel::{
+        sql_function,
+        prelude::*,
+    };
+
+    use crate::{
+        model::Transfer,
+        schema::airport_transfers::dsl as transfer_dsl,
+        storage::Database,
+        util::{
+            Error,
+            ErrorCode::{
+                DbDelete,
+                DbRead,
+                DbSave,
+            },
+        },
+    };
+    use super::super::{
+        db_context::db_macros::get_connection_v2,
+        entities::{
+            TransferDB,
+            InsertTransferDB,
+        },
+    };
+
+    sql_function! { fn last_insert_id() -> BigInt; }
+
+    #[cfg_attr(test, mockall::automock)]
+    pub trait TransferRepository {
+        fn find_all(&self) -> Result<Vec<Transfer>, Error>;
+        fn find_from(&self, airport_id: i64) -> Result<Vec<Transfer>, Error>;
+        /// Creates the transfer, or updates price and duration of an existing one between the same airports
+        fn save(&self, transfer: Transfer) -> Result<Transfer, Error>;
+        fn delete(&self, from_airport_id: i64, to_airport_id: i64) -> Result<(), Error>;
+    }
+
+    struct TransferRepositoryImpl {
+        db: Arc<Database>,
+    }
+
+    pub fn new_transfer_repository(db: Arc<Database>) -> Arc<impl TransferRepository> {
+        Arc::new(TransferRepositoryImpl {
+            db,
+        })
+    }
+
+    impl TransferRepository for TransferRepositoryImpl {
+        fn find_all(&self) -> Result<Vec<Transfer>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match transfer_dsl::airport_transfers
+                .select(TransferDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|t| t.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn find_from(&self, airport_id: i64) -> Result<Vec<Transfer>, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match transfer_dsl::airport_transfers
+                .filter(transfer_dsl::from_airport_id.eq(airport_id))
+                .select(TransferDB::as_select())
+                .load(conn) {
+                    Ok(result) => Ok(result.iter().map(|t| t.to_model()).collect()),
+                    Err(err) => Err(Error::internal(DbRead, err.to_string())),
+                }
+        }
+
+        fn save(&self, transfer: Transfer) -> Result<Transfer, Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            let trx_result = conn.transaction::<i64, diesel::result::Error, _>(|tx_conn| {
+                let existing = transfer_dsl::airport_transfers
+                    .filter(transfer_dsl::from_airport_id.eq(transfer.from_airport_id))
+                    .filter(transfer_dsl::to_airport_id.eq(transfer.to_airport_id))
+                    .select(transfer_dsl::id)
+                    .first::<i64>(tx_conn)
+                    .optional()?;
+                if let Some(id) = existing {
+                    diesel::update(transfer_dsl::airport_transfers.find(id))
+                        .set((
+                            transfer_dsl::price.eq(transfer.price),
+                            transfer_dsl::duration_minutes.eq(transfer.duration_minutes),
+                        ))
+                        .execute(tx_conn)?;
+                    return Ok(id);
+                }
+                let entity = InsertTransferDB {
+                    from_airport_id: transfer.from_airport_id,
+                    to_airport_id: transfer.to_airport_id,
+                    price: transfer.price,
+                    duration_minutes: transfer.duration_minutes,
+                };
+                diesel::insert_into(transfer_dsl::airport_transfers)
+                    .values(&entity)
+                    .execute(tx_conn)?;
+                match transfer_dsl::airport_transfers
+                    .select(last_insert_id())
+                    .load::<i64>(tx_conn) {
+                        Err(err) => Err(err),
+                        Ok(ids) if !ids.is_empty() => Ok(ids[0]),
+                        _ => Ok(-1),
+                    }
+            });
+            match trx_result {
+                Ok(id) => Ok(Transfer {
+                    id,
+                    ..transfer
+                }),
+                Err(err) => Err(Error::internal(DbSave, err.to_string())),
+            }
+        }
+
+        fn delete(&self, from_airport_id: i64, to_airport_id: i64) -> Result<(), Error> {
+            let conn = &mut get_connection_v2!(self.db);
+            match diesel::delete(
+                transfer_dsl::airport_transfers
+                    .filter(transfer_dsl::from_airport_id.eq(from_airport_id))
+                    .filter(transfer_dsl::to_airport_id.eq(to_airport_id))
+            ).execute(conn) {
+                Err(err) => Err(Error::internal(DbDelete, err.to_string())),
+                Ok(result) if result > 0 => Ok(()),
+                _ => Err(Error::not_found("transfer not found".to_string())),
+            }
+        }
+    }
+}
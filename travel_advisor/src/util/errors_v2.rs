@@ -24,7 +24,10 @@ pub enum ErrorV2 {
     BadRequest(ErrorV2Payload),
 
     #[display(fmt="unauthorized")]
-    Unauthorized(ErrorV2Payload)
+    Unauthorized(ErrorV2Payload),
+
+    #[display(fmt="conflict")]
+    Conflict(ErrorV2Payload),
 }
 
 #[derive(Serialize, Debug, Clone, Display, Error)]
@@ -32,12 +35,37 @@ pub enum ErrorV2 {
 pub struct ErrorV2Payload {
     code: ErrorCode,
     description: String,
+    /// Entities preventing the request from being fulfilled
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    references: Vec<String>,
+    /// Parts of the submitted text that caused the error
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    spans: Vec<TextSpan>,
+}
+
+impl ErrorV2Payload {
+    pub fn code(&self) -> &ErrorCode {
+        &self.code
+    }
+
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+}
+
+/// Character range of a text, `end` is exclusive
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
 }
 
 fn do_wrap(msg: String, p: ErrorV2Payload) -> ErrorV2Payload {
     ErrorV2Payload {
         code: p.code,
         description: format!("{}: {}", msg, p.description),
+        references: p.references,
+        spans: p.spans,
     }
 }
 
@@ -47,6 +75,8 @@ impl ErrorV2 {
         Self::Internal(ErrorV2Payload{
             code: code,
             description: msg,
+            references: vec![],
+            spans: vec![],
         })
     }
 
@@ -54,6 +84,8 @@ impl ErrorV2 {
         Self::Internal(ErrorV2Payload{
             code: code,
             description: msg.to_string(),
+            references: vec![],
+            spans: vec![],
         })
     }
 
@@ -61,6 +93,17 @@ impl ErrorV2 {
         Self::NotFound(ErrorV2Payload{
             code: ErrorCode::EntityNotFound,
             description: msg,
+            references: vec![],
+            spans: vec![],
+        })
+    }
+
+    pub fn not_found_with_code(code: ErrorCode, msg: String) -> Self {
+        Self::NotFound(ErrorV2Payload{
+            code,
+            description: msg,
+            references: vec![],
+            spans: vec![],
         })
     }
 
@@ -68,6 +111,8 @@ impl ErrorV2 {
         Self::Forbidden(ErrorV2Payload {
             code: ErrorCode::ForbiddenResource,
             description: msg,
+            references: vec![],
+            spans: vec![],
         })
     }
 
@@ -79,6 +124,8 @@ impl ErrorV2 {
         Self::Unauthorized(ErrorV2Payload {
             code: ErrorCode::Unauthorized,
             description: msg,
+            references: vec![],
+            spans: vec![],
         })
     }
 
@@ -86,6 +133,8 @@ impl ErrorV2 {
         Self::Unauthorized(ErrorV2Payload {
             code: ErrorCode::Unauthorized,
             description: msg.to_string(),
+            references: vec![],
+            spans: vec![],
         })
     }
 
@@ -93,6 +142,35 @@ impl ErrorV2 {
         Self::BadRequest(ErrorV2Payload {
             code: ErrorCode::ValidationError,
             description: msg,
+            references: vec![],
+            spans: vec![],
+        })
+    }
+
+    pub fn bad_request_with_code(code: ErrorCode, msg: String) -> Self {
+        Self::BadRequest(ErrorV2Payload {
+            code,
+            description: msg,
+            references: vec![],
+            spans: vec![],
+        })
+    }
+
+    pub fn bad_request_with_spans(code: ErrorCode, msg: String, spans: Vec<TextSpan>) -> Self {
+        Self::BadRequest(ErrorV2Payload {
+            code,
+            description: msg,
+            references: vec![],
+            spans,
+        })
+    }
+
+    pub fn conflict(code: ErrorCode, msg: String, references: Vec<String>) -> Self {
+        Self::Conflict(ErrorV2Payload {
+            code,
+            description: msg,
+            references,
+            spans: vec![],
         })
     }
 
@@ -103,6 +181,7 @@ impl ErrorV2 {
             Self::Forbidden(p) => Self::Forbidden(do_wrap(msg, p.clone())),
             Self::BadRequest(p) => Self::BadRequest(do_wrap(msg, p.clone())),
             Self::Unauthorized(p) => Self::Unauthorized(do_wrap(msg, p.clone())),
+            Self::Conflict(p) => Self::Conflict(do_wrap(msg, p.clone())),
         }
     }
 
@@ -113,6 +192,7 @@ impl ErrorV2 {
             Self::Forbidden(p) => Self::Forbidden(do_wrap(msg.to_string(), p.clone())),
             Self::BadRequest(p) => Self::BadRequest(do_wrap(msg.to_string(), p.clone())),
             Self::Unauthorized(p) => Self::Unauthorized(do_wrap(msg.to_string(), p.clone())),
+            Self::Conflict(p) => Self::Conflict(do_wrap(msg.to_string(), p.clone())),
         }
     }
 
@@ -127,6 +207,7 @@ impl ResponseError for ErrorV2 {
             Self::Forbidden(p) => (HttpResponse::Forbidden(), p),
             Self::BadRequest(p) => (HttpResponse::BadRequest(), p),
             Self::Unauthorized(p) => (HttpResponse::Unauthorized(), p),
+            Self::Conflict(p) => (HttpResponse::Conflict(), p),
         };
         let payload_str = match serde_json::to_string(&payload) {
             Ok(p) => p,
@@ -145,6 +226,7 @@ impl ResponseError for ErrorV2 {
             Self::Forbidden(_) => StatusCode::FORBIDDEN,
             Self::BadRequest(_) => StatusCode::BAD_REQUEST,
             Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Conflict(_) => StatusCode::CONFLICT,
         }
     }
 
@@ -200,4 +282,40 @@ pub enum ErrorCode {
 
     #[display(fmt="SERIALIZATION_ERROR")]
     SerializationError,
+
+    #[display(fmt="UNSATISFIABLE_ROUTE_CONSTRAINTS")]
+    UnsatisfiableRouteConstraints,
+
+    #[display(fmt="CITY_HAS_NO_AIRPORTS")]
+    CityHasNoAirports,
+
+    #[display(fmt="COUNTRY_HAS_NO_AIRPORTS")]
+    CountryHasNoAirports,
+
+    #[display(fmt="TAG_HAS_NO_AIRPORTS")]
+    TagHasNoAirports,
+
+    #[display(fmt="DUPLICATE_AIRPORT_CODE")]
+    DuplicateAirportCode,
+
+    #[display(fmt="CITY_IN_USE")]
+    CityInUse,
+
+    #[display(fmt="DUPLICATE_CITY_ALIAS")]
+    DuplicateCityAlias,
+
+    #[display(fmt="DUPLICATE_COMMENT_FLAG")]
+    DuplicateCommentFlag,
+
+    #[display(fmt="TEXT_TOO_LONG")]
+    TextTooLong,
+
+    #[display(fmt="BANNED_WORD")]
+    BannedWord,
+
+    #[display(fmt="TOO_MANY_LINKS")]
+    TooManyLinks,
+
+    #[display(fmt="REPEATED_CHARACTERS")]
+    RepeatedCharacters,
 }
@@ -5,6 +5,7 @@ pub use errors::errors_mod as app_errors;
 
 pub use errors_v2::ErrorV2 as Error;
 pub use errors_v2::ErrorCode as ErrorCode;
+pub use errors_v2::TextSpan as TextSpan;
 
 mod errors_test;
 